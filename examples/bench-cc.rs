@@ -0,0 +1,287 @@
+#[macro_use]
+extern crate explanation;
+
+extern crate rand;
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::cell::RefCell;
+use std::time::Instant;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use timely::dataflow::*;
+use timely::dataflow::scopes::Child;
+use timely::dataflow::operators::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+
+use explanation::{Variable, MonotonicVariable, Round};
+use explanation::radix;
+use explanation::delay::Delay;
+use explanation::bench::{Measurement, Counting, random_graph, overhead};
+use explanation::profile::Profiler;
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+// usage: bench-cc nodes edges rounds [profile] [timely arguments]
+//
+// Loads a random graph into connected components, then performs `rounds` rounds each removing one distinct edge.
+// The computation is run once without instrumentation and once with, where the instrumented run also asks for
+// the explanation of one random node's label in each round (nodes are labeled by their own identifiers, so
+// the giant component, which node zero almost surely belongs to, has label zero).
+//
+// Resident memory is reported as its growth during each run, so that memory the uninstrumented run kept is not
+// counted against the instrumented one. Memory the uninstrumented run freed may be reused by the instrumented
+// run without growing the process, so the instrumented figure is a lower bound.
+//
+// Allocations are counted by the allocator, across all workers, and reported for each run; instrumentation that
+// copies records where it could move them shows up here before it shows up in the timings.
+//
+// With `profile`, the instrumented run also reports the time its join and min operators spend on each path.
+fn main() {
+
+    let nodes: u32 = std::env::args().nth(1).and_then(|x| x.parse().ok()).unwrap_or(1000);
+    let edges: usize = std::env::args().nth(2).and_then(|x| x.parse().ok()).unwrap_or(2000);
+    let rounds: usize = std::env::args().nth(3).and_then(|x| x.parse().ok()).unwrap_or(10);
+    // each round removes an edge not removed before.
+    let rounds = std::cmp::min(rounds, edges);
+    let profile = std::env::args().nth(4) == Some("profile".to_owned());
+
+    timely::execute_from_args(std::env::args(), move |root| {
+
+        let index = root.index();
+        let peers = root.peers();
+
+        let graph_data = random_graph(nodes, edges, 0);
+        let seed: &[_] = &[1, 2, 3, 4];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+        let mut order = (0 .. graph_data.len()).collect::<Vec<_>>();
+        rng.shuffle(&mut order[..]);
+        let removals = order.into_iter().take(rounds).map(|i| graph_data[i]).collect::<Vec<_>>();
+        let queries = (0 .. rounds).map(|_| rng.gen_range(0, nodes)).collect::<Vec<_>>();
+
+        // BEGIN UNINSTRUMENTED
+        let mut baseline = Measurement::new("uninstrumented");
+        {
+            let (mut graph, mut label, probe) = root.scoped::<u32, _, _>(move |streaming| {
+
+                let (graph_handle, graph) = streaming.new_input(); let graph = Collection::new(graph);
+                let (label_handle, label) = streaming.new_input(); let label = Collection::new(label);
+
+                let edges = graph.map(|(x,y)| (y,x)).concat(&graph);
+                let labels = label.iterate(|inner| {
+                    let edges = edges.enter(&inner.scope());
+                    let label = label.enter(&inner.scope());
+                    inner.join_u(&edges)
+                         .map(|(_,l,d)| (d,l))
+                         .concat(&label)
+                         .group_u(|_, s, t| t.push((*s.next().unwrap().0, 1)))
+                });
+
+                (graph_handle, label_handle, labels.probe().0)
+            });
+
+            let timer = Instant::now();
+            for (i, &(src, dst)) in graph_data.iter().enumerate() {
+                if i % peers == index {
+                    graph.send(((src, dst), 1));
+                }
+            }
+            for node in 0 .. nodes {
+                if node as usize % peers == index {
+                    label.send(((node, node), 1));
+                }
+            }
+            graph.advance_to(1);
+            label.advance_to(1);
+            root.step_while(|| probe.lt(&graph.time()));
+            baseline.loaded(edges, timer);
+
+            for round in 0 .. rounds {
+                let timer = Instant::now();
+                if index == 0 { graph.send((removals[round], -1)); }
+                graph.advance_to(round as u32 + 2);
+                label.advance_to(round as u32 + 2);
+                root.step_while(|| probe.lt(&graph.time()));
+                baseline.round(timer);
+            }
+            baseline.finish();
+        }
+        // END UNINSTRUMENTED
+
+        // BEGIN INSTRUMENTED
+        let mut instrumented = Measurement::new("instrumented");
+        let profiler = Profiler::new();
+        {
+            let profiler = profiler.clone();
+            let (mut graph, mut label, mut query, probe) = root.scoped::<u32, _, _>(move |streaming| {
+
+                // Construct inputs for graph data, label data, and queries made against the results.
+                // NOTE: label data supplied separately as per other systems, which provide graph node
+                // NOTE: data independently from the graph; otherwise we would compute and maintain it.
+                let (graph_handle, graph) = streaming.new_input(); let graph = Collection::new(graph);
+                let (label_handle, label) = streaming.new_input(); let label = Collection::new(label);
+                let (query_handle, query) = streaming.new_input(); let query = Collection::new(query);
+
+                // Iterative scope for rounds of input correction
+                let (graph_must, label_must) = streaming.scoped::<Round,_,_>(move |correction| {
+
+                    // Bring each input into the scope.
+                    let graph = graph.enter(correction);
+                    let label = label.enter(correction);
+                    let query = query.enter(correction);
+
+                    // Each data input uses a MonotonicVariable to track its elements required to explain outputs.
+                    // These collections grow monotonically in each round of correction, limited by the full set.
+                    // Elements are tagged with the query requiring them, so that a batch of queries can share a
+                    // fixpoint and still have their explanations reported separately.
+                    let mut graph_must = MonotonicVariable::new(correction);
+                    let mut label_must = MonotonicVariable::new(correction);
+
+                    // the working inputs are the union of requirements across all queries.
+                    let graph_work = graph_must.stream.map(|(x,_q)| x).threshold(|_, w| if w > 0 { 1 } else { 0 });
+                    let label_work = label_must.stream.map(|(x,_q)| x).threshold(|_, w| if w > 0 { 1 } else { 0 });
+
+                    // Scope for explanation derivation.
+                    let child_scope = RefCell::new(correction.new_subscope());
+                    let child_index = child_scope.borrow().index;
+
+                    // determine and return necessary members of `graph` and `label`.
+                    let (graph_need, label_need) = {
+
+                        // wrap an explanation scope builder.
+                        let mut explanation_scope = Child {
+                            subgraph: &child_scope,
+                            parent: correction.clone(),
+                        };
+                
+                        // define variables for each input to the computation.
+                        // the data source is from outside the correction loop,
+                        // and the working source are the *_must collections.
+                        let mut var_graph = Variable::new(graph.clone(), graph_work, &mut explanation_scope);
+                        let mut var_label = Variable::new(label.clone(), label_work, &mut explanation_scope);
+
+                        // transpose edges and concatenate, symmetrizing the graph.
+                        let mut var_edges = var_graph.map_inverse(|(x,y)| (y,x), |(y,x)| (x,y))
+                                                     .concat(&mut var_graph);
+
+                        // actual computation loop; can you believe we do computation, too?
+                        let mut final_labels = correction.scoped::<u32,_,_>(|inner| {
+
+                            // BEGIN FEEDBACK SETUP
+                            let (handle1, cycle1) = inner.loop_variable(u32::max_value(), 1); let cycle1 = Collection::new(cycle1);
+                            let (handle2, cycle2) = inner.loop_variable(u32::max_value(), 1); let cycle2 = Collection::new(cycle2);
+                            let mut var_inner = Variable::new(cycle1, cycle2, &mut explanation_scope);
+                            // END FEEDBACK SETUP
+
+                            // join edges with looped labels, then re-order to have dst as key
+                            let mut var_transmit = 
+                                var_edges.enter(inner)
+                                         .join_u(&mut var_inner)
+                                         .map_inverse(|(x,(y,l))| (y,(l,x)), |(y,(l,x))| (x,(y,l)));
+
+                            // bring in initial labels from outside, concat with proposals
+                            let mut var_options = 
+                                var_label.enter_delayed(inner, Delay::log_scaled(), |&(node, _)| node as u64)
+                                         .map_inverse(|(x,l)| (x,(l,x)), |(x,(l,_))| (x,l))
+                                         .concat(&mut var_transmit);
+
+                            // group the labels by key, using min! macro
+                            let mut var_min = min!(var_options, |(l,_d)| l, explanation_scope);
+
+                            if profile {
+                                var_transmit.profile(&var_inner, "cc/join", &profiler);
+                                var_min.profile(&var_options, "cc/min", &profiler);
+                            }
+
+                            // BEGIN FEEDBACK CONNECT
+                            var_min.stream.inner.connect_loop(handle1);
+                            var_min.working.inner.connect_loop(handle2);
+                            var_min.depends.add(
+                                &radix::consolidate_u(
+                                    &explanation::retreat(&var_inner.depends.stream),
+                                    |x| x.0
+                                )
+                            );
+                            var_min.lineage_feedback(&var_inner);
+                            // END FEEDBACK CONNECT
+
+                            leave!(var_min, explanation_scope)
+                        });
+
+                        // introduce any query elements as initial dependences.
+                        final_labels.depends.add(&query.enter(&explanation_scope));
+
+                        // pop input requirements out of the explanation scope and return them.
+                        (var_graph.depends.stream.leave(), var_label.depends.stream.leave())
+                    };
+
+                    // all explanation infrastructure in place; add to correct scope.
+                    correction.add_operator_with_index(child_scope.into_inner(), child_index);
+
+                    // intersect required edges and labels with existing edges and labels.
+                    graph_must.add(&graph_need.map(|(k,v,_t,q)| ((k,v),q)).semijoin(&graph));
+                    label_must.add(&label_need.map(|(k,v,_t,q)| ((k,v),q)).semijoin(&label));
+
+                    // merge the things we need, pop them out of the loop, and probe
+                    (graph_must.stream.leave(), label_must.stream.leave())
+                });
+
+                // attach a probe, so that we can await completeness.
+                let query_probe = graph_must.concat(&label_must).probe().0;
+
+                (graph_handle, label_handle, query_handle, query_probe)
+            });
+
+            let timer = Instant::now();
+            for (i, &(src, dst)) in graph_data.iter().enumerate() {
+                if i % peers == index {
+                    graph.send(((src, dst), 1));
+                }
+            }
+            for node in 0 .. nodes {
+                if node as usize % peers == index {
+                    label.send(((node, node), 1));
+                }
+            }
+            graph.advance_to(1);
+            label.advance_to(1);
+            query.advance_to(1);
+            root.step_while(|| probe.lt(&query.time()));
+            instrumented.loaded(edges, timer);
+
+            for round in 0 .. rounds {
+                let timer = Instant::now();
+                if index == 0 {
+                    graph.send((removals[round], -1));
+                    query.send(((
+                        queries[round], 
+                        0, 
+                        Product::new(RootTimestamp::new(u32::max_value()), Round::max_value()),
+                        queries[round]
+                    ), 1));
+                }
+                graph.advance_to(round as u32 + 2);
+                label.advance_to(round as u32 + 2);
+                query.advance_to(round as u32 + 2);
+                root.step_while(|| probe.lt(&query.time()));
+                instrumented.round(timer);
+            }
+            instrumented.finish();
+        }
+        // END INSTRUMENTED
+
+        if index == 0 {
+            println!("{}", baseline);
+            println!("{}", instrumented);
+            println!("{}", overhead(&baseline, &instrumented));
+            if profile {
+                print!("{}", profiler.breakdown());
+            }
+        }
+    }).unwrap();
+}
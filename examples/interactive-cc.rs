@@ -1,233 +1,151 @@
-#[macro_use]
 extern crate explanation;
 
 #[allow(unused_variables)]
 extern crate rand;
 extern crate timely;
 extern crate graph_map;
-extern crate differential_dataflow;
 
-use std::cell::RefCell;
 use std::io::BufRead;
+use std::sync::mpsc::{channel, Sender, Receiver};
+#[cfg(feature = "tui")]
+use std::sync::{Arc, Mutex};
 
 use graph_map::GraphMMap;
-use timely::dataflow::*;
-use timely::dataflow::scopes::Child;
-use timely::dataflow::operators::*;
-use timely::progress::timestamp::RootTimestamp;
-use timely::progress::nested::product::Product;
-use differential_dataflow::Collection;
-use differential_dataflow::operators::*;
 
-use explanation::{Variable, MonotonicVariable};
+use explanation::interactive::{self, CcCommand, Event};
+#[cfg(feature = "tui")]
+use explanation::explorer::Explorer;
 
 fn main() {
 
+    let explorer = display();
     timely::execute_from_args(std::env::args(), move |root| {
 
-        // BEGIN DATAFLOW CONSTRUCTION
-        // Outer-most streaming scope; here inputs to the graph, labels, queries, etc may change.
-        let (mut graph, mut label, mut query, probe) = root.scoped::<u32, _, _>(move |streaming| {
-
-            // Construct inputs for graph data, label data, and queries made against the results.
-            // NOTE: label data supplied separately as per other systems, which provide graph node
-            // NOTE: data independently from the graph; otherwise we would compute and maintain it.
-            let (graph_handle, graph) = streaming.new_input(); let graph = Collection::new(graph);
-            let (label_handle, label) = streaming.new_input(); let label = Collection::new(label);
-            let (query_handle, query) = streaming.new_input(); let query = Collection::new(query);
-
-            // Iterative scope for rounds of input correction
-            let (mut graph_must, mut label_must) = streaming.scoped::<u32,_,_>(move |correction| {
-
-                // Bring each input into the scope.
-                let graph = graph.enter(correction);
-                let label = label.enter(correction);
-                let query = query.enter(correction);
-
-                // Each data input uses a MonotonicVariable to track its elements required to explain outputs.
-                // These collections grow monotonically in each round of correction, limited by the full set.
-                let mut graph_must = MonotonicVariable::new(correction);
-                let mut label_must = MonotonicVariable::new(correction);
-
-                // Scope for explanation derivation.
-                let child_scope = RefCell::new(correction.new_subscope());
-                let child_index = child_scope.borrow().index;
-
-                // determine and return necessary members of `graph` and `label`.
-                let (graph_need, label_need) = {
-
-                    // wrap an explanation scope builder.
-                    let mut explanation_scope = Child {
-                        subgraph: &child_scope,
-                        parent: correction.clone(),
-                    };
-            
-                    // define variables for each input to the computation.
-                    // the data source is from outside the correction loop,
-                    // and the working source are the *_must collections.
-                    let mut var_graph = Variable::new(graph.clone(), graph_must.stream.clone(), &mut explanation_scope);
-                    let mut var_label = Variable::new(label.clone(), label_must.stream.clone(), &mut explanation_scope);
-
-                    // transpose edges and concatenate, symmetrizing the graph.
-                    let mut var_edges = var_graph.map_inverse(|(x,y)| (y,x), |(y,x)| (x,y))
-                                                 .concat(&mut var_graph);
-
-                    // actual computation loop; can you believe we do computation, too?
-                    let mut final_labels = correction.scoped::<u32,_,_>(|inner| {
-
-                        // BEGIN FEEDBACK SETUP
-                        let (handle1, cycle1) = inner.loop_variable(u32::max_value(), 1); let cycle1 = Collection::new(cycle1);
-                        let (handle2, cycle2) = inner.loop_variable(u32::max_value(), 1); let cycle2 = Collection::new(cycle2);
-                        let mut var_inner = Variable::new(cycle1, cycle2, &mut explanation_scope);
-                        // END FEEDBACK SETUP
-
-                        // join edges with looped labels, then re-order to have dst as key
-                        let mut var_transmit = 
-                            var_edges.enter(inner)
-                                     .join_u(&mut var_inner)
-                                     .map_inverse(|(x,(y,l))| (y,(l,x)), |(y,(l,x))| (x,(y,l)));
-
-                        // bring in initial labels from outside, concat with proposals
-                        let mut var_options = 
-                            var_label.enter_at(inner, |r| 256 * (((((r.0).0) as f64).ln() * 10.0) as u32))
-                                     .map_inverse(|(x,l)| (x,(l,x)), |(x,(l,_))| (x,l))
-                                     .concat(&mut var_transmit);
-
-                        // group the labels by key, using min! macro
-                        let mut var_min = min!(var_options, |(l,_d)| l, explanation_scope);
-
-                        // BEGIN FEEDBACK CONNECT
-                        var_min.stream.inner.connect_loop(handle1);
-                        var_min.working.inner.connect_loop(handle2);
-                        var_min.depends.add(
-                            &var_inner.depends.stream
-                            .filter(|&(_,_,t,_)| t.inner > 0)
-                            .map(|(x,l,t,q)| (x,l,Product::new(t.outer, t.inner - 1),q))
-                        );
-                        // END FEEDBACK CONNECT
-
-                        leave!(var_min, explanation_scope)
-                    });
-
-                    // introduce any query elements as initial dependences.
-                    final_labels.depends.add(&query.enter(&explanation_scope));
-
-                    // pop input requirements out of the explanation scope and return them.
-                    (var_graph.depends.stream.leave(), var_label.depends.stream.leave())
-                };
-
-                // all explanation infrastructure in place; add to correct scope.
-                correction.add_operator_with_index(child_scope.into_inner(), child_index);
-
-                // intersect required edges and labels with existing edges and labels.
-                graph_must.add(&graph_need.map(|(k,v,_t,_q)| ((k,v),())).semijoin(&graph).map(|((k,v),_)| (k,v)));
-                label_must.add(&label_need.map(|(k,v,_t,_q)| ((k,v),())).semijoin(&label).map(|((k,v),_)| (k,v)));
-
-                // merge the things we need, pop them out of the loop, and probe
-                (graph_must.stream.leave(), label_must.stream.leave())
-            });
-
-            // print out what we require from each input.
-            graph_must = graph_must.inspect(|x| println!("graph_must:\t{:?}", x));
-            label_must = label_must.inspect(|x| println!("label_must:\t{:?}", x));
-
-            // attach a probe, so that we can await completeness.
-            let query_probe = graph_must.concat(&label_must).probe().0;
-
-            (graph_handle, label_handle, query_handle, query_probe)
-        });
-        // END DATAFLOW CONSTRUCTION
-
         // BEGIN DATA LOADING
         // NOTE: This could be replaced with your favorite data format.
+        // NOTE: label data supplied separately as per other systems, which provide graph node
+        // NOTE: data independently from the graph; otherwise we would compute and maintain it.
+        let mut graph = Vec::new();
+        let mut label = Vec::new();
         if let Some(filename) = std::env::args().nth(1) {
             let edges = GraphMMap::new(&filename);
             for node in 0..edges.nodes() {
                 if node % root.peers() == root.index() {
                     if edges.edges(node).len() > 0 {
-                        label.send(((node as u32, node as u32), 1));                        
+                        label.push((node as u32, node as u32));
                     }
                     for &edge in edges.edges(node) {
-                        graph.send(((node as u32, edge as u32), 1));
+                        graph.push((node as u32, edge as u32));
                     }
                 }
             }
         }
         // END DATA LOADING
 
-        // close labels, advance graph and query inputs to the next epoch.
-        graph.advance_to(1);
-        label.advance_to(1);
-        query.advance_to(1);
-        root.step_while(|| probe.lt(&query.time()));
-        println!("");
-
-        let timer = ::std::time::Instant::now();
-        root.step_while(|| probe.lt(&query.time()));
-        if root.index() == 0 { println!("initialization elapsed:\t{:?}", timer.elapsed()); }
-        
-        let mut round = 1;
-        let input = std::io::stdin();
-        for line in input.lock().lines().map(|x| x.unwrap()) {
-        
-            let mut elts = line[..].split_whitespace();
-        
-            if let Some(command) = elts.next() {
-                if command == "query" {
-                    if let Some(sign) = elts.next() {
-                        let sign = if sign == "-" { -1i32 } else { 1 };
-                        if let Some(source) = elts.next() {
-                            if let Some(node) = source.parse::<u32>().ok() {
-                                query.send(((
-                                    node, 
-                                    0, 
-                                    Product::new(RootTimestamp::new(0), u32::max_value()),
-                                    0 as u32
-                                ),sign));
-                            }
-                        }
-                    }
-                }
-                if command == "graph" {
-                    if let Some(sign) = elts.next() {
-                        let sign = if sign == "-" { -1i32 } else { 1 };
-                        if let Some(source) = elts.next() {
-                            if let Some(source) = source.parse::<u32>().ok() {
-                                if let Some(target) = elts.next() {
-                                    if let Some(target) = target.parse::<u32>().ok() {
-                                        graph.send(((source, target),sign));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                if command == "label" {
-                    if let Some(sign) = elts.next() {
-                        let sign = if sign == "-" { -1i32 } else { 1 };
-                        if let Some(source) = elts.next() {
-                            if let Some(source) = source.parse::<u32>().ok() {
-                                if let Some(target) = elts.next() {
-                                    if let Some(target) = target.parse::<u32>().ok() {
-                                        label.send(((source, target),sign));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-        
-                graph.advance_to(round + 1);
-                label.advance_to(round + 1);
-                query.advance_to(round + 1);
-                let timer = ::std::time::Instant::now();
-                root.step_while(|| probe.lt(&query.time()));
-                if root.index() == 0 {
-                    println!("round {:?} elapsed:\t{:?}", round, timer.elapsed());
-                }
-        
-                round += 1;
+        // commands are read from stdin, and what the session reports is printed.
+        let (command_send, command_recv) = channel();
+        let (event_send, event_recv) = channel();
+        let index = root.index();
+        let display = explorer.clone();
+        ::std::thread::spawn(move || read_commands(command_send, display));
+        let display = explorer.clone();
+        ::std::thread::spawn(move || print_events(index, event_recv, display));
+
+        // independent query lanes each have their own correction loop, and their own copy of its indices.
+        let lanes = std::env::args().nth(2).and_then(|x| x.parse().ok()).unwrap_or(1);
+        interactive::cc(root, lanes, graph, label, command_recv, event_send);
+
+    }).unwrap();
+}
+
+/// What the session reports is drawn as a screen of queries, shared by all workers.
+#[cfg(feature = "tui")]
+type Display = Arc<Mutex<Explorer<(u32, u32)>>>;
+
+/// What the session reports is printed as it arrives.
+#[cfg(not(feature = "tui"))]
+type Display = ();
+
+#[cfg(feature = "tui")]
+fn display() -> Display { Arc::new(Mutex::new(Explorer::new(&["graph", "label"]))) }
+
+#[cfg(not(feature = "tui"))]
+fn display() -> Display { }
+
+/// Reads commands from stdin and sends them to the session.
+#[cfg(not(feature = "tui"))]
+fn read_commands(commands: Sender<CcCommand>, _display: Display) {
+    let input = std::io::stdin();
+    for line in input.lock().lines().map(|x| x.unwrap()) {
+        match CcCommand::parse(&line) {
+            Ok(Some(command)) => if commands.send(command).is_err() { break; },
+            Ok(None) => { },
+            Err(reason) => println!("rejected:\t{}", reason),
+        }
+    }
+}
+
+/// Reads commands from stdin, and sends them to the session; `open <query>` and `close <query>` instead expand
+/// and collapse queries on the screen.
+#[cfg(feature = "tui")]
+fn read_commands(commands: Sender<CcCommand>, display: Display) {
+    let input = std::io::stdin();
+    for line in input.lock().lines().map(|x| x.unwrap()) {
+        let mut words = line.split_whitespace();
+        if let (Some(verb), Some(Ok(query))) = (words.next(), words.next().map(|x| x.parse::<u32>())) {
+            if verb == "open" || verb == "close" {
+                let mut explorer = display.lock().unwrap();
+                explorer.toggle(query);
+                print!("{}", explorer.render());
+                continue;
             }
         }
-    }).unwrap();
+        let command = match CcCommand::parse(&line) {
+            Ok(Some(command)) => command,
+            Ok(None) => continue,
+            Err(reason) => { println!("rejected:\t{}", reason); continue; },
+        };
+        // queries are identified by the node they ask about.
+        if let CcCommand::Query(ref pairs, epoch, sign, _) = command {
+            let mut explorer = display.lock().unwrap();
+            for &(node, label) in pairs.iter() {
+                let asked = if epoch == u32::max_value() { format!("{:?}", (node, label)) } else { format!("{:?}@{}", (node, label), epoch) };
+                explorer.ask(node, &asked, sign);
+            }
+        }
+        if commands.send(command).is_err() { break; }
+    }
+}
+
+/// Prints each event the session reports.
+#[cfg(not(feature = "tui"))]
+fn print_events(index: usize, events: Receiver<Event<((u32, u32), u32)>>, _display: Display) {
+    for event in events.iter() {
+        match event {
+            // print out what we require from each input, as `((key, val), query)`.
+            Event::Must(0, x, w) => println!("graph_must:\t{:?}", (x, w)),
+            Event::Must(_, x, w) => println!("label_must:\t{:?}", (x, w)),
+            Event::Converged(lane, x, w) => println!("converged:\t{:?}\t(lane {})", (x, w), lane),
+            Event::Diagnostic(x, w) => println!("diagnostic:\t{:?}", (x, w)),
+            Event::Shortfall(input, (x, q), w) => println!("shortfall:\t{:?}", ((input as u32, x, q), w)),
+            Event::Cached(q, w) => println!("cached:\t{:?}", (q, w)),
+            Event::Initialized(elapsed) => if index == 0 { println!("\ninitialization elapsed:\t{:?}", elapsed) },
+            Event::Round(round, elapsed) => if index == 0 { println!("round {:?} elapsed:\t{:?}", round, elapsed) },
+            Event::CaughtUp(lane) => if index == 0 { println!("lane {:?} caught up", lane) },
+        }
+    }
+}
+
+/// Applies each event the session reports to the screen, which the first worker redraws as rounds complete.
+#[cfg(feature = "tui")]
+fn print_events(index: usize, events: Receiver<Event<((u32, u32), u32)>>, display: Display) {
+    for event in events.iter() {
+        let redraw = match event {
+            Event::Initialized(_) | Event::Round(_, _) | Event::CaughtUp(_) => index == 0,
+            _ => false,
+        };
+        let mut explorer = display.lock().unwrap();
+        explorer.apply(event);
+        if redraw { print!("{}", explorer.render()); }
+    }
 }
@@ -0,0 +1,94 @@
+//! Estimates of the tuples retained by instrumented dataflows.
+//!
+//! Each instrumented collection is backed by state in the operators that consume it: arrangements of the
+//! actual and working records, and the accumulated requirements. An `Accountant` observes the updates flowing
+//! into these collections and maintains their net counts, which bound the number of distinct tuples the
+//! downstream arrangements must retain. Counts are per worker; names may be hierarchical (e.g. "cc/edges")
+//! so that `Accountant::scope` can aggregate everything registered under a common prefix.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+
+use differential_dataflow::{Data, Collection};
+
+/// Net retained tuples for one instrumented collection.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// Tuples in the actual collection.
+    pub stream: i64,
+    /// Tuples in the working collection.
+    pub working: i64,
+    /// Accumulated requirement tuples.
+    pub depends: i64,
+}
+
+impl Counts {
+    /// The sum of all three counts.
+    pub fn total(&self) -> i64 {
+        self.stream + self.working + self.depends
+    }
+    fn add(&mut self, other: &Counts) {
+        self.stream += other.stream;
+        self.working += other.working;
+        self.depends += other.depends;
+    }
+}
+
+/// The part of an instrumented collection being counted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Part {
+    /// The actual collection.
+    Stream,
+    /// The working collection.
+    Working,
+    /// The accumulated requirements.
+    Depends,
+}
+
+/// A shared registry of retained tuple counts, by name.
+#[derive(Clone, Default)]
+pub struct Accountant {
+    counts: Rc<RefCell<BTreeMap<String, Counts>>>,
+}
+
+impl Accountant {
+    /// Creates a new, empty accountant.
+    pub fn new() -> Accountant {
+        Accountant { counts: Rc::new(RefCell::new(BTreeMap::new())) }
+    }
+    /// Counts updates to `collection` as `part` of the collection called `name`.
+    pub fn observe<G: Scope, D: Data>(&self, name: &str, part: Part, collection: &Collection<G, D>) {
+        let counts = self.counts.clone();
+        let name = name.to_owned();
+        collection.inner.inspect_batch(move |_time, data| {
+            let delta = data.iter().map(|x| x.1 as i64).sum::<i64>();
+            let mut counts = counts.borrow_mut();
+            let entry = counts.entry(name.clone()).or_insert(Counts::default());
+            match part {
+                Part::Stream => entry.stream += delta,
+                Part::Working => entry.working += delta,
+                Part::Depends => entry.depends += delta,
+            }
+        });
+    }
+    /// Current counts for the collection called `name`.
+    pub fn get(&self, name: &str) -> Counts {
+        self.counts.borrow().get(name).cloned().unwrap_or(Counts::default())
+    }
+    /// Current counts summed over all collections whose names begin with `prefix`.
+    pub fn scope(&self, prefix: &str) -> Counts {
+        let mut result = Counts::default();
+        for (_, counts) in self.counts.borrow().iter().filter(|x| x.0.starts_with(prefix)) {
+            result.add(counts);
+        }
+        result
+    }
+    /// Current counts for all collections, ordered by name.
+    pub fn report(&self) -> Vec<(String, Counts)> {
+        self.counts.borrow().iter().map(|(name, counts)| (name.clone(), *counts)).collect()
+    }
+}
@@ -0,0 +1,461 @@
+//! Instrumented graph algorithms, for use within an explanation scope.
+//!
+//! Each algorithm takes its inputs as `Variable`s in a correction scope, builds any iterative loop it needs, and
+//! returns its output as a `Variable` in the same scope, with requirements on the output propagated back to the
+//! inputs. Drivers introduce queries on the output, and read requirements off the inputs, as `cc::explain` does.
+
+use std::hash::Hash;
+
+use timely::dataflow::*;
+use timely::dataflow::scopes::Child;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::operators::*;
+use differential_dataflow::lattice::Lattice;
+
+use {Variable, VariableFeedback, Round};
+use delay::Delay;
+
+/// Labels each node with the least label reachable from it along the symmetrized `edges`.
+///
+/// `edges` are `(src, dst)` pairs and `labels` are `(node, label)` pairs, both in the correction scope whose
+/// explanation scope is `explanation_scope`; the result holds a `(node, label)` pair for each node reached by some
+/// label. Initial labels are introduced in order of the logarithm of their node, so that small labels win early,
+/// and each node keeps the least label it has seen, which explains a label by one path from the node it started at.
+pub fn connected_components<'a, G>(
+    edges: &mut Variable<'a, G, u32, u32, G>,
+    labels: &mut Variable<'a, G, u32, u32, G>,
+    explanation_scope: &mut Child<'a, G, Round>) -> Variable<'a, G, u32, u32, G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+    // transpose edges and concatenate, symmetrizing the graph.
+    let mut var_edges = permute!(edges, (x,y) => (y,x)).concat(edges);
+
+    if cfg!(feature = "check-working") {
+        var_edges.check_working("cc/edges");
+    }
+
+    // actual computation loop; can you believe we do computation, too?
+    let mut correction = edges.stream.scope();
+    correction.scoped::<u32,_,_>(|inner| {
+
+        let mut var_inner = VariableFeedback::new(inner, explanation_scope);
+
+        // join edges with looped labels, then re-order to have dst as key
+        let mut var_transmit =
+            var_edges.enter(inner)
+                     .join_u(&mut var_inner)
+                     .map_inverse(|(x,(y,l))| (y,(l,x)), |(y,(l,x))| (x,(y,l)));
+
+        // bring in initial labels from outside, concat with proposals
+        let mut var_options =
+            labels.enter_delayed(inner, Delay::log_scaled(), |&(node, _)| node as u64)
+                  .map_inverse(|(x,l)| (x,(l,x)), |(x,(l,_))| (x,l))
+                  .concat(&mut var_transmit);
+
+        // group the labels by key, using min! macro
+        let mut var_min = min!(var_options, |(l,_d)| l, *explanation_scope);
+
+        // the minimum of a subset of labels may be a label the full set does not propose, so neither the minima
+        // nor anything derived from them through the loop are checked with `check_working`.
+
+        var_inner.set_u(&mut var_min);
+
+        leave!(var_min, *explanation_scope)
+    })
+}
+
+/// Computes the number of `edges` on a shortest directed path from any of `roots` to each node.
+///
+/// `edges` are `(src, dst)` pairs and `roots` are `(node, distance)` pairs, usually with distance zero; the result
+/// holds a `(node, distance)` pair for each node reachable from some root. Each node keeps the least distance
+/// proposed to it, and among equal proposals the one from the least neighbor, so that a queried distance is
+/// explained by the edges of one shortest path and the root it starts from, rather than by every shortest path.
+pub fn bfs<'a, G>(
+    edges: &mut Variable<'a, G, u32, u32, G>,
+    roots: &mut Variable<'a, G, u32, u32, G>,
+    explanation_scope: &mut Child<'a, G, Round>) -> Variable<'a, G, u32, u32, G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+    let mut correction = edges.stream.scope();
+    correction.scoped::<u32,_,_>(|inner| {
+
+        let mut var_inner = VariableFeedback::new(inner, explanation_scope);
+
+        // each reached node proposes one more than its distance to each neighbor, keyed by the neighbor. requests
+        // for roots' own proposals also reach the inverse, which saturates for them rather than underflowing.
+        let mut var_transmit =
+            edges.enter(inner)
+                 .join_u(&mut var_inner)
+                 .map_inverse(|(x,(y,d))| (y,(d+1,x)), |(y,(d,x))| (x,(y,d.saturating_sub(1))));
+
+        // roots propose their own distances, which they are responsible for.
+        let mut var_options =
+            roots.enter(inner)
+                 .map_inverse(|(x,d)| (x,(d,x)), |(x,(d,_))| (x,d))
+                 .concat(&mut var_transmit);
+
+        // keep the least distance, the proposing node breaking ties.
+        let mut var_min = min!(var_options, |(d,_s)| d, *explanation_scope);
+
+        var_inner.set_u(&mut var_min);
+
+        leave!(var_min, *explanation_scope)
+    })
+}
+
+/// Computes the least total weight of a directed path from any of `sources` to each node.
+///
+/// `edges` are `(src, (dst, weight))` triples and `sources` are `(node, cost)` pairs, usually with cost zero; the
+/// result holds a `(node, cost)` pair for each node reachable from some source. As with `bfs`, each node keeps the
+/// least cost proposed to it and among equal proposals the one from the least neighbor (then the least weight),
+/// so that a queried cost is explained by the weighted edges of one shortest path and the source it starts from.
+/// Paths whose cost would overflow a `u32` are not followed.
+pub fn sssp<'a, G>(
+    edges: &mut Variable<'a, G, u32, (u32, u32), G>,
+    sources: &mut Variable<'a, G, u32, u32, G>,
+    explanation_scope: &mut Child<'a, G, Round>) -> Variable<'a, G, u32, u32, G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+    let mut correction = edges.stream.scope();
+    correction.scoped::<u32,_,_>(|inner| {
+
+        let mut var_inner = VariableFeedback::new(inner, explanation_scope);
+
+        // each reached node proposes its cost plus the edge weight to each neighbor, keyed by the neighbor. costs
+        // that would overflow are not proposed, and as every proposed cost is at least its edge weight, the inverse
+        // only saturates for records that were never proposed.
+        let mut var_transmit =
+            edges.enter(inner)
+                 .join_u(&mut var_inner)
+                 .filter(|&(_,((_,w),c))| c.checked_add(w).is_some())
+                 .map_inverse(|(x,((y,w),c))| (y,(c+w,x,w)), |(y,(c,x,w))| (x,((y,w),c.saturating_sub(w))));
+
+        // sources propose their own costs, which they are responsible for.
+        let mut var_options =
+            sources.enter(inner)
+                   .map_inverse(|(x,c)| (x,(c,x,0)), |(x,(c,_,_))| (x,c))
+                   .concat(&mut var_transmit);
+
+        // keep the least cost, the proposing node and edge weight breaking ties.
+        let mut var_min = min!(var_options, |(c,_s,_w)| c, *explanation_scope);
+
+        var_inner.set_u(&mut var_min);
+
+        leave!(var_min, *explanation_scope)
+    })
+}
+
+/// The key of the pair `(source, node)` in the output of `reachability`.
+pub fn reach_key(source: u32, node: u32) -> u64 {
+    ((source as u64) << 32) | (node as u64)
+}
+
+/// The `(source, node)` pair named by a key from `reach_key`.
+pub fn reach_pair(key: u64) -> (u32, u32) {
+    ((key >> 32) as u32, key as u32)
+}
+
+/// Computes which nodes each of `sources` reaches along directed `edges`, and in how many steps.
+///
+/// `edges` are `(src, dst)` pairs and `sources` are `(node, distance)` pairs, usually with distance zero. The result
+/// holds a `(reach_key(source, node), distance)` pair for each node a source reaches, keyed so that a query about
+/// "source reaches node" can name the pair without its distance, by `Variable::query_by_key`. Each pair keeps the
+/// least distance proposed for it, the proposing node breaking ties, so that it is explained by the edges of one
+/// path from the source rather than by the whole subgraph the source reaches.
+pub fn reachability<'a, G>(
+    edges: &mut Variable<'a, G, u32, u32, G>,
+    sources: &mut Variable<'a, G, u32, u32, G>,
+    explanation_scope: &mut Child<'a, G, Round>) -> Variable<'a, G, u64, u32, G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+    let mut correction = edges.stream.scope();
+    correction.scoped::<u32,_,_>(|inner| {
+
+        // looped records are `(node, source << 32 | distance)`, so that they can be joined with edges by node.
+        let mut var_inner = VariableFeedback::new(inner, explanation_scope);
+
+        // each reached node proposes one more than its distance to each neighbor, keyed by source and neighbor.
+        // requests for sources' own records also reach the inverse, which saturates for them as `bfs`'s does.
+        let mut var_transmit =
+            edges.enter(inner)
+                 .join_u(&mut var_inner)
+                 .map_inverse(|(x,(y,p))| (reach_key((p >> 32) as u32, y), ((p as u32) + 1, x)),
+                              |(k,(d,x))| { let (s,y) = reach_pair(k); (x,(y,reach_key(s, d.saturating_sub(1)))) });
+
+        // sources reach themselves, at their own distances.
+        let mut var_options =
+            sources.enter(inner)
+                   .map_inverse(|(s,d)| (reach_key(s, s),(d,s)), |(k,(d,_))| (reach_pair(k).0,d))
+                   .concat(&mut var_transmit);
+
+        // keep the least distance for each pair, the proposing node breaking ties.
+        let mut var_min = min!(var_options, |(d,_x)| d, *explanation_scope);
+
+        let mut var_back = var_min.map_inverse(|(k,d)| { let (s,y) = reach_pair(k); (y,reach_key(s, d)) },
+                                               |(y,p)| (reach_key((p >> 32) as u32, y), p as u32));
+        var_inner.set_u(&mut var_back);
+
+        leave!(var_min, *explanation_scope)
+    })
+}
+
+/// Computes which nodes share a strongly connected component with each of `sources`.
+///
+/// `edges` are `(src, dst)` pairs and `sources` are `(node, distance)` pairs as for `reachability`. The result holds
+/// a `(reach_key(source, node), (out, back))` pair for each node that the source both reaches and is reached by,
+/// where `out` and `back` are the lengths of the paths there and back. A query naming the pair by key is explained
+/// by the edges of one path each way, which together form a cycle through both nodes, and the source's record.
+///
+/// Reachability is computed from each source in both directions, so asking about every node costs space for every
+/// pair of nodes connected by some path, even those not sharing a component.
+pub fn scc<'a, G>(
+    edges: &mut Variable<'a, G, u32, u32, G>,
+    sources: &mut Variable<'a, G, u32, u32, G>,
+    explanation_scope: &mut Child<'a, G, Round>) -> Variable<'a, G, u64, (u32, u32), G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+    // nodes each source reaches, and nodes reaching each source, along transposed edges.
+    let mut forward = reachability(edges, sources, explanation_scope);
+    let mut transposed = permute!(edges, (x,y) => (y,x));
+    let mut backward = reachability(&mut transposed, sources, explanation_scope);
+
+    // pairs present in both directions are mutually reachable.
+    forward.join_u(&mut backward)
+}
+
+/// Matches individuals to recipients by repeated proposal and rejection, until no proposals change.
+///
+/// `prefs` are `(a_id, (a_pref, b_id, b_pref))` records, each an individual's preference for a recipient and the
+/// recipient's preference for the individual, lower being more appealing. Each individual proposes to its most
+/// appealing option not yet rejected, each recipient accepts its most appealing proposal, and rejected proposals
+/// are fed back. The result holds the accepted proposals, in the same form, which explain a match by the
+/// preferences that were proposed and rejected on the way to it.
+pub fn stable_matching<'a, G>(
+    prefs: &mut Variable<'a, G, u32, (u32, u32, u32), G>,
+    explanation_scope: &mut Child<'a, G, Round>) -> Variable<'a, G, u32, (u32, u32, u32), G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+    let mut correction = prefs.stream.scope();
+    correction.scoped::<u32,_,_>(|inner| {
+
+        let mut var_rejections = VariableFeedback::new(inner, explanation_scope);
+
+        // proposals are `prefs` excluding any rejections.
+        let mut var_entered = prefs.enter(inner);
+        let mut var_options = except!(var_entered, var_rejections, *explanation_scope);
+
+        // have each individual propose to its most appealing option.
+        let mut var_proposals = min!(var_options, |x| x, *explanation_scope);
+
+        // rotate preferences to be keyed by recipient, ordered by their preference; take min; rotate back.
+        let mut var_accepts1 = permute!(var_proposals, (a,(c,b,d)) => (b,(d,a,c)));
+        let mut var_accepts2 = min!(var_accepts1, |x| x, *explanation_scope);
+        let mut var_accepts = permute!(var_accepts2, (b,(d,a,c)) => (a,(c,b,d)));
+
+        // rejected proposals should be fed back around
+        let mut var_rejected = except!(var_proposals, var_accepts, *explanation_scope)
+                                .concat(&mut var_rejections)
+                                .consolidate();
+
+        var_rejections.set_u(&mut var_rejected);
+
+        // accepted proposals are what we want to keep.
+        leave!(var_accepts, *explanation_scope)
+    })
+}
+
+/// Enumerates the triangles of an undirected graph.
+///
+/// `edges` holds each undirected edge once, as `(low, high)` with `low < high`. The result holds `(a, (b, c))` with
+/// `a < b < c` for each triangle, which is explained by its three edges. No loop is involved, so the collections
+/// may be in any scope within the correction scope.
+pub fn triangles<'a, G, Gp>(
+    edges: &mut Variable<'a, G, u32, u32, Gp>) -> Variable<'a, G, u32, (u32, u32), Gp>
+    where G: Scope, Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>, G::Timestamp: Ord+Hash+Lattice {
+
+    // pairs of edges leaving the same node, towards increasing nodes.
+    let mut other = edges.map_inverse(|x| x, |x| x);
+    let mut wedges = edges.join_u(&mut other).filter(|&(_,(b,c))| b < c);
+
+    // each pair is closed by the edge between its two far ends, keyed by the pair.
+    let mut open = wedges.map_inverse(|(a,(b,c))| (pair_key(b, c), a), |(k,a)| (a,key_pair(k)));
+    let mut closing = edges.map_inverse(|(b,c)| (pair_key(b, c), 0), |(k,_)| key_pair(k));
+    open.join_u(&mut closing)
+        .map_inverse(|(k,(a,_))| (a,key_pair(k)), |(a,(b,c))| (pair_key(b, c),(a,0)))
+}
+
+/// Packs two nodes into one unsigned key, for joins and groups keyed by a pair.
+pub fn pair_key(a: u32, b: u32) -> u64 {
+    ((a as u64) << 32) | (b as u64)
+}
+
+/// The two nodes packed by `pair_key`.
+pub fn key_pair(key: u64) -> (u32, u32) {
+    ((key >> 32) as u32, key as u32)
+}
+
+/// Selects a maximal independent set of the undirected graph `edges`, preferring nodes with lesser priorities.
+///
+/// `edges` are `(src, dst)` pairs, symmetrized here, and `nodes` are `(node, priority)` pairs; ties in priority are
+/// broken by node. A node joins the set when no neighbor in the set precedes it, as a greedy pass in priority order
+/// would decide; random priorities give Luby's algorithm. The result holds a `(node, (priority, by))` decision for
+/// each node, where `by` is the node itself for a member of the set, and otherwise the first member neighbor that
+/// excludes it. A member is explained by its own record, as a node without neighbors is always a member; an
+/// excluded node is explained by the edge to the excluding neighbor, and why that neighbor is a member.
+pub fn maximal_independent_set<'a, G>(
+    edges: &mut Variable<'a, G, u32, u32, G>,
+    nodes: &mut Variable<'a, G, u32, u32, G>,
+    explanation_scope: &mut Child<'a, G, Round>) -> Variable<'a, G, u32, (u32, u32), G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+    // transpose edges and concatenate, symmetrizing the graph.
+    let mut var_edges = permute!(edges, (x,y) => (y,x)).concat(edges);
+
+    let mut correction = edges.stream.scope();
+    correction.scoped::<u32,_,_>(|inner| {
+
+        let mut var_inner = VariableFeedback::new(inner, explanation_scope);
+
+        // members announce their priorities to their neighbors, keyed by the neighbor.
+        let mut var_transmit =
+            var_edges.enter(inner)
+                     .join_u(&mut var_inner)
+                     .map_inverse(|(x,(y,p))| (y,(p,x)), |(y,(p,x))| (x,(y,p)));
+
+        // each node competes with the members among its neighbors.
+        let mut var_options =
+            nodes.enter(inner)
+                 .map_inverse(|(x,p)| (x,(p,x)), |(x,(p,_))| (x,p))
+                 .concat(&mut var_transmit);
+
+        // the first of a node and its member neighbors decides it.
+        let mut var_min = min!(var_options, |x| x, *explanation_scope);
+
+        let mut var_members = var_min.filter(|&(x,(_,y))| x == y)
+                                     .map_inverse(|(x,(p,_))| (x,p), |(x,p)| (x,(p,x)));
+        var_inner.set_u(&mut var_members);
+
+        leave!(var_min, *explanation_scope)
+    })
+}
+
+/// Computes PageRank-like scores, explained by at most `k` contributions to each node's score per iteration.
+///
+/// `edges` are `(src, (dst, share))` triples, each passing `share` millionths of the source's score to the
+/// destination, and `base` holds `(node, score)` pairs, which each node's score starts from; with shares of the
+/// damping factor divided by out-degree, and a uniform base, this is PageRank in fixed point. The result holds a
+/// `(node, score)` pair for each node with a base score or an in-edge. Scores only grow from round to round, and
+/// stop once rounding leaves them unchanged. Contributions and scores saturate at `u64::max_value()`.
+///
+/// A score depends on the scores of every node that can reach it, which is usually the whole graph. Instead, a
+/// queried score demands only the `k` largest contributions to it in each iteration, as the edge and score each came
+/// from, and recursively those scores' largest contributions. The result is an approximate explanation, one that
+/// reproduces the largest part of the score, whose size `k` bounds at each step.
+pub fn top_contributors<'a, G>(
+    edges: &mut Variable<'a, G, u32, (u32, u32), G>,
+    base: &mut Variable<'a, G, u32, u64, G>,
+    k: usize,
+    explanation_scope: &mut Child<'a, G, Round>) -> Variable<'a, G, u32, u64, G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+    let mut correction = edges.stream.scope();
+    correction.scoped::<u32,_,_>(|inner| {
+
+        let mut var_inner = VariableFeedback::new(inner, explanation_scope);
+
+        // each node passes its share of its score to each neighbor, keyed by the neighbor; contributions carry the
+        // edge and score they came from, so that each can be traced back to them. scores large enough to overflow
+        // when scaled by a share saturate, as sums do.
+        let mut var_transmit =
+            edges.enter(inner)
+                 .join_u(&mut var_inner)
+                 .map_inverse(|(x,((y,w),r))| (y,(r.saturating_mul(w as u64) / 1000000,x,w,r)), |(y,(_,x,w,r))| (x,((y,w),r)));
+
+        // each node starts from its base score.
+        let mut var_options =
+            base.enter(inner)
+                .map_inverse(|(x,b)| (x,(b,x,0,0)), |(x,(b,_,_,_))| (x,b))
+                .concat(&mut var_transmit);
+
+        // sum contributions, explaining each sum by its largest.
+        let mut var_sum = top_sum!(var_options, |(c,_x,_w,_r)| c, k, *explanation_scope);
+
+        var_inner.set_u(&mut var_sum);
+
+        leave!(var_sum, *explanation_scope)
+    })
+}
+
+/// Matches the left and right nodes of a bipartite graph, with as many matched edges as any matching has.
+///
+/// `edges` are `(left, (right, cost))` triples, and `left_nodes` is at least the number of distinct left nodes.
+/// Matching proceeds as an auction: each right node holds the highest bid it has received, each left node holding
+/// no bid bids on its cheapest edge, raising that right node's price to the next cheapest one plus one, and a left
+/// node stops bidding once every edge costs at least what matching it is worth. With every edge worth more than
+/// `left_nodes`, the prices settle on a maximum matching; costs only break ties between equally priced edges, and
+/// the matching need not be the cheapest maximum one. The result holds the matched `(left, (right, cost))` edges.
+///
+/// A matched edge is explained by the bid that won it, the prices of the other edges that bid was raised past,
+/// the bids that set those prices, and so on back: where a left node was displaced from its first choice and
+/// matched elsewhere, its explanation holds the edges of the augmenting path along which it moved.
+pub fn bipartite_matching<'a, G>(
+    edges: &mut Variable<'a, G, u32, (u32, u32), G>,
+    left_nodes: u32,
+    explanation_scope: &mut Child<'a, G, Round>) -> Variable<'a, G, u32, (u32, u32), G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+    assert!(left_nodes < u32::max_value() - 1, "bipartite_matching prices edges above left_nodes + 1");
+    let value = left_nodes + 1;
+
+    // edges keyed by their right node, where bids are held.
+    let mut var_edges = permute!(edges, (l,(r,c)) => (r,(l,c)));
+
+    let mut correction = edges.stream.scope();
+    correction.scoped::<u32,_,_>(|inner| {
+
+        let mut var_held = VariableFeedback::new(inner, explanation_scope);
+
+        // each edge is offered to its left node at the price of the bid held at its right node, naming the bid,
+        // or at zero where no bid is held there; bids are at least one, so the two never coincide.
+        let mut var_entered = var_edges.enter(inner);
+        let mut var_priced =
+            var_entered.join(&mut var_held)
+                       .map_inverse(|(r,((l,c),(p,h,hc)))| (l,(p,c,r,(h,hc))), |(l,(p,c,r,(h,hc)))| (r,((l,c),(p,h,hc))));
+        let mut var_free = antijoin_u!(var_entered, var_held, *explanation_scope);
+        let mut var_unpriced = var_free.map_inverse(|(r,(l,c))| (l,(0,c,r,(u32::max_value(),0))), |(l,(_,c,r,_))| (r,(l,c)));
+        let mut var_offers = var_priced.concat(&mut var_unpriced);
+
+        // only left nodes holding no bid take up offers.
+        let mut var_holders = permute!(var_held, (r,(p,l,c)) => (l,(r,p,c)));
+        let mut var_open = antijoin_u!(var_offers, var_holders, *explanation_scope);
+
+        // each open left node bids on its cheapest offer, costs and then right nodes breaking ties, if the offer
+        // is worth taking; the bid is one more than the second cheapest offer, or than the value of an edge if
+        // that is less, or there is no second offer.
+        let mut var_best = min!(var_open, |x| x, *explanation_scope);
+        let mut var_rest = except!(var_open, var_best, *explanation_scope);
+        let mut var_second = min!(var_rest, |x| x, *explanation_scope);
+        let mut var_worth = var_best.filter(move |&(_,(p,_,_,_))| p < value);
+        let mut var_contested = var_worth.join(&mut var_second);
+        let mut var_raised = min!(var_contested, move |((_,c,r,_),(p,_,_,_))| (::std::cmp::min(p, value) + 1, r, c), *explanation_scope);
+        let mut var_alone = antijoin_u!(var_worth, var_second, *explanation_scope);
+        let mut var_alone = min!(var_alone, move |(_,c,r,_)| (value + 1, r, c), *explanation_scope);
+        let mut var_bids = var_raised.concat(&mut var_alone);
+        let mut var_bids = permute!(var_bids, (l,(b,r,c)) => (r,(b,l,c)));
+
+        // each right node holds the highest bid it has, the least left node breaking ties; a new bid is always
+        // higher than the price it was made at, and so displaces the bid held.
+        let mut var_candidates = var_held.concat(&mut var_bids);
+        let mut var_highest = min!(var_candidates, |(b,l,c)| (u32::max_value() - b, l, c), *explanation_scope);
+        let mut var_next = var_highest.map_inverse(|(r,(n,l,c))| (r,(u32::max_value() - n,l,c)), |(r,(b,l,c))| (r,(u32::max_value() - b,l,c)));
+
+        var_held.set_u(&mut var_next);
+
+        // each left node holds at most one bid, which names its matched edge.
+        let mut var_holding = permute!(var_next, (r,(p,l,c)) => (l,(r,p,c)));
+        let mut var_matched = min!(var_holding, |(r,_p,c)| (r,c), *explanation_scope);
+        leave!(var_matched, *explanation_scope)
+    })
+}
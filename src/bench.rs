@@ -0,0 +1,175 @@
+//! Support for measuring the overhead of explanation instrumentation.
+//!
+//! The `bench-cc` example uses these to run connected components with and without instrumentation on the
+//! same synthetic graph, and to report load throughput, per-round latencies, the growth in resident memory, and
+//! the number of allocations for each.
+
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::time::{Duration, Instant};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rand::{Rng, SeedableRng, StdRng};
+
+/// Generates `edges` random edges among `nodes` nodes, deterministically from `seed`.
+pub fn random_graph(nodes: u32, edges: usize, seed: usize) -> Vec<(u32, u32)> {
+    let seed: &[_] = &[seed, 1, 2, 3];
+    let mut rng: StdRng = SeedableRng::from_seed(seed);
+    (0 .. edges).map(|_| (rng.gen_range(0, nodes), rng.gen_range(0, nodes))).collect()
+}
+
+/// Reports the resident memory of the process in bytes, where the platform makes this available.
+pub fn resident_bytes() -> Option<usize> {
+    let mut statm = String::new();
+    if File::open("/proc/self/statm").and_then(|mut file| file.read_to_string(&mut statm)).is_err() {
+        return None;
+    }
+    statm.split_whitespace()
+         .nth(1)
+         .and_then(|pages| pages.parse::<usize>().ok())
+         .map(|pages| pages * 4096)
+}
+
+/// The system allocator, counting the allocations made through it by any thread.
+///
+/// Binaries install it with `#[global_allocator]`, after which `allocations` reports the count; reallocations
+/// count as allocations, as growing a vector is as costly as the allocation it replaces.
+pub struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, size)
+    }
+}
+
+/// Reports the number of allocations made by the process, if `Counting` is installed to count them.
+pub fn allocations() -> Option<usize> {
+    match ALLOCATIONS.load(Ordering::Relaxed) {
+        0 => None,
+        count => Some(count),
+    }
+}
+
+/// Measurements taken from one configuration of a computation.
+pub struct Measurement {
+    /// A name for the configuration, e.g. "instrumented".
+    pub name: String,
+    /// Number of input records loaded initially.
+    pub records: usize,
+    /// Time taken to load and process the initial input.
+    pub load: Duration,
+    /// Time taken for each subsequent round (update or query).
+    pub rounds: Vec<Duration>,
+    /// Growth in resident memory from the measurement's creation to the final round, if available.
+    pub memory: Option<usize>,
+    /// Allocations from the measurement's creation to the final round, if counted.
+    pub allocations: Option<usize>,
+    // resident memory and allocations when the measurement was created.
+    resident: Option<usize>,
+    allocated: Option<usize>,
+}
+
+impl Measurement {
+    /// Creates an empty measurement for configuration `name`, whose memory and allocations are measured from now.
+    ///
+    /// Configurations measured one after the other in a process should each be created just before they start,
+    /// so that memory kept by earlier configurations is not counted against later ones.
+    pub fn new(name: &str) -> Measurement {
+        Measurement {
+            name: name.to_owned(),
+            records: 0,
+            load: Duration::new(0, 0),
+            rounds: Vec::new(),
+            memory: None,
+            allocations: None,
+            resident: resident_bytes(),
+            allocated: allocations(),
+        }
+    }
+    /// Records the time since `start` as the time to load `records` input records.
+    pub fn loaded(&mut self, records: usize, start: Instant) {
+        self.records = records;
+        self.load = start.elapsed();
+    }
+    /// Records the time since `start` as the duration of one round.
+    pub fn round(&mut self, start: Instant) {
+        self.rounds.push(start.elapsed());
+    }
+    /// Records the growth in resident memory, and the allocations, since the measurement was created.
+    pub fn finish(&mut self) {
+        self.memory = match (resident_bytes(), self.resident) {
+            (Some(now), Some(then)) => Some(now.saturating_sub(then)),
+            _ => None,
+        };
+        self.allocations = match (allocations(), self.allocated) {
+            (Some(now), Some(then)) => Some(now - then),
+            _ => None,
+        };
+    }
+    /// Input records processed per second during loading.
+    pub fn throughput(&self) -> f64 {
+        self.records as f64 / seconds(self.load)
+    }
+    /// The median round latency, if any rounds were recorded.
+    pub fn median(&self) -> Option<Duration> {
+        let mut rounds = self.rounds.clone();
+        rounds.sort();
+        rounds.get(rounds.len() / 2).cloned()
+    }
+    /// The maximum round latency, if any rounds were recorded.
+    pub fn max(&self) -> Option<Duration> {
+        self.rounds.iter().max().cloned()
+    }
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{}:\tload {:?} ({:.0} records/s)", self.name, self.load, self.throughput()));
+        if let (Some(median), Some(max)) = (self.median(), self.max()) {
+            try!(write!(f, "\trounds {} (median {:?}, max {:?})", self.rounds.len(), median, max));
+        }
+        if let Some(memory) = self.memory {
+            try!(write!(f, "\tresident +{} MB", memory >> 20));
+        }
+        if let Some(allocations) = self.allocations {
+            try!(write!(f, "\tallocations {}", allocations));
+        }
+        Ok(())
+    }
+}
+
+/// Compares an instrumented measurement against an uninstrumented baseline.
+pub fn overhead(baseline: &Measurement, instrumented: &Measurement) -> String {
+    let mut report = format!("load overhead:\t{:.2}x", seconds(instrumented.load) / seconds(baseline.load));
+    if let (Some(base), Some(inst)) = (baseline.median(), instrumented.median()) {
+        report.push_str(&format!("\tmedian round overhead:\t{:.2}x", seconds(inst) / seconds(base)));
+    }
+    // a baseline that did not grow the process has no ratio to report.
+    if let (Some(base), Some(inst)) = (baseline.memory, instrumented.memory) {
+        if base > 0 {
+            report.push_str(&format!("\tmemory overhead:\t{:.2}x", inst as f64 / base as f64));
+        }
+    }
+    if let (Some(base), Some(inst)) = (baseline.allocations, instrumented.allocations) {
+        if base > 0 {
+            report.push_str(&format!("\tallocation overhead:\t{:.2}x", inst as f64 / base as f64));
+        }
+    }
+    report
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + (duration.subsec_nanos() as f64 / 1000000000.0)
+}
@@ -0,0 +1,110 @@
+//! Per-query budgets, bounding the work a single query can demand of an explanation.
+//!
+//! Most queries are explained by a handful of records in a few rounds, but a query about a giant component may
+//! require most of the input, and keep the correction loop busy for as long as it takes to gather it. A `Budget`
+//! caps the records each query may require of an input and the correction rounds in which it may add to them.
+//! Requirements beyond the budget are dropped rather than accumulated, so that the query receives a partial
+//! explanation and a `Truncated` diagnostic saying so, and the rest of the cluster is not held up on its behalf.
+//!
+//! A partial explanation need not reproduce its output; it is the records found before the budget ran out, and
+//! is reported as such so that no driver mistakes it for a complete one.
+
+use std::rc::Rc;
+use std::collections::HashMap;
+
+use timely::dataflow::*;
+use timely::dataflow::scopes::Child;
+use timely::dataflow::operators::*;
+use timely::dataflow::channels::pact::Exchange;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::{Data, Collection};
+use differential_dataflow::operators::*;
+use differential_dataflow::lattice::Lattice;
+
+use Round;
+use diagnostics::Diagnostic;
+
+/// Limits on the requirements of a single query.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Budget {
+    /// The most distinct records the query may require of each input.
+    pub records: usize,
+    /// The last correction round in which the query may add to its requirements.
+    pub rounds: Round,
+}
+
+impl Budget {
+    /// A budget that never truncates anything.
+    pub fn unlimited() -> Budget {
+        Budget { records: usize::max_value(), rounds: Round::max_value() }
+    }
+}
+
+/// Admits the additions to each query's requirements while they remain within `budget(query)`.
+///
+/// `additions` should hold distinct records, as after a `threshold`, each tagged with the query `query` extracts.
+/// Returns the admitted records, and the dropped records for which some query's budget was exceeded. Records
+/// are admitted in the order their rounds complete, and within a round in sorted order, so that the same
+/// additions truncate the same way whatever the number of workers. A dropped record is not admitted later,
+/// even should a retraction free room for it, as the partial explanation is only a best effort; retracting an
+/// admitted record frees its place for records added afterwards. Epochs whose rounds overlap may truncate
+/// differently depending on which rounds complete first.
+pub fn limit<G, D, F, B>(additions: &Collection<Child<G, Round>, D>, query: F, budget: B) -> (Collection<Child<G, Round>, D>, Collection<Child<G, Round>, D>)
+    where G: Scope, D: Data, F: Fn(&D)->u32+'static, B: Fn(u32)->Budget+'static {
+
+    // each query's budget is tracked at one worker, which sees all of its records.
+    let query = Rc::new(query);
+    let route = query.clone();
+    let exchange = Exchange::new(move |x: &(D, i32)| route(&x.0) as u64);
+
+    // records admitted for each query, and updates awaiting their time's completion.
+    let mut admitted = HashMap::<u32, Vec<D>>::new();
+    let mut pending = Vec::<(Product<G::Timestamp, Round>, Vec<(D, i32)>)>::new();
+    let limited = additions.inner.unary_notify(exchange, "Budget", vec![], move |input, output, notificator| {
+
+        while let Some((time, data)) = input.next() {
+            if let Some(position) = pending.iter().position(|x| x.0 == time.time()) {
+                pending[position].1.extend(data.drain(..));
+            }
+            else {
+                pending.push((time.time(), data.drain(..).collect()));
+            }
+            notificator.notify_at(time);
+        }
+
+        while let Some((time, _count)) = notificator.next() {
+            if let Some(position) = pending.iter().position(|x| x.0 == time.time()) {
+                let (_, mut batch) = pending.swap_remove(position);
+                batch.sort();
+                let mut session = output.session(&time);
+                for (record, weight) in batch {
+                    let q = query(&record);
+                    let within = budget(q);
+                    let records = admitted.entry(q).or_insert_with(Vec::new);
+                    let present = records.iter().position(|x| x == &record);
+                    match (present, weight > 0) {
+                        (Some(_), true) => session.give(((record, true), weight)),
+                        (Some(position), false) => { records.swap_remove(position); session.give(((record, true), weight)); },
+                        (None, true) if time.time().inner <= within.rounds && records.len() < within.records => {
+                            records.push(record.clone());
+                            session.give(((record, true), weight));
+                        },
+                        (None, _) => session.give(((record, false), weight)),
+                    }
+                }
+            }
+        }
+    });
+
+    let limited = Collection::new(limited);
+    (limited.filter(|x| x.1).map(|(x,_)| x), limited.filter(|x| !x.1).map(|(x,_)| x))
+}
+
+/// Reports a `Truncated` diagnostic for each query with some `dropped` record, as returned by `limit`.
+pub fn truncated<G, D, F>(dropped: &Collection<G, D>, query: F) -> Collection<G, Diagnostic>
+    where G: Scope, D: Data, F: Fn(&D)->u32+'static, G::Timestamp: Lattice {
+    dropped.map(move |x| query(&x))
+           .threshold(|_, w| if w > 0 { 1 } else { 0 })
+           .map(|q| Diagnostic::Truncated(q))
+}
@@ -0,0 +1,73 @@
+//! Explanations cached across queries, and reused while the input records they name remain.
+//!
+//! Explaining a query runs the correction fixpoint to completion, which is wasted work when the same question was
+//! answered before and the inputs have since changed only elsewhere. A `Cache` holds must-sets by query, with the
+//! version of the inputs each was derived from. Re-asking at the same version returns the cached must-set as is;
+//! at a later version the cache checks that each cached record is still present in its input, which is cheap next
+//! to a fixpoint, and returns the must-set only if so. The cache is ordinary data, held by a driver outside any
+//! dataflow, so it outlives the sessions that fill it.
+//!
+//! A must-set whose records all remain still reproduces its output, as the records it was computed from are
+//! unchanged. Whether the output itself still holds depends on the rest of the input, and is for the driver to
+//! know, just as it knows which outputs are worth asking about. The connected components session in `interactive`
+//! is one such driver: added records may lower a label, but deleted ones cannot, so it consults the cache only
+//! for must-sets cached since the last addition.
+
+use std::hash::Hash;
+use std::collections::HashMap;
+
+/// A cached must-set, and the input version it was last valid at.
+struct Entry<D> {
+    version: u64,
+    records: Vec<Vec<D>>,
+}
+
+/// Must-sets of queries of type `Q`, over input records of type `D`.
+pub struct Cache<Q, D> {
+    entries: HashMap<Q, Entry<D>>,
+}
+
+impl<Q: Eq+Hash, D> Cache<Q, D> {
+    /// A new, empty cache.
+    pub fn new() -> Self {
+        Cache { entries: HashMap::new() }
+    }
+    /// Stores the must-set explaining `query` at input version `version`, as the required records of each input.
+    pub fn insert(&mut self, query: Q, version: u64, records: Vec<Vec<D>>) {
+        self.entries.insert(query, Entry { version: version, records: records });
+    }
+    /// The cached must-set of `query`, if it is still valid at input version `version`.
+    ///
+    /// A must-set cached at `version` is returned as is. One cached at another version is returned only if each
+    /// of its records is still `present` in its input, as `(input, record)`, and is then recorded as valid at
+    /// `version`; otherwise it is discarded, and `query` must be explained afresh.
+    pub fn get<F: Fn(usize, &D)->bool>(&mut self, query: &Q, version: u64, present: F) -> Option<&[Vec<D>]> {
+        let valid = match self.entries.get(query) {
+            Some(entry) => entry.version == version || entry.records.iter().enumerate().all(|(input, records)| records.iter().all(|x| present(input, x))),
+            None => return None,
+        };
+        if !valid {
+            self.entries.remove(query);
+            return None;
+        }
+        let entry = self.entries.get_mut(query).unwrap();
+        entry.version = version;
+        Some(&entry.records[..])
+    }
+    /// The input version the must-set of `query` was last valid at, if one is cached.
+    pub fn version(&self, query: &Q) -> Option<u64> {
+        self.entries.get(query).map(|entry| entry.version)
+    }
+    /// Discards the must-set of `query`, for example once it is withdrawn.
+    pub fn remove(&mut self, query: &Q) {
+        self.entries.remove(query);
+    }
+    /// The number of cached must-sets.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Whether no must-sets are cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,359 @@
+//! Connected components, instrumented for explanation.
+//!
+//! Labels propagate along symmetrized edges, each node keeping the least label it has seen, with initial labels
+//! introduced in order of their logarithm so that small labels win early. Queries name `(node, label)` outputs
+//! and are answered with the graph and label records required to reproduce them.
+//!
+//! The uninstrumented computation is also available, along with a check that a set of required records does
+//! reproduce the queried outputs when the computation is run on those records alone, and a probe for records
+//! that could be removed with the queried outputs still reproduced.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use timely::dataflow::*;
+use timely::dataflow::scopes::Child;
+use timely::dataflow::operators::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+use differential_dataflow::lattice::Lattice;
+
+use {Variable, MonotonicVariable, Round, converged, dedup_queries, fan_out, count_at_query, retired_queries};
+use algos;
+use diagnostics::{self, Diagnostic};
+use budget::{self, Budget};
+use testing::Dataflow;
+use replay::{self, Computation};
+
+/// Explains connected components for a collection of queries.
+///
+/// Returns the required graph and label records, each tagged by the query requiring them, markers for the
+/// first correction round in which each query's requirements stopped changing, diagnostics for queries
+/// that could not be explained as asked, and required records missing from the inputs, as `(input, record, query)`
+/// with `input` 0 for graph records and 1 for label records, weighted by the copies missing. The `prior_*` collections are
+/// requirements carried over from the previous epoch, which seed the correction loop rather than having to be
+/// re-derived round by round. Only those of queries still asked, and of records still present, are carried over;
+/// the requirements of withdrawn queries are retired from the loop once their withdrawal completes.
+///
+/// Queries are `(node, label, time, query)`, and are explained by input records as they were at `time`: records
+/// inserted later are never required, and records deleted later remain required.
+/// A query with time `(u32::max_value(), Round::max_value())` covers the whole history of the output, and so
+/// continues to be explained as the inputs change. Queries asking the same question under different identifiers
+/// share one explanation, which is reported under each identifier.
+///
+/// Epochs need not complete before later epochs are introduced. The requirements reported at each epoch depend
+/// only on the inputs and queries up to that epoch, and on the requirements fed back from the epoch before it,
+/// however many epochs are in flight at once.
+pub fn explain<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(
+    streaming: &mut G,
+    graph: &Collection<G, (u32, u32)>,
+    label: &Collection<G, (u32, u32)>,
+    query: &Collection<G, (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32)>,
+    prior_graph: &Collection<G, ((u32, u32), u32)>,
+    prior_label: &Collection<G, ((u32, u32), u32)>)
+-> (Collection<G, ((u32, u32), u32)>, Collection<G, ((u32, u32), u32)>, Collection<G, (u32, Round)>, Collection<G, Diagnostic>, Collection<G, (u32, (u32, u32), u32)>) {
+    explain_with(streaming, graph, label, query, prior_graph, prior_label, None, None)
+}
+
+/// As `explain`, but with further queries derived from the computed labels by `derive`.
+///
+/// `derive` is applied to the `(node, label)` outputs within the correction scope, and returns queries in the
+/// same form as `query`, which are seeded into the explanation alongside it; an anomaly detector over the labels
+/// might ask about each label it finds suspicious, without a driver relaying its findings back as input. Derived
+/// queries are reported under their own identifiers, which should be distinct from those of external queries,
+/// and are not shared with identical external queries.
+pub fn explain_derived<G, F>(
+    streaming: &mut G,
+    graph: &Collection<G, (u32, u32)>,
+    label: &Collection<G, (u32, u32)>,
+    query: &Collection<G, (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32)>,
+    prior_graph: &Collection<G, ((u32, u32), u32)>,
+    prior_label: &Collection<G, ((u32, u32), u32)>,
+    derive: F)
+-> (Collection<G, ((u32, u32), u32)>, Collection<G, ((u32, u32), u32)>, Collection<G, (u32, Round)>, Collection<G, Diagnostic>, Collection<G, (u32, (u32, u32), u32)>)
+where G: Scope<Timestamp=Product<RootTimestamp, u32>>,
+      F: for<'b> Fn(&Collection<Child<'b, G, Round>, (u32, u32)>)->Collection<Child<'b, G, Round>, (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32)>+'static {
+    explain_with(streaming, graph, label, query, prior_graph, prior_label, Some(Box::new(derive)), None)
+}
+
+/// As `explain`, but with the requirements of each query limited to `budget(query)`.
+///
+/// Each query may require at most the budgeted number of graph records and of label records, and may add to its
+/// requirements only up to the budgeted correction round; see `budget::limit`. A query whose requirements exceed
+/// its budget is reported with a partial explanation and a `Truncated` diagnostic, and otherwise costs no more
+/// than its budget, however much of the input its output depends on. Requirements carried over in `prior_*`
+/// were limited when first derived, and are not counted again.
+pub fn explain_budgeted<G, B>(
+    streaming: &mut G,
+    graph: &Collection<G, (u32, u32)>,
+    label: &Collection<G, (u32, u32)>,
+    query: &Collection<G, (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32)>,
+    prior_graph: &Collection<G, ((u32, u32), u32)>,
+    prior_label: &Collection<G, ((u32, u32), u32)>,
+    budget: B)
+-> (Collection<G, ((u32, u32), u32)>, Collection<G, ((u32, u32), u32)>, Collection<G, (u32, Round)>, Collection<G, Diagnostic>, Collection<G, (u32, (u32, u32), u32)>)
+where G: Scope<Timestamp=Product<RootTimestamp, u32>>,
+      B: Fn(u32)->Budget+'static {
+    explain_with(streaming, graph, label, query, prior_graph, prior_label, None, Some(Rc::new(budget)))
+}
+
+// the explanation behind `explain`, `explain_derived`, and `explain_budgeted`, with queries derived from the labels
+// only if `derive` is set, and requirements limited only if `budget` is set.
+fn explain_with<G>(
+    streaming: &mut G,
+    graph: &Collection<G, (u32, u32)>,
+    label: &Collection<G, (u32, u32)>,
+    query: &Collection<G, (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32)>,
+    prior_graph: &Collection<G, ((u32, u32), u32)>,
+    prior_label: &Collection<G, ((u32, u32), u32)>,
+    derive: Option<Box<for<'b> Fn(&Collection<Child<'b, G, Round>, (u32, u32)>)->Collection<Child<'b, G, Round>, (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32)>>>,
+    budget: Option<Rc<Fn(u32)->Budget>>)
+-> (Collection<G, ((u32, u32), u32)>, Collection<G, ((u32, u32), u32)>, Collection<G, (u32, Round)>, Collection<G, Diagnostic>, Collection<G, (u32, (u32, u32), u32)>)
+where G: Scope<Timestamp=Product<RootTimestamp, u32>> {
+
+    let graph = graph.clone();
+    let label = label.clone();
+    // identical queries share one explanation, reported to each of them at the end.
+    let (query, aliases) = dedup_queries(query);
+    let prior_graph = prior_graph.clone();
+    let prior_label = prior_label.clone();
+
+    // Iterative scope for rounds of input correction
+    let (graph_must, label_must, converged, diagnostics, shortfall, derived) = streaming.scoped::<Round,_,_>(move |correction| {
+
+        // Bring each input into the scope.
+        let graph = graph.enter(correction);
+        let label = label.enter(correction);
+        let query = query.enter(correction);
+
+        // queries about times before any graph or label data are explained by nothing, and reported as such.
+        let before_data = diagnostics::before_data(&query, &graph.map(|_| ()).concat(&label.map(|_| ())));
+
+        // Each data input uses a MonotonicVariable to track its elements required to explain outputs.
+        // These collections grow monotonically in each round of correction, limited by the full set.
+        // Elements are tagged with the query requiring them, so that a batch of queries can share a
+        // fixpoint and still have their explanations reported separately.
+        let mut graph_must = MonotonicVariable::new(correction);
+        let mut label_must = MonotonicVariable::new(correction);
+
+        // requirements from the previous epoch, of queries still asked and records still present, are required
+        // from the first round; as explanations cover the full history of queried outputs, they would be
+        // re-derived anyway. requirements of withdrawn queries are not carried over, and are retired from the
+        // loop's accumulated state once the withdrawal completes.
+        let live = query.map(|(_,_,_,q)| q).threshold(|_, w| if w > 0 { 1 } else { 0 });
+        let retired = retired_queries(&query, |x| x.3);
+        graph_must.add(&carried(&prior_graph.enter(correction), &live, &graph));
+        label_must.add(&carried(&prior_label.enter(correction), &live, &label));
+        graph_must.retire_by(&retired, |x| x.1);
+        label_must.retire_by(&retired, |x| x.1);
+
+        // the working inputs are the union of requirements across all queries, where still present; records
+        // required by queries about earlier epochs may since have been deleted.
+        let graph_work = graph_must.stream.map(|(x,_q)| (x,())).semijoin(&graph).map(|(x,())| x).threshold(|_, w| if w > 0 { 1 } else { 0 });
+        let label_work = label_must.stream.map(|(x,_q)| (x,())).semijoin(&label).map(|(x,())| x).threshold(|_, w| if w > 0 { 1 } else { 0 });
+
+        // Scope for explanation derivation.
+        let child_scope = RefCell::new(correction.new_subscope());
+        let child_index = child_scope.borrow().index;
+
+        // determine and return necessary members of `graph` and `label`.
+        let (graph_need, label_need, query, derived, not_found) = {
+
+            // wrap an explanation scope builder.
+            let mut explanation_scope = Child {
+                subgraph: &child_scope,
+                parent: correction.clone(),
+            };
+    
+            // define variables for each input to the computation.
+            // the data source is from outside the correction loop,
+            // and the working source are the *_must collections.
+            let mut var_graph = Variable::new(graph.clone(), graph_work, &mut explanation_scope);
+            let mut var_label = Variable::new(label.clone(), label_work, &mut explanation_scope);
+
+            if cfg!(feature = "check-working") {
+                var_graph.check_working("cc/graph");
+                var_label.check_working("cc/label");
+            }
+
+            // labels propagate along the graph; see `algos::connected_components`.
+            let mut final_labels = algos::connected_components(&mut var_graph, &mut var_label, &mut explanation_scope);
+
+            // queries derived from the labels, if any, join the external queries from here on.
+            let derived = derive.map(|derive| derive(&final_labels.stream));
+            let query = match derived {
+                Some(ref derived) => query.concat(derived),
+                None => query,
+            };
+
+            // introduce any query elements as initial dependences.
+            final_labels.seed(&query);
+
+            // queries naming labels that do not exist cannot be explained, and are reported instead.
+            let not_found = diagnostics::not_found(&query, &final_labels.stream);
+
+            // pop input requirements out of the explanation scope and return them.
+            (var_graph.depends.stream.leave(), var_label.depends.stream.leave(), query, derived, not_found)
+        };
+
+        // all explanation infrastructure in place; add to correct scope.
+        correction.add_operator_with_index(child_scope.into_inner(), child_index);
+
+        // intersect required edges and labels with edges and labels as of each query's time, so that updates
+        // after the time a query asks about neither add to nor remove from its explanation.
+        let graph_available = count_at_query(&graph_need, &graph, &query);
+        let label_available = count_at_query(&label_need, &label, &query);
+        let graph_additions = graph_available.threshold(|_, w| if w > 0 { 1 } else { 0 });
+        let label_additions = label_available.threshold(|_, w| if w > 0 { 1 } else { 0 });
+
+        // requirements beyond a query's budget are dropped, and the query reported as truncated.
+        let (graph_additions, label_additions, truncated) = match budget {
+            Some(budget) => {
+                let other = budget.clone();
+                let (graph_admitted, graph_dropped) = budget::limit(&graph_additions, |x| x.1, move |q| budget(q));
+                let (label_admitted, label_dropped) = budget::limit(&label_additions, |x| x.1, move |q| other(q));
+                let truncated = budget::truncated(&graph_dropped.map(|(_,q)| q).concat(&label_dropped.map(|(_,q)| q)), |&q| q);
+                (graph_admitted, label_admitted, Some(truncated))
+            },
+            None => (graph_additions, label_additions, None),
+        };
+        graph_must.add(&graph_additions);
+        label_must.add(&label_additions);
+
+        // required records the intersection drops for want of copies are reported, rather than quietly omitted.
+        let shortfall = diagnostics::shortfall(&graph_need, &graph_available).map(|(x,q)| (0,x,q))
+                                  .concat(&diagnostics::shortfall(&label_need, &label_available).map(|(x,q)| (1,x,q)));
+        let insufficient = shortfall.map(|(_,_,q)| q)
+                                    .threshold(|_, w| if w > 0 { 1 } else { 0 })
+                                    .map(|q| Diagnostic::InsufficientCopies(q));
+
+        // report the first round in which each query's requirements stopped changing.
+        let converged = converged(&graph_must.stream.concat(&label_must.stream), |x| x.1);
+
+        // report any query, external or derived, whose requirements are still changing after many rounds.
+        let asked = query.map(|(_,_,_,q)| q).threshold(|_, w| if w > 0 { 1 } else { 0 });
+        let exhausted = diagnostics::exhausted(&graph_must.stream.concat(&label_must.stream), &asked, diagnostics::ROUND_LIMIT, |x| x.1);
+
+        // merge the things we need, pop them out of the loop, and probe
+        let diagnostics = not_found.concat(&exhausted).concat(&before_data).concat(&insufficient);
+        let diagnostics = match truncated {
+            Some(truncated) => diagnostics.concat(&truncated),
+            None => diagnostics,
+        };
+        (graph_must.stream.leave(), label_must.stream.leave(), converged.leave(), diagnostics.leave(), shortfall.leave(), derived.map(|derived| derived.map(|(_,_,_,q)| q).leave()))
+    });
+
+    // derived queries are their own representatives.
+    let aliases = match derived {
+        Some(derived) => aliases.concat(&derived.map(|q| (q,q))).threshold(|_, w| if w > 0 { 1 } else { 0 }),
+        None => aliases,
+    };
+
+    (fan_out(&graph_must, &aliases),
+     fan_out(&label_must, &aliases),
+     fan_out(&converged.map(|(q,r)| (r,q)), &aliases).map(|(r,q)| (q,r)),
+     fan_out(&diagnostics.map(|d| (d, d.query())), &aliases).map(|(d,q)| d.for_query(q)),
+     fan_out(&shortfall.map(|(i,x,q)| ((i,x),q)), &aliases).map(|((i,x),q)| (i,x,q)))
+}
+
+// the `prior` requirements of `live` queries on records still in `input`, to be carried into the next epoch.
+fn carried<G: Scope>(prior: &Collection<G, ((u32, u32), u32)>, live: &Collection<G, u32>, input: &Collection<G, (u32, u32)>) -> Collection<G, ((u32, u32), u32)>
+    where G::Timestamp: Lattice+Ord {
+    prior.map(|(x,q)| (q,x))
+         .semijoin(live)
+         .map(|(q,x)| (x,q))
+         .semijoin(input)
+}
+
+/// The instrumented computation as a `testing::Dataflow`, with graph and label inputs, in that order.
+///
+/// Its must-sets are the graph and label requirements reported by `explain`, with requirements fed back to
+/// seed the following epoch as drivers do.
+pub struct Instrumented;
+
+impl Dataflow for Instrumented {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, ((u32, u32), u32)>> {
+
+        let (prior_graph_handle, prior_graph) = scope.loop_variable(u32::max_value(), 1);
+        let (prior_label_handle, prior_label) = scope.loop_variable(u32::max_value(), 1);
+        let prior_graph = Collection::new(prior_graph);
+        let prior_label = Collection::new(prior_label);
+
+        let (graph_must, label_must, _, _, _) = explain(scope, &inputs[0], &inputs[1], queries, &prior_graph, &prior_label);
+        graph_must.inner.connect_loop(prior_graph_handle);
+        label_must.inner.connect_loop(prior_label_handle);
+
+        vec![graph_must, label_must]
+    }
+}
+
+/// Connected components, without instrumentation.
+///
+/// Each node with a label is labeled by the least label reachable from it along the symmetrized edges.
+pub fn components<G: Scope>(graph: &Collection<G, (u32, u32)>, label: &Collection<G, (u32, u32)>) -> Collection<G, (u32, u32)>
+    where G::Timestamp: Lattice+Ord {
+
+    let edges = graph.map(|(x,y)| (y,x)).concat(&graph);
+    label.iterate(|inner| {
+        let edges = edges.enter(&inner.scope());
+        let label = label.enter(&inner.scope());
+        inner.join_u(&edges)
+             .map(|(_,l,d)| (d,l))
+             .concat(&label)
+             .group_u(|_, s, t| if let Some((l, _)) = s.next() { t.push((*l, 1)) })
+    })
+}
+
+/// `components` as a `replay::Computation`, with the graph and label as its two inputs.
+#[derive(Copy, Clone, Debug)]
+pub struct Components;
+
+impl Computation for Components {
+    type Data = (u32, u32);
+    type Output = (u32, u32);
+    fn build<G: Scope>(&self, inputs: &[Collection<G, (u32, u32)>]) -> Collection<G, (u32, u32)> where G::Timestamp: Lattice+Ord {
+        components(&inputs[0], &inputs[1])
+    }
+}
+
+/// Runs `components` on just the supplied `graph` and `label` records, and returns those `queries` that it does
+/// not reproduce.
+///
+/// An empty result means the records are sufficient to explain the queried outputs. See `replay::missing`.
+pub fn sufficient(graph: &[(u32, u32)], label: &[(u32, u32)], queries: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    replay::missing(&Components, &[graph.to_vec(), label.to_vec()], queries)
+}
+
+/// Probes up to `limit` of the supplied records, returning the graph and label records without which `sufficient`
+/// still reproduces all of the `queries`.
+///
+/// Each probed record costs a run of the computation. Any record returned is evidence that the explanation is not
+/// minimal, though an empty result does not guarantee minimality: two records may each be redundant only because
+/// of the other, and records beyond `limit` are not probed. Graph records are probed before label records.
+pub fn redundant(graph: &[(u32, u32)], label: &[(u32, u32)], queries: &[(u32, u32)], limit: usize) -> (Vec<(u32, u32)>, Vec<(u32, u32)>) {
+
+    let mut graph_redundant = Vec::new();
+    let mut label_redundant = Vec::new();
+
+    for index in 0 .. ::std::cmp::min(limit, graph.len()) {
+        let fewer = graph.iter().enumerate().filter(|x| x.0 != index).map(|x| *x.1).collect::<Vec<_>>();
+        if sufficient(&fewer, label, queries).is_empty() {
+            graph_redundant.push(graph[index]);
+        }
+    }
+
+    for index in 0 .. ::std::cmp::min(limit.saturating_sub(graph.len()), label.len()) {
+        let fewer = label.iter().enumerate().filter(|x| x.0 != index).map(|x| *x.1).collect::<Vec<_>>();
+        if sufficient(graph, &fewer, queries).is_empty() {
+            label_redundant.push(label[index]);
+        }
+    }
+
+    (graph_redundant, label_redundant)
+}
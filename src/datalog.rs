@@ -0,0 +1,321 @@
+//! A small Datalog dialect, compiled to instrumented dataflow.
+//!
+//! Programs declare input relations and give rules deriving further relations from them, for example
+//!
+//! ```text
+//! input edge.
+//! path(x, y) :- edge(x, y).
+//! path(x, z) :- path(x, y), edge(y, z).
+//! ```
+//!
+//! All relations are binary, over `u32`. A rule body is one atom, or two atoms sharing exactly one variable, and
+//! the head names two distinct variables from the body. Text after `%` on a line is a comment. Derived relations
+//! may be recursive, and all are computed in one loop; each derived fact is explained by one derivation of it,
+//! and recursively by the facts that derivation used, down to input facts.
+
+use timely::dataflow::*;
+use timely::dataflow::scopes::Child;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+
+use {Variable, VariableFeedback, Round};
+use algos::{pair_key, key_pair};
+use diagnostics::{self, Diagnostic};
+use generic;
+
+/// A relation applied to two variables.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Atom {
+    /// The name of the relation.
+    pub relation: String,
+    /// The names of the variables in each position.
+    pub args: (String, String),
+}
+
+/// A rule deriving its head from the atoms of its body.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    /// The derived atom.
+    pub head: Atom,
+    /// One or two atoms which together imply the head.
+    pub body: Vec<Atom>,
+}
+
+/// A parsed program: its input relations, the relations it derives, and the rules deriving them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+    /// Input relations, in order of declaration.
+    pub inputs: Vec<String>,
+    /// Derived relations, in order of their first appearance as a rule head.
+    pub outputs: Vec<String>,
+    /// The rules, in order of appearance.
+    pub rules: Vec<Rule>,
+}
+
+// where an atom's relation comes from.
+enum Source {
+    Input(usize),
+    Output(usize),
+}
+
+impl Program {
+    /// Parses and checks a program, returning a description of the first problem found.
+    pub fn parse(text: &str) -> Result<Program, String> {
+
+        let mut program = Program { inputs: Vec::new(), outputs: Vec::new(), rules: Vec::new() };
+
+        // statements end with '.'; anything after the last is an error unless blank.
+        let text = text.lines().map(|line| line.split('%').next().unwrap()).collect::<Vec<_>>().join(" ");
+        let mut statements = text.split('.').map(|x| x.trim()).collect::<Vec<_>>();
+        if let Some(rest) = statements.pop() {
+            if rest.len() > 0 { return Err(format!("statement not ended by '.': {}", rest)); }
+        }
+
+        for statement in statements {
+            if statement.starts_with("input ") {
+                let name = statement["input ".len() ..].trim();
+                if !is_name(name) { return Err(format!("bad relation name: {}", name)); }
+                if program.inputs.iter().any(|x| x == name) { return Err(format!("input declared twice: {}", name)); }
+                program.inputs.push(name.to_owned());
+            }
+            else {
+                let mut parts = statement.splitn(2, ":-");
+                let head = try!(parse_atom(parts.next().unwrap()));
+                let body = match parts.next() {
+                    Some(body) => try!(split_atoms(body).into_iter().map(|x| parse_atom(x)).collect::<Result<Vec<_>, _>>()),
+                    None => return Err(format!("not a declaration or rule: {}", statement)),
+                };
+                if !program.outputs.iter().any(|x| x == &head.relation) {
+                    program.outputs.push(head.relation.clone());
+                }
+                program.rules.push(Rule { head: head, body: body });
+            }
+        }
+
+        if program.inputs.is_empty() || program.rules.is_empty() {
+            return Err("programs need at least one input and one rule".to_owned());
+        }
+        for rule in program.rules.iter() {
+            try!(program.check(rule));
+        }
+
+        Ok(program)
+    }
+
+    // checks that a rule has a form `build` can compile.
+    fn check(&self, rule: &Rule) -> Result<(), String> {
+
+        let describe = || format!("{}({}, {})", rule.head.relation, rule.head.args.0, rule.head.args.1);
+        if self.inputs.contains(&rule.head.relation) {
+            return Err(format!("{}: input relations cannot be derived", describe()));
+        }
+        if rule.head.args.0 == rule.head.args.1 {
+            return Err(format!("{}: head variables must be distinct", describe()));
+        }
+        for atom in rule.body.iter() {
+            if !self.inputs.contains(&atom.relation) && !self.outputs.contains(&atom.relation) {
+                return Err(format!("{}: unknown relation {}", describe(), atom.relation));
+            }
+            if atom.args.0 == atom.args.1 {
+                return Err(format!("{}: variables within a body atom must be distinct", describe()));
+            }
+        }
+        let variables = match rule.body.len() {
+            1 => vec![rule.body[0].args.0.clone(), rule.body[0].args.1.clone()],
+            2 => {
+                match shared(&rule.body[0], &rule.body[1]) {
+                    Some(shared) => {
+                        vec![shared.clone(), other(&rule.body[0], &shared), other(&rule.body[1], &shared)]
+                    },
+                    None => return Err(format!("{}: body atoms must share exactly one variable", describe())),
+                }
+            },
+            _ => return Err(format!("{}: bodies must have one or two atoms", describe())),
+        };
+        if !variables.contains(&rule.head.args.0) || !variables.contains(&rule.head.args.1) {
+            return Err(format!("{}: head variables must appear in the body", describe()));
+        }
+        Ok(())
+    }
+
+    // where the named relation comes from.
+    fn source(&self, relation: &str) -> Source {
+        match self.inputs.iter().position(|x| x == relation) {
+            Some(index) => Source::Input(index),
+            None => Source::Output(self.outputs.iter().position(|x| x == relation).unwrap()),
+        }
+    }
+
+    /// Builds the program's derived relations from its `inputs`, in the order of `self.inputs`, and returns them
+    /// in the order of `self.outputs`.
+    ///
+    /// Derived relations are computed together in one loop. Each rule's derivations are tagged by the rule and by
+    /// the body variable the head omits, and `min!` keeps one derivation of each fact, so that a fact with several
+    /// derivations is explained by one of them.
+    pub fn build<'a, G>(
+        &self,
+        inputs: &mut [Variable<'a, G, u32, u32, G>],
+        explanation_scope: &mut Child<'a, G, Round>) -> Vec<Variable<'a, G, u32, u32, G>>
+        where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+        assert_eq!(inputs.len(), self.inputs.len());
+
+        let mut correction = inputs[0].stream.scope();
+        correction.scoped::<u32,_,_>(|inner| {
+
+            // one loop variable for each derived relation, as rules may derive each from any of them.
+            let mut loops = VariableFeedback::family(inner, explanation_scope, self.outputs.len());
+
+            // derivations of each output, as `(pair_key(x, y), (rule, witness))`.
+            let mut derivations = (0 .. self.outputs.len()).map(|_| None).collect::<Vec<Option<Variable<_, u64, (u32, u32), _>>>>();
+
+            for (index, rule) in self.rules.iter().enumerate() {
+
+                let rule_id = index as u32;
+
+                // the body's variables, and the positions among them of the head's variables and the witness.
+                let (names, mut derived) = if rule.body.len() == 1 {
+                    let atom = &rule.body[0];
+                    let names = vec![atom.args.0.clone(), atom.args.1.clone()];
+                    let mut body = match self.source(&atom.relation) {
+                        Source::Input(i) => inputs[i].enter(inner),
+                        Source::Output(i) => loops[i].map_inverse(|x| x, |x| x),
+                    };
+                    (names, body.map_inverse(|(a,b)| (a,(b,0)), |(a,(b,_))| (a,b)))
+                }
+                else {
+                    let shared = shared(&rule.body[0], &rule.body[1]).unwrap();
+                    let names = vec![shared.clone(), other(&rule.body[0], &shared), other(&rule.body[1], &shared)];
+                    let mut sides = Vec::new();
+                    for atom in rule.body.iter() {
+                        let mut body = match self.source(&atom.relation) {
+                            Source::Input(i) => inputs[i].enter(inner),
+                            Source::Output(i) => loops[i].map_inverse(|x| x, |x| x),
+                        };
+                        // key each atom by the shared variable.
+                        sides.push(if atom.args.0 == shared { body.map_inverse(|x| x, |x| x) }
+                                   else { permute!(body, (a,b) => (b,a)) });
+                    }
+                    let mut right = sides.pop().unwrap();
+                    let mut left = sides.pop().unwrap();
+                    (names, left.join_u(&mut right))
+                };
+
+                let i = names.iter().position(|x| x == &rule.head.args.0).unwrap();
+                let j = names.iter().position(|x| x == &rule.head.args.1).unwrap();
+                // the witness is the variable the head omits; with one body atom, the placeholder zero.
+                let k = 3 - i - j;
+
+                let mut derived = derived.map_inverse(
+                    move |(a,(b,c))| { let x = [a,b,c]; (pair_key(x[i], x[j]), (rule_id, x[k])) },
+                    move |(key,(_,w))| { let (p,q) = key_pair(key); let mut x = [0,0,0]; x[i] = p; x[j] = q; x[k] = w; (x[0],(x[1],x[2])) }
+                );
+
+                let head = self.outputs.iter().position(|x| x == &rule.head.relation).unwrap();
+                derivations[head] = Some(match derivations[head].take() {
+                    Some(mut prior) => prior.concat(&mut derived),
+                    None => derived,
+                });
+            }
+
+            // keep one derivation of each fact, and present the facts as pairs.
+            let mut outputs = Vec::new();
+            for (index, derivation) in derivations.into_iter().enumerate() {
+
+                let mut derivation = derivation.unwrap();
+                let mut var_min = min!(derivation, |_| 0u32, *explanation_scope);
+                let mut relation = var_min.map_inverse(|(key,_)| key_pair(key), |(x,y)| (pair_key(x, y), 0));
+
+                loops[index].set_u(&mut relation);
+
+                outputs.push(leave!(relation, *explanation_scope));
+            }
+
+            outputs
+        })
+    }
+}
+
+/// Explains the derived relations of `program` for a collection of queries.
+///
+/// `inputs` are the program's input relations, in the order of `program.inputs`. Queries are `(relation, (x, y),
+/// time, query)`, where `relation` indexes `program.outputs`, and are explained by input records as they were at
+/// `time`. Returns the required input records as `(input, record, query)`, markers for the first correction round
+/// in which each query's requirements stopped changing, and diagnostics for queries naming facts that were not
+/// derived.
+pub fn explain<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(
+    streaming: &mut G,
+    program: &Program,
+    inputs: &[Collection<G, (u32, u32)>],
+    query: &Collection<G, (u32, (u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32)>)
+-> (Collection<G, (u32, (u32, u32), u32)>, Collection<G, (u32, Round)>, Collection<G, Diagnostic>) {
+
+    let program = program.clone();
+    generic::drive(streaming, inputs, query, move |mut vars, query, explanation_scope| {
+
+        let mut outputs = program.build(&mut vars[..], explanation_scope);
+
+        // introduce each relation's queries as initial dependences, and report those naming absent facts.
+        let mut not_found = Vec::new();
+        for (index, output) in outputs.iter_mut().enumerate() {
+            let index = index as u32;
+            let queries = query.filter(move |x| x.0 == index).map(|(_,(x,y),t,q)| (x,y,t,q));
+            output.seed(&queries);
+            not_found.push(diagnostics::not_found(&queries, &output.stream));
+        }
+        not_found.into_iter().fold(None, |all: Option<Collection<_,_>>, x| {
+            Some(match all { Some(all) => all.concat(&x), None => x })
+        }).unwrap()
+    })
+}
+
+// splits a rule body at the commas between atoms, but not those within them.
+fn split_atoms(body: &str) -> Vec<&str> {
+    let mut atoms = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in body.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => { atoms.push(&body[start .. index]); start = index + 1; },
+            _ => { },
+        }
+    }
+    atoms.push(&body[start ..]);
+    atoms
+}
+
+// parses `relation(x, y)`.
+fn parse_atom(text: &str) -> Result<Atom, String> {
+    let text = text.trim();
+    let open = try!(text.find('(').ok_or(format!("expected an atom: {}", text)));
+    if !text.ends_with(')') { return Err(format!("expected an atom: {}", text)); }
+    let relation = text[.. open].trim();
+    let args = text[open + 1 .. text.len() - 1].split(',').map(|x| x.trim()).collect::<Vec<_>>();
+    if !is_name(relation) { return Err(format!("bad relation name: {}", relation)); }
+    if args.len() != 2 || !args.iter().all(|x| is_name(x)) {
+        return Err(format!("atoms take two variables: {}", text));
+    }
+    Ok(Atom { relation: relation.to_owned(), args: (args[0].to_owned(), args[1].to_owned()) })
+}
+
+// names are non-empty runs of letters, digits, and underscores.
+fn is_name(text: &str) -> bool {
+    text.len() > 0 && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+// the single variable two atoms share, if they share exactly one.
+fn shared(atom1: &Atom, atom2: &Atom) -> Option<String> {
+    let vars1 = [&atom1.args.0, &atom1.args.1];
+    let shared = [&atom2.args.0, &atom2.args.1].iter().filter(|x| vars1.contains(x)).map(|x| (*x).clone()).collect::<Vec<_>>();
+    if shared.len() == 1 { Some(shared[0].clone()) } else { None }
+}
+
+// the variable of an atom other than `var`.
+fn other(atom: &Atom, var: &str) -> String {
+    if atom.args.0 == var { atom.args.1.clone() } else { atom.args.0.clone() }
+}
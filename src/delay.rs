@@ -0,0 +1,60 @@
+//! Strategies for delaying records as they enter an iterative scope.
+//!
+//! Prioritized iteration introduces some records to a loop later than others, so that the loop settles on the
+//! early records before it sees the rest. Connected components introduces each node's label at a round growing
+//! with the logarithm of the node, so that small labels spread first and large labels are mostly beaten before
+//! they start. A `Delay` names such a strategy, and `at` computes the round at which a record of some priority
+//! enters.
+//!
+//! Delays are used through `Variable::enter_delayed`, which also maps requirements on the delayed records back
+//! out of the loop. A record entering at round `d` is present in every round from `d` on, and so is required of
+//! the outer collection at the outer part of the requirement's time, whatever its round; strategies need no
+//! inverse, and there is none to get wrong.
+
+/// A strategy mapping a record's priority to the round in which it enters a loop.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Delay {
+    /// Every record enters at the same round.
+    Constant(u32),
+    /// Records enter at `spacing` rounds for each step of `1 / steps` in the natural logarithm of their priority,
+    /// with priorities of zero entering with those of one.
+    Logarithmic {
+        /// The steps per unit of logarithm.
+        steps: f64,
+        /// The rounds between consecutive steps.
+        spacing: u32,
+    },
+    /// Records enter at one round per `width` of their priority, in buckets of equal width. The width must be
+    /// positive, as `Delay::bucketed` checks.
+    Bucketed(u64),
+}
+
+impl Delay {
+    /// The logarithmic delay connected components uses, of ten steps per unit each 256 rounds apart.
+    pub fn log_scaled() -> Delay {
+        Delay::Logarithmic { steps: 10.0, spacing: 256 }
+    }
+
+    /// Buckets of `width` priorities each entering one round after the last.
+    pub fn bucketed(width: u64) -> Delay {
+        assert!(width > 0, "bucketed delays need a positive width");
+        Delay::Bucketed(width)
+    }
+
+    /// The round at which a record of `priority` enters.
+    ///
+    /// Buckets past the last round a `u32` can name all enter at that round.
+    pub fn at(&self, priority: u64) -> u32 {
+        match *self {
+            Delay::Constant(round) => round,
+            Delay::Logarithmic { steps, spacing } => {
+                let priority = ::std::cmp::max(priority, 1);
+                spacing * (((priority as f64).ln() * steps) as u32)
+            },
+            Delay::Bucketed(width) => {
+                let bucket = priority.checked_div(width).expect("bucketed delays need a positive width");
+                ::std::cmp::min(bucket, u32::max_value() as u64) as u32
+            },
+        }
+    }
+}
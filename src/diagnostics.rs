@@ -0,0 +1,225 @@
+//! Records describing queries the explanation infrastructure could not answer as asked.
+//!
+//! An explanation is a set of required input records, and an empty set is a legitimate answer: some outputs
+//! need no inputs at all. Problems with the query itself are instead reported on a separate collection of
+//! `Diagnostic` records, so that drivers can tell an empty explanation from a question that made no sense.
+
+use std::rc::Rc;
+use std::hash::Hash;
+
+use abomonation::Abomonation;
+
+use timely::dataflow::*;
+use timely::dataflow::scopes::Child;
+use timely::dataflow::operators::*;
+use timely::dataflow::channels::pact::Pipeline;
+
+use differential_dataflow::{Data, Collection};
+use differential_dataflow::operators::*;
+use differential_dataflow::lattice::Lattice;
+
+use {Round, lift_weighted};
+
+/// A problem with a query, identified by the query it concerns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Diagnostic {
+    /// The query names a record not present in the queried output.
+    QueryNotFound(u32),
+    /// Requirements for the query were still changing after `ROUND_LIMIT` rounds of correction. See `exhausted`.
+    RoundsExhausted(u32),
+    /// The query asks about a time before any input data, and so has an empty explanation.
+    QueryBeforeData(u32),
+    /// Requirements for the query name input records with fewer copies than required, and the explanation
+    /// reported for it omits them.
+    InsufficientCopies(u32),
+    /// Requirements for the query exceeded its budget, and the explanation reported for it is partial.
+    /// See `budget::limit`.
+    Truncated(u32),
+    /// The query was not admitted into the explanation scope, and was not explained. See `admit`.
+    Rejected(u32),
+}
+
+impl Diagnostic {
+    /// The query the diagnostic concerns.
+    pub fn query(&self) -> u32 {
+        match *self {
+            Diagnostic::QueryNotFound(q) => q,
+            Diagnostic::RoundsExhausted(q) => q,
+            Diagnostic::QueryBeforeData(q) => q,
+            Diagnostic::InsufficientCopies(q) => q,
+            Diagnostic::Truncated(q) => q,
+            Diagnostic::Rejected(q) => q,
+        }
+    }
+    /// The same diagnostic, about `query` instead.
+    pub fn for_query(&self, query: u32) -> Diagnostic {
+        match *self {
+            Diagnostic::QueryNotFound(_) => Diagnostic::QueryNotFound(query),
+            Diagnostic::RoundsExhausted(_) => Diagnostic::RoundsExhausted(query),
+            Diagnostic::QueryBeforeData(_) => Diagnostic::QueryBeforeData(query),
+            Diagnostic::InsufficientCopies(_) => Diagnostic::InsufficientCopies(query),
+            Diagnostic::Truncated(_) => Diagnostic::Truncated(query),
+            Diagnostic::Rejected(_) => Diagnostic::Rejected(query),
+        }
+    }
+}
+
+// diagnostics hold no owned memory, and so need nothing beyond their bytes.
+impl Abomonation for Diagnostic { }
+
+/// Reports a `QueryNotFound` diagnostic for each query whose named record is absent from `outputs` at the time
+/// the query asks about.
+///
+/// Queries are `(key, val, time, query)` requirements, as introduced into an explanation scope. A query about an
+/// earlier epoch is checked against the outputs as they were then, as `count_at_query` checks inputs: a record
+/// produced later does not answer it, and a record retracted later still does. A query is only reported while its
+/// record is absent, so that correcting either the query or the output retracts the report.
+pub fn not_found<G, K, V>(queries: &Collection<G, (K, V, G::Timestamp, u32)>, outputs: &Collection<G, (K, V)>) -> Collection<G, Diagnostic>
+    where G: Scope, K: Data+Default+Hash, V: Data+Default+Hash, G::Timestamp: Data+Lattice+Ord {
+
+    let queries = queries.map(|(k,v,t,q)| ((k,v),(q,t)));
+    let present = lift_weighted(outputs).join(&queries)
+                                        .filter(|&(_, ref t1, (_, ref t2))| t1 <= t2)
+                                        .map(|(x,_,(q,_))| (x,q))
+                                        .threshold(|_, w| if w > 0 { 1 } else { 0 });
+    queries.map(|(x,(q,_))| (x,q))
+           .concat(&present.negate())
+           .map(|(_,q)| Diagnostic::QueryNotFound(q))
+}
+
+/// Splits `(key, val, time, query)` queries into those `allowed` admits and `Rejected` diagnostics for the rest.
+///
+/// Admission is decided per query, and withdrawing a rejected query withdraws its diagnostic. Predicates might
+/// admit a range of keys, as `move |x| lower <= x.0 && x.0 < upper`, or the clients of a `tenants::Namespaces`
+/// entitled to some input, as `move |x| entitled.contains(&namespaces.client(x.3))`.
+pub fn admit<G, K, V, T, F>(queries: &Collection<G, (K, V, T, u32)>, allowed: F) -> (Collection<G, (K, V, T, u32)>, Collection<G, Diagnostic>)
+    where G: Scope, K: Data, V: Data, T: Data, F: Fn(&(K, V, T, u32))->bool+'static {
+    let allowed = Rc::new(allowed);
+    let clone = allowed.clone();
+    (queries.filter(move |x| allowed(x)),
+     queries.filter(move |x| !clone(x)).map(|(_,_,_,q)| Diagnostic::Rejected(q)))
+}
+
+/// The correction round from which a query whose requirements are still changing is reported by `exhausted`.
+///
+/// Most queries settle within a few rounds, and even a long path settles in as many rounds as it has edges; a
+/// query still changing a million rounds in has most likely run away.
+pub const ROUND_LIMIT: Round = 1 << 20;
+
+/// Reports a `RoundsExhausted` diagnostic for each of the `live` queries with requirements changing in a round of
+/// `limit` or later.
+///
+/// The correction loop runs until requirements stop changing, and a query that keeps changing them holds up the
+/// loop without producing any other sign of trouble; drivers pass `ROUND_LIMIT`. The report stands once made,
+/// whether or not the query later settles, and is retracted when the query is withdrawn, at which point it is no
+/// longer among the `live` queries.
+pub fn exhausted<G, D, F>(must: &Collection<Child<G, Round>, D>, live: &Collection<Child<G, Round>, u32>, limit: Round, query: F) -> Collection<Child<G, Round>, Diagnostic>
+    where G: Scope, G::Timestamp: Lattice+Ord, D: Data, F: Fn(&D)->u32+'static {
+
+    let late = must.inner.unary_stream(Pipeline, "Exhausted", move |input, output| {
+        while let Some((time, data)) = input.next() {
+            if time.time().inner >= limit {
+                output.session(&time).give_iterator(data.drain(..).map(|(datum, _)| ((query(&datum), ()), 1)));
+            }
+        }
+    });
+
+    Collection::new(late).threshold(|_, w| if w > 0 { 1 } else { 0 })
+                         .semijoin(live)
+                         .map(|(q, ())| Diagnostic::RoundsExhausted(q))
+}
+
+/// Reports a `QueryBeforeData` diagnostic for each query whose time is not greater or equal to the time of any
+/// update to `inputs`.
+///
+/// Such a query asks about outputs before there was any data to produce them, and is explained by the empty set
+/// of inputs, which is reported as such rather than left for the driver to puzzle over. Each query is checked
+/// once the time at which it was introduced is complete, and only if input updates at or before its time can no
+/// longer arrive; a query about a time the inputs have yet to reach, such as one about the whole history, is not
+/// reported, as data may still arrive for it. Withdrawing the query withdraws the report, whatever data arrived
+/// in the meantime.
+pub fn before_data<G, K, V, D>(queries: &Collection<G, (K, V, G::Timestamp, u32)>, inputs: &Collection<G, D>) -> Collection<G, Diagnostic>
+    where G: Scope, K: Data, V: Data, D: Data, G::Timestamp: Data {
+
+    // each worker announces each time at which it sees input updates to all workers.
+    let mut announced = Vec::<G::Timestamp>::new();
+    let times = inputs.inner.unary_stream(Pipeline, "InputTimes", move |input, output| {
+        while let Some((time, data)) = input.next() {
+            if data.len() > 0 && !announced.iter().any(|t| t <= &time.time()) {
+                announced.retain(|t| !(time.time() <= *t));
+                announced.push(time.time());
+                output.session(&time).give(());
+            }
+        }
+    });
+
+    // minimal times at which any input update was seen, queries awaiting their time's completion, and the queries
+    // reported, with the number of times each was reported.
+    let mut seen = Vec::<G::Timestamp>::new();
+    let mut pending = Vec::<(G::Timestamp, Vec<((K, V, G::Timestamp, u32), i32)>)>::new();
+    let mut reported = Vec::<((K, V, G::Timestamp, u32), i32)>::new();
+    Collection::new(queries.inner.binary_notify(&times.broadcast(), Pipeline, Pipeline, "BeforeData", vec![], move |input1, input2, output, notificator| {
+
+        while let Some((time, _data)) = input2.next() {
+            if !seen.iter().any(|t| t <= &time.time()) {
+                seen.retain(|t| !(time.time() <= *t));
+                seen.push(time.time());
+            }
+        }
+
+        while let Some((time, data)) = input1.next() {
+            if let Some(position) = pending.iter().position(|x| x.0 == time.time()) {
+                pending[position].1.extend(data.drain(..));
+            }
+            else {
+                pending.push((time.time(), data.drain(..).collect()));
+            }
+            notificator.notify_at(time);
+        }
+
+        while let Some((time, _count)) = notificator.next() {
+            if let Some(position) = pending.iter().position(|x| x.0 == time.time()) {
+                let (_, batch) = pending.swap_remove(position);
+                let mut session = output.session(&time);
+                for (query, weight) in batch {
+                    let position = reported.iter().position(|x| x.0 == query);
+                    if weight > 0 {
+                        // updates at or before the query's time may still arrive while the input frontier allows them.
+                        let settled = !notificator.frontier(1).iter().any(|t| t <= &query.2);
+                        if settled && !seen.iter().any(|t| t <= &query.2) {
+                            session.give((Diagnostic::QueryBeforeData(query.3), weight));
+                            match position {
+                                Some(position) => reported[position].1 += weight,
+                                None => reported.push((query, weight)),
+                            }
+                        }
+                    }
+                    else if let Some(position) = position {
+                        // withdrawals retract what was reported, whether or not data has arrived since.
+                        let retracted = ::std::cmp::min(-weight, reported[position].1);
+                        session.give((Diagnostic::QueryBeforeData(query.3), -retracted));
+                        reported[position].1 -= retracted;
+                        if reported[position].1 == 0 { reported.swap_remove(position); }
+                    }
+                }
+            }
+        }
+    }))
+}
+
+/// Reports each required record with fewer copies available than the one its requirement asks for, tagged by
+/// the query requiring it and with the number of missing copies as its weight.
+///
+/// `available` holds each required `(record, query)` weighted by the record's count as of the query's time, as
+/// `count_at_query` produces. Requirements may name records whose count at that time has fallen to zero or below,
+/// and intersecting them with the input would silently drop those records, leaving an explanation weaker than the
+/// query asked for. Each requirement asks for one copy; a record is reported with a weight of one more than the
+/// copies deleted in excess. Restoring the copies retracts the report.
+pub fn shortfall<G, K, V, T>(need: &Collection<G, (K, V, T, u32)>, available: &Collection<G, ((K, V), u32)>) -> Collection<G, ((K, V), u32)>
+    where G: Scope, K: Data+Default+Hash, V: Data+Default+Hash, T: Data {
+
+    need.map(|(k,v,_t,q)| ((k,v),q))
+        .threshold(|_, w| if w > 0 { 1 } else { 0 })
+        .concat(&available.negate())
+        .threshold(|_, w| if w > 0 { w } else { 0 })
+}
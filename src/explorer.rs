@@ -0,0 +1,168 @@
+//! A terminal view of an interactive session's queries and their explanations.
+//!
+//! Rather than printing each event as it arrives, an `Explorer` accumulates a session's events into the current
+//! state of each query: whether it is outstanding, the input records it requires, grouped by input, and whether it
+//! has converged. `render` draws that state as a screen, which drivers redraw as epochs close, listing every
+//! outstanding query on one line and the required records of those the user has expanded.
+//!
+//! Drawing uses only ANSI escape sequences, and input remains line at a time, so that expanding a query is a
+//! command typed like any other. Available with the `tui` feature.
+
+use std::fmt::{Debug, Write};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use Round;
+use diagnostics::Diagnostic;
+use interactive::Event;
+
+// clears the terminal and moves the cursor to its top left corner.
+const CLEAR: &'static str = "\x1b[2J\x1b[H";
+
+/// What is known about one query.
+struct Query<D> {
+    /// What was asked, for display.
+    asked: String,
+    /// The accumulated weight of the query's asks and withdrawals.
+    weight: i32,
+    /// Required records, as `(input, record)` with accumulated weights.
+    records: BTreeMap<(usize, D), i32>,
+    /// Required records missing from their inputs, as `(input, record)` with accumulated copies.
+    missing: BTreeMap<(usize, D), i32>,
+    /// Rounds in which the query converged, with accumulated weights.
+    converged: BTreeMap<Round, i32>,
+    /// The accumulated weight of the query's answers from the session's cache.
+    cached: i32,
+    /// Diagnostics reported for the query, with accumulated weights.
+    diagnostics: BTreeMap<Diagnostic, i32>,
+}
+
+impl<D: Ord> Query<D> {
+    fn new() -> Query<D> {
+        Query {
+            asked: String::new(),
+            weight: 0,
+            records: BTreeMap::new(),
+            missing: BTreeMap::new(),
+            converged: BTreeMap::new(),
+            cached: 0,
+            diagnostics: BTreeMap::new(),
+        }
+    }
+    // queries the driver did not ask, such as derived queries, are outstanding while they require records.
+    fn outstanding(&self) -> bool {
+        self.weight > 0 || (self.asked.is_empty() && !self.records.is_empty())
+    }
+}
+
+/// The accumulated state of a session's queries, for display.
+pub struct Explorer<D> {
+    inputs: Vec<String>,
+    queries: BTreeMap<u32, Query<D>>,
+    expanded: BTreeSet<u32>,
+    status: String,
+}
+
+impl<D: Ord+Clone+Debug> Explorer<D> {
+    /// A new explorer for a session whose inputs are called `inputs`, in order.
+    pub fn new(inputs: &[&str]) -> Explorer<D> {
+        Explorer {
+            inputs: inputs.iter().map(|x| x.to_string()).collect(),
+            queries: BTreeMap::new(),
+            expanded: BTreeSet::new(),
+            status: String::new(),
+        }
+    }
+
+    /// Records that `query`, described by `asked`, was asked (positive `weight`) or withdrawn (negative).
+    pub fn ask(&mut self, query: u32, asked: &str, weight: i32) {
+        let entry = self.queries.entry(query).or_insert_with(Query::new);
+        entry.asked = asked.to_owned();
+        entry.weight += weight;
+    }
+
+    /// Applies an event reported by the session, about `(record, query)` pairs.
+    pub fn apply(&mut self, event: Event<(D, u32)>) {
+        match event {
+            Event::Must(input, (record, q), w) => update(&mut self.query(q).records, (input, record), w),
+            Event::Shortfall(input, (record, q), w) => update(&mut self.query(q).missing, (input, record), w),
+            Event::Converged(_, (q, round), w) => update(&mut self.query(q).converged, round, w),
+            Event::Diagnostic(x, w) => update(&mut self.query(x.query()).diagnostics, x, w),
+            Event::Initialized(elapsed) => self.status = format!("initialized in {}", seconds(elapsed)),
+            Event::Round(round, elapsed) => self.status = format!("round {} closed in {}", round, seconds(elapsed)),
+            Event::CaughtUp(lane) => self.status = format!("{}; lane {} caught up", self.status, lane),
+            Event::Cached(q, w) => self.query(q).cached += w,
+        }
+        // forget queries that were withdrawn and whose requirements have all been retracted.
+        let stale = self.queries.iter().filter(|&(_, x)| !x.outstanding() && x.records.is_empty()).map(|(&q, _)| q).collect::<Vec<_>>();
+        for q in stale { self.queries.remove(&q); self.expanded.remove(&q); }
+    }
+
+    /// Expands `query` to show its required records, or collapses it if it is expanded.
+    pub fn toggle(&mut self, query: u32) {
+        if !self.expanded.remove(&query) {
+            self.expanded.insert(query);
+        }
+    }
+
+    /// Draws the current state as a screen, starting with the sequence that clears the terminal.
+    ///
+    /// Each outstanding query is listed with its number of required records and whether it has converged or was
+    /// answered from the session's cache; expanded queries list their required records grouped by input, followed
+    /// by any missing records and diagnostics.
+    pub fn render(&self) -> String {
+        let mut screen = String::from(CLEAR);
+        let outstanding = self.queries.values().filter(|x| x.outstanding()).count();
+        let _ = writeln!(screen, "{} outstanding queries; `open <query>` expands a query, `close <query>` collapses it\n", outstanding);
+        for (&q, query) in self.queries.iter() {
+            let open = self.expanded.contains(&q);
+            let state = if !query.outstanding() { "withdrawn".to_owned() }
+                        else if query.cached > 0 { "answered from cache".to_owned() }
+                        else {
+                            match query.converged.iter().filter(|x| *x.1 > 0).map(|x| *x.0).max() {
+                                Some(round) => format!("converged in round {}", round),
+                                None => "explaining".to_owned(),
+                            }
+                        };
+            let _ = writeln!(screen, "{} {:>6}  {:<24} {:>6} records  {}", if open { "-" } else { "+" }, q, query.asked, query.records.len(), state);
+            if open {
+                for (input, name) in self.inputs.iter().enumerate() {
+                    let records = query.records.keys().filter(|x| x.0 == input).collect::<Vec<_>>();
+                    let _ = writeln!(screen, "           {} ({})", name, records.len());
+                    for &&(_, ref record) in records.iter() {
+                        let _ = writeln!(screen, "             {:?}", record);
+                    }
+                }
+                for (&(input, ref record), &copies) in query.missing.iter() {
+                    let _ = writeln!(screen, "           missing {} copies of {:?} from {}", copies, record, self.name(input));
+                }
+                for diagnostic in query.diagnostics.keys() {
+                    let _ = writeln!(screen, "           {:?}", diagnostic);
+                }
+            }
+        }
+        let _ = writeln!(screen, "\n{}", self.status);
+        screen
+    }
+
+    // the state of query `q`, created if it has not been seen.
+    fn query(&mut self, q: u32) -> &mut Query<D> {
+        self.queries.entry(q).or_insert_with(Query::new)
+    }
+
+    // the name of input `input`, or its number if it is not named.
+    fn name(&self, input: usize) -> String {
+        self.inputs.get(input).cloned().unwrap_or_else(|| format!("input {}", input))
+    }
+}
+
+// adds `weight` to the count of `key`, removing it once its count reaches zero.
+fn update<K: Ord+Clone>(map: &mut BTreeMap<K, i32>, key: K, weight: i32) {
+    let count = map.get(&key).map(|x| *x).unwrap_or(0) + weight;
+    if count == 0 { map.remove(&key); } else { map.insert(key, count); }
+}
+
+// a duration in seconds, to the millisecond.
+fn seconds(duration: Duration) -> String {
+    format!("{}.{:03}s", duration.as_secs(), duration.subsec_nanos() / 1_000_000)
+}
@@ -0,0 +1,269 @@
+//! Computations written once, for both plain collections and instrumented variables.
+//!
+//! `CollectionLike` is implemented by `Collection` and by `Variable`, with the operators the two share. A
+//! computation written against it, with the collection type as a type parameter, runs uninstrumented when given
+//! collections and instrumented when given variables, so that production code need not pay for instrumentation
+//! and explanations need not be built from a second copy of the computation:
+//!
+//! ```text
+//! fn two_hops<C>(edges: &mut C) -> <C as Rebind<u32, (u32, u32)>>::Output
+//!     where C: CollectionLike<u32, u32>+Rebind<u32, u32, Output=C>+Rebind<u32, (u32, u32)> {
+//!     let mut reversed = edges.map_inverse(|(x,y)| (y,x), |(y,x)| (x,y));
+//!     reversed.join_u(edges)
+//! }
+//! ```
+//!
+//! Operators that change the record type name their result through `Rebind`, as the trait cannot name "this
+//! kind of collection, of other records" directly; computations must ask for each record type they use. Loops
+//! are not covered, as variables need their explanation scope threaded through them; computations needing loops
+//! are better written as in `algos`, with an uninstrumented twin as in `cc::components`, and explained by
+//! `explain_iterative`. Both are built on `drive`, the correction loop the `datalog` and `sql` frontends share.
+
+use std::cell::RefCell;
+use std::hash::Hash;
+
+use timely::dataflow::*;
+use timely::dataflow::scopes::Child;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use timely_sort::Unsigned;
+
+use differential_dataflow::{Data, Collection};
+use differential_dataflow::operators::*;
+use differential_dataflow::lattice::Lattice;
+
+use {Variable, MonotonicVariable, Round, converged, count_at_query};
+use diagnostics::{self, Diagnostic};
+
+/// The same kind of collection as `Self`, of `(K, V)` records.
+pub trait Rebind<K, V> {
+    /// The rebound collection type.
+    type Output;
+}
+
+impl<G: Scope, K: Data, V: Data, K2: Data, V2: Data> Rebind<K2, V2> for Collection<G, (K, V)> {
+    type Output = Collection<G, (K2, V2)>;
+}
+
+impl<'a, G, K, V, K2, V2, Gp> Rebind<K2, V2> for Variable<'a, G, K, V, Gp>
+    where G: Scope, K: Data+Default, V: Data+Default, K2: Data+Default, V2: Data+Default,
+          Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>, G::Timestamp: Ord+Hash {
+    type Output = Variable<'a, G, K2, V2, Gp>;
+}
+
+/// Operators shared by plain collections and instrumented variables of `(K, V)` records.
+///
+/// Each method behaves as the `Variable` method of the same name. Collections ignore what only instrumentation
+/// needs, such as the inverse passed to `map_inverse`.
+pub trait CollectionLike<K: Data+Default, V: Data+Default>: Sized {
+    /// The scope the records live in.
+    type Scope: Scope;
+    /// The underlying collection of records.
+    fn records(&self) -> Collection<Self::Scope, (K, V)>;
+    /// Maps records using an invertible function (and its inverse).
+    fn map_inverse<K2, V2, F1, F2>(&mut self, logic: F1, inverse: F2) -> <Self as Rebind<K2, V2>>::Output
+        where Self: Rebind<K2, V2>, K2: Data+Default, V2: Data+Default,
+              F1: Fn((K,V))->(K2,V2)+'static, F2: Fn((K2,V2))->(K,V)+'static;
+    /// Retains only the records satisfying `logic`.
+    fn filter<F: Fn(&(K,V))->bool+'static>(&mut self, logic: F) -> Self;
+    /// Concatenates two collections.
+    fn concat(&mut self, other: &mut Self) -> Self;
+    /// Subtracts one collection from another.
+    fn except(&mut self, other: &mut Self) -> Self;
+    /// Joins two collections using an unsigned key.
+    fn join_u<V2>(&mut self, other: &mut <Self as Rebind<K, V2>>::Output) -> <Self as Rebind<K, (V, V2)>>::Output
+        where Self: Rebind<K, V2>+Rebind<K, (V, V2)>, K: Unsigned, V2: Unsigned+Default+Data;
+    /// Joins two collections using a hashed key.
+    fn join<V2>(&mut self, other: &mut <Self as Rebind<K, V2>>::Output) -> <Self as Rebind<K, (V, V2)>>::Output
+        where Self: Rebind<K, V2>+Rebind<K, (V, V2)>, K: Hash, V2: Data+Default;
+}
+
+impl<G, K, V> CollectionLike<K, V> for Collection<G, (K, V)>
+    where G: Scope, K: Data+Default, V: Data+Default, G::Timestamp: Lattice {
+    type Scope = G;
+    fn records(&self) -> Collection<G, (K, V)> {
+        self.clone()
+    }
+    fn map_inverse<K2, V2, F1, F2>(&mut self, logic: F1, _inverse: F2) -> Collection<G, (K2, V2)>
+        where K2: Data+Default, V2: Data+Default,
+              F1: Fn((K,V))->(K2,V2)+'static, F2: Fn((K2,V2))->(K,V)+'static {
+        self.map(logic)
+    }
+    fn filter<F: Fn(&(K,V))->bool+'static>(&mut self, logic: F) -> Self {
+        Collection::filter(&*self, logic)
+    }
+    fn concat(&mut self, other: &mut Self) -> Self {
+        Collection::concat(&*self, &*other)
+    }
+    fn except(&mut self, other: &mut Self) -> Self {
+        Collection::concat(&*self, &other.negate())
+    }
+    fn join_u<V2>(&mut self, other: &mut Collection<G, (K, V2)>) -> Collection<G, (K, (V, V2))>
+        where K: Unsigned, V2: Unsigned+Default+Data {
+        JoinUnsigned::join_u(&*self, &*other).map(|(x,y,z)| (x,(y,z)))
+    }
+    fn join<V2>(&mut self, other: &mut Collection<G, (K, V2)>) -> Collection<G, (K, (V, V2))>
+        where K: Hash, V2: Data+Default {
+        Join::join(&*self, &*other).map(|(x,y,z)| (x,(y,z)))
+    }
+}
+
+impl<'a, G, K, V, Gp> CollectionLike<K, V> for Variable<'a, G, K, V, Gp>
+    where G: Scope, K: Data+Default, V: Data+Default,
+          Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>, G::Timestamp: Ord+Hash+Lattice {
+    type Scope = G;
+    fn records(&self) -> Collection<G, (K, V)> {
+        self.stream.clone()
+    }
+    fn map_inverse<K2, V2, F1, F2>(&mut self, logic: F1, inverse: F2) -> Variable<'a, G, K2, V2, Gp>
+        where K2: Data+Default, V2: Data+Default,
+              F1: Fn((K,V))->(K2,V2)+'static, F2: Fn((K2,V2))->(K,V)+'static {
+        Variable::map_inverse(self, logic, inverse)
+    }
+    fn filter<F: Fn(&(K,V))->bool+'static>(&mut self, logic: F) -> Self {
+        Variable::filter(self, logic)
+    }
+    fn concat(&mut self, other: &mut Self) -> Self {
+        Variable::concat(self, other)
+    }
+    fn except(&mut self, other: &mut Self) -> Self {
+        Variable::except(self, other)
+    }
+    fn join_u<V2>(&mut self, other: &mut Variable<'a, G, K, V2, Gp>) -> Variable<'a, G, K, (V, V2), Gp>
+        where K: Unsigned, V2: Unsigned+Default+Data {
+        Variable::join_u(self, other)
+    }
+    fn join<V2>(&mut self, other: &mut Variable<'a, G, K, V2, Gp>) -> Variable<'a, G, K, (V, V2), Gp>
+        where K: Hash, V2: Data+Default {
+        Variable::join(self, other)
+    }
+}
+
+/// Explains the outputs of a computation written against `CollectionLike`, for a collection of queries.
+///
+/// `logic` is the computation applied to variables, which for a generic function `f` is just `|x| f(x)`; the
+/// same function applied to `input` itself computes the outputs without instrumentation. Queries are `(key, val,
+/// time, query)` naming outputs, and are explained by input records as they were at `time`. Returns the required
+/// input records, tagged by query, markers for the first correction round in which each query's requirements
+/// stopped changing, and diagnostics for queries naming absent outputs.
+pub fn explain<G, K, V, K2, V2, F>(
+    streaming: &mut G,
+    input: &Collection<G, (K, V)>,
+    query: &Collection<G, (K2, V2, Product<Product<RootTimestamp, u32>, Round>, u32)>,
+    logic: F)
+-> (Collection<G, ((K, V), u32)>, Collection<G, (u32, Round)>, Collection<G, Diagnostic>)
+where G: Scope<Timestamp=Product<RootTimestamp, u32>>,
+      K: Data+Default+Hash, V: Data+Default+Hash, K2: Data+Default+Hash, V2: Data+Default+Hash,
+      F: for<'b, 'c> Fn(&mut Variable<'c, Child<'b, G, Round>, K, V, Child<'b, G, Round>>)->Variable<'c, Child<'b, G, Round>, K2, V2, Child<'b, G, Round>>+'static {
+    explain_iterative(streaming, input, query, move |x, _| logic(x))
+}
+
+/// As `explain`, for computations that also need the explanation scope, as those building loops in `algos` do.
+///
+/// `logic` is handed the input variable and the explanation scope, so that single-input algorithms such as
+/// `|x, s| algos::bipartite_matching(x, 100, s)` can be explained without a driver of their own.
+pub fn explain_iterative<G, K, V, K2, V2, F>(
+    streaming: &mut G,
+    input: &Collection<G, (K, V)>,
+    query: &Collection<G, (K2, V2, Product<Product<RootTimestamp, u32>, Round>, u32)>,
+    logic: F)
+-> (Collection<G, ((K, V), u32)>, Collection<G, (u32, Round)>, Collection<G, Diagnostic>)
+where G: Scope<Timestamp=Product<RootTimestamp, u32>>,
+      K: Data+Default+Hash, V: Data+Default+Hash, K2: Data+Default+Hash, V2: Data+Default+Hash,
+      F: for<'b, 'c> Fn(&mut Variable<'c, Child<'b, G, Round>, K, V, Child<'b, G, Round>>, &mut Child<'c, Child<'b, G, Round>, Round>)->Variable<'c, Child<'b, G, Round>, K2, V2, Child<'b, G, Round>>+'static {
+
+    let (must, converged, diagnostics) = drive(streaming, &[input.clone()], query, move |mut vars, query, explanation_scope| {
+        let mut var_input = vars.pop().unwrap();
+        let mut output = logic(&mut var_input, explanation_scope);
+
+        // introduce any query elements as initial dependences, and report those naming absent outputs.
+        output.seed(query);
+        diagnostics::not_found(query, &output.stream)
+    });
+
+    (must.map(|(_,x,q)| (x,q)), converged, diagnostics)
+}
+
+/// The correction loop shared by the drivers: explains some computation over `inputs` for a collection of queries.
+///
+/// `logic` is handed a variable for each input, in order, the queries, and the explanation scope. It builds the
+/// computation, seeds its outputs with the queries, and returns what it reports about them, usually diagnostics;
+/// requirements it places on the variables are intersected with the inputs as of each query's time, and fed back
+/// as the working inputs of the next round until they stop changing. Queries are `(.., .., time, query)`, of
+/// whatever keys and values the outputs have. Returns the required input records as `(input, record, query)`,
+/// markers for the first correction round in which each query's requirements stopped changing, and the reports.
+pub fn drive<G, K, V, K2, V2, D, F>(
+    streaming: &mut G,
+    inputs: &[Collection<G, (K, V)>],
+    query: &Collection<G, (K2, V2, Product<Product<RootTimestamp, u32>, Round>, u32)>,
+    logic: F)
+-> (Collection<G, (u32, (K, V), u32)>, Collection<G, (u32, Round)>, Collection<G, D>)
+where G: Scope<Timestamp=Product<RootTimestamp, u32>>,
+      K: Data+Default+Hash, V: Data+Default+Hash, K2: Data+Default+Hash, V2: Data+Default+Hash, D: Data,
+      F: for<'b, 'c> FnOnce(Vec<Variable<'c, Child<'b, G, Round>, K, V, Child<'b, G, Round>>>, &Collection<Child<'b, G, Round>, (K2, V2, Product<Product<RootTimestamp, u32>, Round>, u32)>, &mut Child<'c, Child<'b, G, Round>, Round>)->Collection<Child<'b, G, Round>, D>+'static {
+
+    let inputs = inputs.to_vec();
+    let query = query.clone();
+
+    // Iterative scope for rounds of input correction
+    let (musts, converged, reports) = streaming.scoped::<Round,_,_>(move |correction| {
+
+        // Bring each input into the scope.
+        let inputs = inputs.iter().map(|x| x.enter(correction)).collect::<Vec<_>>();
+        let query = query.enter(correction);
+
+        // Each input uses a MonotonicVariable to track its elements required to explain outputs.
+        let mut musts = inputs.iter().map(|_| MonotonicVariable::new(correction)).collect::<Vec<_>>();
+
+        // the working inputs are the union of requirements across all queries, where still present.
+        let works = musts.iter().zip(inputs.iter()).map(|(must, input)| {
+            must.stream.map(|(x,_q)| (x,())).semijoin(input).map(|(x,())| x).threshold(|_, w| if w > 0 { 1 } else { 0 })
+        }).collect::<Vec<_>>();
+
+        // Scope for explanation derivation.
+        let child_scope = RefCell::new(correction.new_subscope());
+        let child_index = child_scope.borrow().index;
+
+        // determine and return necessary members of each input.
+        let (needs, reports) = {
+
+            // wrap an explanation scope builder.
+            let mut explanation_scope = Child {
+                subgraph: &child_scope,
+                parent: correction.clone(),
+            };
+
+            let vars = inputs.iter().zip(works.into_iter()).map(|(input, work)| {
+                Variable::new(input.clone(), work, &mut explanation_scope)
+            }).collect::<Vec<_>>();
+
+            // input requirements, popped out of the explanation scope; `logic` adds to them as it builds.
+            let needs = vars.iter().map(|x| x.depends.stream.leave()).collect::<Vec<_>>();
+            let reports = logic(vars, &query, &mut explanation_scope);
+
+            (needs, reports)
+        };
+
+        // all explanation infrastructure in place; add to correct scope.
+        correction.add_operator_with_index(child_scope.into_inner(), child_index);
+
+        // intersect requirements with the inputs as of each query's time.
+        let mut required = Vec::new();
+        for (index, (must, need)) in musts.iter_mut().zip(needs.iter()).enumerate() {
+            must.add(&count_at_query(need, &inputs[index], &query).threshold(|_, w| if w > 0 { 1 } else { 0 }));
+            let index = index as u32;
+            required.push(must.stream.map(move |(x,q)| (index,x,q)));
+        }
+        let required = required.into_iter().fold(None, |all: Option<Collection<_,_>>, x| {
+            Some(match all { Some(all) => all.concat(&x), None => x })
+        }).unwrap();
+
+        // report the first round in which each query's requirements, across all inputs, stopped changing.
+        let converged = converged(&required, |x| x.2);
+
+        (required.leave(), converged.leave(), reports.leave())
+    });
+
+    (musts, converged, reports)
+}
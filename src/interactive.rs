@@ -0,0 +1,550 @@
+//! Interactive explanation sessions, driven by commands received on a channel.
+//!
+//! Each driver builds an instrumented computation on a worker, loads its initial records, and then applies one
+//! command per round, stepping the computation until the round's queries are answered. What the computation
+//! reports (required records, convergence, diagnostics, timings) is sent as `Event`s on a second channel, so
+//! that a session can be scripted and its results asserted on as easily as it can be read from a terminal.
+//!
+//! With the `deterministic` feature, two sessions given the same commands report the same events in the same
+//! order, regardless of the number of workers or how their messages interleave. Reported collections are
+//! routed to worker zero, each round's events are sorted and consolidated before they are sent, and every round
+//! awaits all lanes, as otherwise which lanes are still busy depends on how long each round happens to take.
+//! Nor are queries answered from the cache a single worker keeps, which more workers would explain afresh.
+//! Timings are still reported, and naturally differ between runs.
+//!
+//! The feature only fixes how events are gathered and ordered. That the events themselves agree relies on the
+//! sessions' computations choosing their witnesses by comparing records, as `min!` does, rather than by when
+//! records arrive; other computations, for example ones truncating explanations with `budget::limit` over epochs
+//! whose rounds overlap, may report different explanations from run to run with the feature as without it.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::sync::mpsc::{Receiver, Sender};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use timely::Allocate;
+use timely::dataflow::*;
+use timely::dataflow::scopes::Root;
+use timely::dataflow::operators::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::{Data, Collection};
+
+use {Round, cc, stable};
+use cache::Cache;
+use diagnostics::Diagnostic;
+
+/// Something reported by a session, about records of type `M`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Event<M> {
+    /// A change to the required records of an input, as `(input, (record, query), weight)`.
+    Must(usize, M, i32),
+    /// A change to the round in which a query converged, as `(lane, (query, round), weight)`.
+    Converged(usize, (u32, Round), i32),
+    /// A change to the diagnostics reported for queries.
+    Diagnostic(Diagnostic, i32),
+    /// A change to the required records missing from an input, as `(input, (record, query), copies)`.
+    Shortfall(usize, M, i32),
+    /// Initial records were loaded and processed, taking the supplied time.
+    Initialized(Duration),
+    /// The supplied round of commands completed, taking the supplied time.
+    Round(u32, Duration),
+    /// A lane still explaining queries from earlier rounds has caught up.
+    CaughtUp(usize),
+    /// A change to the queries answered from the session's cache rather than explained afresh, as `(query, weight)`.
+    Cached(u32, i32),
+}
+
+/// The priority of a query; lower numbers are more urgent, and `0` is the default.
+pub type Priority = u32;
+
+/// A command to a connected components session.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CcCommand {
+    /// Changes the weight of a graph edge.
+    Graph((u32, u32), i32),
+    /// Changes the weight of a node label.
+    Label((u32, u32), i32),
+    /// Asks (or withdraws) queries about `(node, label)` pairs, as of an epoch, with a priority.
+    Query(Vec<(u32, u32)>, u32, i32, Priority),
+    /// Advances the round without changing anything.
+    Step,
+}
+
+impl CcCommand {
+    /// Parses a line of input, returning `None` for blank lines.
+    ///
+    /// Lines have the forms `graph {+,-} src dst`, `label {+,-} node label`, and `query {+,-} node label [node
+    /// label ...]`, where `query@epoch` asks about an epoch rather than the whole history, and `query^priority`
+    /// asks with a priority other than `0`; the two may be combined, in either order, as in `query@3^1`. A query
+    /// command with any other suffix is rejected with a reason, rather than being mistaken for something else.
+    /// Other lines that are not understood still advance the round, and are parsed as `Step`.
+    pub fn parse(line: &str) -> Result<Option<CcCommand>, String> {
+        let mut elts = line.split_whitespace();
+        let command = match elts.next() { Some(command) => command, None => return Ok(None) };
+        let sign = elts.next().map(|x| if x == "-" { -1i32 } else { 1 });
+        let fields = elts.map(|x| x.parse::<u32>().ok()).collect::<Vec<_>>();
+        Ok(Some(match (command, sign) {
+            ("graph", Some(sign)) if fields.len() >= 2 && fields[0].is_some() && fields[1].is_some() => {
+                CcCommand::Graph((fields[0].unwrap(), fields[1].unwrap()), sign)
+            },
+            ("label", Some(sign)) if fields.len() >= 2 && fields[0].is_some() && fields[1].is_some() => {
+                CcCommand::Label((fields[0].unwrap(), fields[1].unwrap()), sign)
+            },
+            (command, Some(sign)) if command.starts_with("query") => {
+                let (epoch, priority) = try!(suffixes(command));
+                let fields = fields.into_iter().filter_map(|x| x).collect::<Vec<_>>();
+                CcCommand::Query(fields.chunks(2).filter(|x| x.len() == 2).map(|x| (x[0], x[1])).collect(), epoch.unwrap_or(u32::max_value()), sign, priority.unwrap_or(0))
+            },
+            _ => CcCommand::Step,
+        }))
+    }
+}
+
+// the epoch following `@` and the priority following `^` in a query command, each at most once and in any order.
+fn suffixes(command: &str) -> Result<(Option<u32>, Option<Priority>), String> {
+    let mut epoch = None;
+    let mut priority = None;
+    let mut rest = &command["query".len() ..];
+    while let Some(marker) = rest.chars().next() {
+        let repeated = match marker { '@' => epoch.is_some(), '^' => priority.is_some(), _ => true };
+        if repeated {
+            return Err(format!("unknown or repeated suffix {:?} in {:?}", marker, command));
+        }
+        // markers are single bytes, and digits too, so the counts below are byte offsets.
+        let digits = rest[1 ..].chars().take_while(|c| c.is_digit(10)).count();
+        let value = match rest[1 .. 1 + digits].parse::<u32>() {
+            Ok(value) => value,
+            Err(_) => return Err(format!("expected a number after {:?} in {:?}", marker, command)),
+        };
+        if marker == '@' { epoch = Some(value); } else { priority = Some(value); }
+        rest = &rest[1 + digits ..];
+    }
+    Ok((epoch, priority))
+}
+
+/// A command to a stable matching session.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StableCommand {
+    /// Changes the weight of a preference, as `(a_id, (a_pref, b_id, b_pref))`.
+    Prefs((u32, (u32, u32, u32)), i32),
+    /// Asks (or withdraws) queries about matched pairs, each identified by its `a_id`.
+    Query(Vec<(u32, (u32, u32, u32))>, i32),
+    /// Advances the round without changing anything.
+    Step,
+}
+
+impl StableCommand {
+    /// Parses a line of input, returning `None` for blank lines.
+    ///
+    /// Lines have the forms `prefs {+,-} a_id a_pref b_id b_pref` and `query {+,-} a_id a_pref b_id b_pref [...]`.
+    /// Lines that are not understood still advance the round, and are parsed as `Step`.
+    pub fn parse(line: &str) -> Option<StableCommand> {
+        let mut elts = line.split_whitespace();
+        elts.next().map(|command| {
+            let sign = elts.next().map(|x| if x == "-" { -1i32 } else { 1 });
+            let fields = elts.map(|x| x.parse::<u32>().ok()).collect::<Vec<_>>();
+            match (command, sign) {
+                ("prefs", Some(sign)) if fields.len() >= 4 && fields[..4].iter().all(|x| x.is_some()) => {
+                    StableCommand::Prefs((fields[0].unwrap(), (fields[1].unwrap(), fields[2].unwrap(), fields[3].unwrap())), sign)
+                },
+                ("query", Some(sign)) => {
+                    let fields = fields.into_iter().filter_map(|x| x).collect::<Vec<_>>();
+                    StableCommand::Query(fields.chunks(4).filter(|x| x.len() == 4).map(|x| (x[0], (x[1], x[2], x[3]))).collect(), sign)
+                },
+                _ => StableCommand::Step,
+            }
+        })
+    }
+}
+
+/// Sends events, or with the `deterministic` feature collects them to be sorted and sent at the end of each round.
+struct Reporter<M> {
+    events: Sender<Event<M>>,
+    buffer: Rc<RefCell<Vec<Event<M>>>>,
+}
+
+impl<M: Ord> Reporter<M> {
+    fn new(events: Sender<Event<M>>) -> Self {
+        Reporter { events: events, buffer: Rc::new(RefCell::new(Vec::new())) }
+    }
+    fn report(&self, event: Event<M>) {
+        if cfg!(feature = "deterministic") {
+            self.buffer.borrow_mut().push(event);
+        }
+        else {
+            let _ = self.events.send(event);
+        }
+    }
+    /// Sends collected events in sorted order, with the weights of otherwise equal events accumulated, followed
+    /// by `event`.
+    fn flush(&self, event: Event<M>) {
+        let mut buffer = self.buffer.borrow_mut();
+        buffer.sort();
+        let mut pending: Option<Event<M>> = None;
+        for next in buffer.drain(..) {
+            let merged = match (pending.as_mut(), &next) {
+                (Some(&mut Event::Must(i1, ref x1, ref mut w1)), &Event::Must(i2, ref x2, w2)) if i1 == i2 && x1 == x2 => { *w1 += w2; true },
+                (Some(&mut Event::Converged(l1, x1, ref mut w1)), &Event::Converged(l2, x2, w2)) if l1 == l2 && x1 == x2 => { *w1 += w2; true },
+                (Some(&mut Event::Diagnostic(x1, ref mut w1)), &Event::Diagnostic(x2, w2)) if x1 == x2 => { *w1 += w2; true },
+                (Some(&mut Event::Shortfall(i1, ref x1, ref mut w1)), &Event::Shortfall(i2, ref x2, w2)) if i1 == i2 && x1 == x2 => { *w1 += w2; true },
+                (Some(&mut Event::Cached(q1, ref mut w1)), &Event::Cached(q2, w2)) if q1 == q2 => { *w1 += w2; true },
+                _ => false,
+            };
+            if !merged {
+                if let Some(event) = pending.take() { self.send_nonzero(event); }
+                pending = Some(next);
+            }
+        }
+        if let Some(event) = pending.take() { self.send_nonzero(event); }
+        let _ = self.events.send(event);
+    }
+    fn send_nonzero(&self, event: Event<M>) {
+        let zero = match event {
+            Event::Must(_, _, w) | Event::Converged(_, _, w) | Event::Diagnostic(_, w) | Event::Shortfall(_, _, w) | Event::Cached(_, w) => w == 0,
+            _ => false,
+        };
+        if !zero { let _ = self.events.send(event); }
+    }
+}
+
+impl<M> Clone for Reporter<M> {
+    fn clone(&self) -> Self {
+        Reporter { events: self.events.clone(), buffer: self.buffer.clone() }
+    }
+}
+
+/// Prepares a collection to be reported; with the `deterministic` feature, gathered at worker zero.
+fn reported<G: Scope, D: Data>(collection: Collection<G, D>) -> Collection<G, D> {
+    if cfg!(feature = "deterministic") {
+        Collection::new(collection.inner.exchange(|_| 0))
+    }
+    else {
+        collection
+    }
+}
+
+/// A query about a connected components label, as `(node, label, time, query)`.
+type CcQuery = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+
+/// The records required by each query, as `(input, record)` with accumulated weights.
+type Musts = Rc<RefCell<HashMap<u32, HashMap<(usize, (u32, u32)), i32>>>>;
+
+// accumulates a change to the records `query` requires from `input`.
+fn accumulate(musts: &Musts, input: usize, (x, query): ((u32, u32), u32), w: i32) {
+    let mut musts = musts.borrow_mut();
+    let empty = {
+        let records = musts.entry(query).or_insert_with(HashMap::new);
+        let zero = { let count = records.entry((input, x)).or_insert(0); *count += w; *count == 0 };
+        if zero { records.remove(&(input, x)); }
+        records.is_empty()
+    };
+    if empty { musts.remove(&query); }
+}
+
+// reports the records of a cached must-set as required by `query` (positive `sign`), or retracts them (negative).
+fn report_cached(reporter: &Reporter<((u32, u32), u32)>, query: u32, records: &[Vec<(u32, u32)>], sign: i32) {
+    for (input, records) in records.iter().enumerate() {
+        for &x in records.iter() {
+            reporter.report(Event::Must(input, (x, query), sign));
+        }
+    }
+    reporter.report(Event::Cached(query, sign));
+}
+
+/// Runs a connected components session on `root`, with queries spread across `lanes` independent lanes.
+///
+/// The `graph` and `label` records are this worker's initial input. Commands are applied one per round until
+/// the channel closes. Required records are reported as `Event::Must` with input 0 for graph records and 1 for
+/// label records. Each round awaits the lanes that were idle or were just asked something; busy lanes continue
+/// in the background, and are reported when they catch up.
+///
+/// Queries with priorities above `0` are background queries: the lanes explaining them are not awaited. Queries
+/// are held back while a busy lane is explaining more urgent ones, and each round releases only the most urgent of
+/// those held, so that interactive queries are asked first and batch audits fill in behind them.
+///
+/// Lanes share the graph and label inputs, but nothing built from them: each lane has its own correction loop,
+/// and its own indices of the graph and labels within it, so memory and the work of each update grow with the
+/// number of lanes. One lane is the right choice unless slow explanations are holding up quick ones.
+///
+/// With one worker, a whole-history query asked again after it was withdrawn is answered from a `Cache` of the
+/// must-sets of withdrawn queries, reported as `Event::Cached` along with its records, rather than explained
+/// afresh. A must-set is cached when its query is withdrawn from a lane that has caught up and reported no
+/// diagnostic for it, and is used only while no input record has been added since, as an added record may lower a
+/// label, and while each of its records remains. A cached answer that stops meeting these is retracted, and its
+/// query asked of its lane like any other.
+/// With more workers, each sees only its share of the input, and every query is explained afresh, as it is with
+/// the `deterministic` feature, so that sessions report the same events whatever their number of workers.
+pub fn cc<A: Allocate>(root: &mut Root<A>, lanes: u32, graph: Vec<(u32, u32)>, label: Vec<(u32, u32)>, commands: Receiver<CcCommand>, events: Sender<Event<((u32, u32), u32)>>) {
+
+    let caching = root.peers() == 1 && !cfg!(feature = "deterministic");
+    let musts: Musts = Rc::new(RefCell::new(HashMap::new()));
+    let flagged = Rc::new(RefCell::new(HashMap::new()));
+
+    let reporter = Reporter::new(events);
+    let sender = reporter.clone();
+    let tracked = musts.clone();
+    let diagnosed = flagged.clone();
+    let (mut graph_input, mut label_input, mut query, probes) = root.scoped::<u32, _, _>(move |streaming| {
+
+        // inputs for graph data and label data, shared by all query lanes.
+        let (graph_handle, graph) = streaming.new_input(); let graph = Collection::new(graph);
+        let (label_handle, label) = streaming.new_input(); let label = Collection::new(label);
+
+        // each lane has its own query input and explanation infrastructure, and its own probe.
+        let mut query_handles = Vec::new();
+        let mut probes = Vec::new();
+        for lane in 0 .. lanes as usize {
+
+            let (query_handle, query) = streaming.new_input(); let query = Collection::new(query);
+
+            // requirements are fed back to the next epoch, to warm-start its correction loop.
+            let (prior_graph_handle, prior_graph) = streaming.loop_variable(u32::max_value(), 1);
+            let (prior_label_handle, prior_label) = streaming.loop_variable(u32::max_value(), 1);
+            let prior_graph = Collection::new(prior_graph);
+            let prior_label = Collection::new(prior_label);
+
+            let (graph_must, label_must, converged, diagnostics, shortfall) = cc::explain(streaming, &graph, &label, &query, &prior_graph, &prior_label);
+            graph_must.inner.connect_loop(prior_graph_handle);
+            label_must.inner.connect_loop(prior_label_handle);
+
+            // required records and diagnostics are also accumulated by query, to decide which must-sets to cache.
+            let (sender1, sender2, sender3, sender4, sender5) = (sender.clone(), sender.clone(), sender.clone(), sender.clone(), sender.clone());
+            let (tracked1, tracked2, diagnosed) = (tracked.clone(), tracked.clone(), diagnosed.clone());
+            let graph_must = reported(graph_must).inspect(move |&(x, w)| { if caching { accumulate(&tracked1, 0, x, w); } sender1.report(Event::Must(0, x, w)) });
+            let label_must = reported(label_must).inspect(move |&(x, w)| { if caching { accumulate(&tracked2, 1, x, w); } sender2.report(Event::Must(1, x, w)) });
+            let converged = reported(converged).inspect(move |&(x, w)| sender3.report(Event::Converged(lane, x, w)));
+            let diagnostics = reported(diagnostics).inspect(move |&(x, w)| { if caching { *diagnosed.borrow_mut().entry(x.query()).or_insert(0) += w; } sender4.report(Event::Diagnostic(x, w)) });
+            let shortfall = reported(shortfall).inspect(move |&((input, x, q), w)| sender5.report(Event::Shortfall(input as usize, (x, q), w)));
+
+            // attach a probe, so that we can await completeness.
+            query_handles.push(query_handle);
+            // everything reported is probed, so that a round's events are all reported by its end.
+            probes.push(graph_must.map(|_| ()).concat(&label_must.map(|_| ()))
+                                  .concat(&converged.map(|_| ()))
+                                  .concat(&diagnostics.map(|_| ()))
+                                  .concat(&shortfall.map(|_| ()))
+                                  .probe().0);
+        }
+
+        (graph_handle, label_handle, query_handles, probes)
+    });
+
+    // the copies of each input record, against which cached must-sets are checked.
+    let mut present = HashMap::new();
+    for edge in graph {
+        if caching { *present.entry((0, edge)).or_insert(0) += 1; }
+        graph_input.send((edge, 1));
+    }
+    for node in label {
+        if caching { *present.entry((1, node)).or_insert(0) += 1; }
+        label_input.send((node, 1));
+    }
+
+    // close initial records, advance graph and query inputs to the next epoch.
+    graph_input.advance_to(1);
+    label_input.advance_to(1);
+    for lane in query.iter_mut() { lane.advance_to(1); }
+    let timer = Instant::now();
+    root.step_while(|| probes.iter().any(|p| p.lt(&graph_input.time())));
+    reporter.flush(Event::Initialized(timer.elapsed()));
+
+    // lanes still explaining queries from earlier rounds; these continue whenever we step.
+    let mut busy = vec![false; lanes as usize];
+
+    // the most urgent priority each lane has been asked since it was last idle, and queries held back while more
+    // urgent ones are outstanding, as `(priority, lane, query, sign)`.
+    let mut urgency = vec![Priority::max_value(); lanes as usize];
+    let mut held = Vec::new();
+
+    // must-sets of withdrawn queries, by `(node, label)`; the number of input changes applied, that number as of
+    // the previous round, and as of the last change adding a record; queries sent to lanes and not withdrawn; and
+    // queries answered from the cache, as `(priority, lane, query, records)`.
+    let whole = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut cache: Cache<(u32, u32), (u32, u32)> = Cache::new();
+    let mut version = 0;
+    let mut settled = 0;
+    let mut added = 0;
+    let mut live: Vec<CcQuery> = Vec::new();
+    let mut answered: Vec<(Priority, usize, CcQuery, Vec<Vec<(u32, u32)>>)> = Vec::new();
+
+    let mut round = 1;
+    for command in commands.iter() {
+
+        // lanes which received queries this round.
+        let mut asked = vec![false; lanes as usize];
+
+        // the input record changed this round, as `(input, record, sign)`.
+        let mut changed = None;
+
+        match command {
+            CcCommand::Graph(edge, sign) => { graph_input.send((edge, sign)); changed = Some((0, edge, sign)); },
+            CcCommand::Label(node, sign) => { label_input.send((node, sign)); changed = Some((1, node, sign)); },
+            CcCommand::Query(pairs, epoch, sign, priority) => {
+                for (node, value) in pairs {
+                    let lane = (node % lanes) as usize;
+                    let asked_query = (node, value, Product::new(RootTimestamp::new(epoch), Round::max_value()), node);
+                    // withdrawing a query still held back cancels it, rather than overtaking it.
+                    match held.iter().position(|x| x.1 == lane && x.2 == asked_query && x.3 == -sign) {
+                        Some(position) => { held.remove(position); },
+                        None => held.push((priority, lane, asked_query, sign)),
+                    }
+                }
+            },
+            CcCommand::Step => { },
+        }
+
+        if let (true, Some((input, x, sign))) = (caching, changed) {
+            version += 1;
+            if sign > 0 { added = version; }
+            *present.entry((input, x)).or_insert(0) += sign;
+            // cached answers that may no longer hold are retracted, and their queries asked afresh.
+            let mut index = 0;
+            while index < answered.len() {
+                if sign > 0 || answered[index].3[input].contains(&x) {
+                    let (priority, lane, asked_query, records) = answered.remove(index);
+                    report_cached(&reporter, asked_query.3, &records, -1);
+                    cache.remove(&(asked_query.0, asked_query.1));
+                    held.push((priority, lane, asked_query, 1));
+                }
+                else {
+                    index += 1;
+                }
+            }
+        }
+
+        // release the most urgent held queries, unless some busy lane is explaining more urgent ones.
+        let outstanding = (0 .. lanes as usize).filter(|&l| busy[l]).map(|l| urgency[l]).min().unwrap_or(Priority::max_value());
+        if let Some(release) = held.iter().map(|x| x.0).min() {
+            if release <= outstanding {
+                let (released, kept): (Vec<_>, Vec<_>) = held.drain(..).partition(|x| x.0 == release);
+                held = kept;
+                for (priority, lane, asked_query, sign) in released {
+                    let key = (asked_query.0, asked_query.1);
+                    if caching && asked_query.2 == whole {
+                        if sign > 0 {
+                            let current = cache.version(&key).map_or(false, |v| v >= added);
+                            if !current { cache.remove(&key); }
+                            let cached = if current { cache.get(&key, version, |input, x| present.get(&(input, *x)).map_or(false, |&c| c > 0)).map(|x| x.to_vec()) } else { None };
+                            if let Some(records) = cached {
+                                report_cached(&reporter, asked_query.3, &records, 1);
+                                answered.push((priority, lane, asked_query, records));
+                                continue;
+                            }
+                        }
+                        else if let Some(position) = answered.iter().position(|x| x.2 == asked_query) {
+                            let (_, _, asked_query, records) = answered.remove(position);
+                            report_cached(&reporter, asked_query.3, &records, -1);
+                            continue;
+                        }
+                        else if !busy[lane] && live.iter().filter(|x| x.3 == asked_query.3).count() == 1 && flagged.borrow().get(&asked_query.3).map_or(true, |&c| c <= 0) {
+                            // the lane has caught up, so the query's must-set is complete as of the previous round,
+                            // and without diagnostics it explains the query in full.
+                            if let Some(records) = musts.borrow().get(&asked_query.3) {
+                                let mut split = vec![Vec::new(), Vec::new()];
+                                for (&(input, x), &w) in records.iter() {
+                                    if w > 0 { split[input].push(x); }
+                                }
+                                for records in split.iter_mut() { records.sort(); }
+                                cache.insert(key, settled, split);
+                            }
+                        }
+                    }
+                    if sign > 0 {
+                        live.push(asked_query.clone());
+                    }
+                    else if let Some(position) = live.iter().position(|x| *x == asked_query) {
+                        live.remove(position);
+                    }
+                    asked[lane] = true;
+                    urgency[lane] = ::std::cmp::min(urgency[lane], priority);
+                    query[lane].send((asked_query, sign));
+                }
+            }
+        }
+
+        graph_input.advance_to(round + 1);
+        label_input.advance_to(round + 1);
+        for lane in query.iter_mut() { lane.advance_to(round + 1); }
+
+        // await lanes that were idle, and those we just asked something urgent; busy lanes and lanes explaining
+        // only background queries carry on.
+        let waiting = (0 .. lanes as usize).filter(|&l| (!busy[l] && !asked[l]) || (asked[l] && urgency[l] == 0) || cfg!(feature = "deterministic")).collect::<Vec<_>>();
+        let timer = Instant::now();
+        root.step_while(|| waiting.iter().any(|&l| probes[l].lt(&graph_input.time())));
+        reporter.flush(Event::Round(round, timer.elapsed()));
+
+        for lane in 0 .. lanes as usize {
+            let still_busy = probes[lane].lt(&graph_input.time());
+            if busy[lane] && !still_busy {
+                reporter.report(Event::CaughtUp(lane));
+            }
+            if !still_busy { urgency[lane] = Priority::max_value(); }
+            busy[lane] = still_busy;
+        }
+
+        settled = version;
+        round += 1;
+    }
+}
+
+/// Runs a stable matching session on `root`.
+///
+/// The `prefs` records are this worker's initial input. Commands are applied one per round until the channel
+/// closes, and each round awaits the explanation of any queries asked. Required records are reported as
+/// `Event::Must` with input 0, and convergence is reported with lane 0.
+pub fn stable<A: Allocate>(root: &mut Root<A>, prefs: Vec<(u32, (u32, u32, u32))>, commands: Receiver<StableCommand>, events: Sender<Event<((u32, (u32, u32, u32)), u32)>>) {
+
+    let reporter = Reporter::new(events);
+    let sender = reporter.clone();
+    let (mut prefs_input, mut query, probe) = root.scoped::<u32, _, _>(move |streaming| {
+
+        // inputs for preference data and queries made against the results.
+        let (prefs_handle, prefs) = streaming.new_input(); let prefs = Collection::new(prefs);
+        let (query_handle, query) = streaming.new_input(); let query = Collection::new(query);
+
+        let (prefs_must, converged, diagnostics) = stable::explain(streaming, &prefs, &query);
+
+        let (sender1, sender2, sender3) = (sender.clone(), sender.clone(), sender.clone());
+        let prefs_must = reported(prefs_must).inspect(move |&(x, w)| sender1.report(Event::Must(0, x, w)));
+        let converged = reported(converged).inspect(move |&(x, w)| sender2.report(Event::Converged(0, x, w)));
+        let diagnostics = reported(diagnostics).inspect(move |&(x, w)| sender3.report(Event::Diagnostic(x, w)));
+
+        // everything reported is probed, so that a round's events are all reported by its end.
+        let probe = prefs_must.map(|_| ()).concat(&converged.map(|_| ())).concat(&diagnostics.map(|_| ())).probe().0;
+
+        (prefs_handle, query_handle, probe)
+    });
+
+    for pref in prefs { prefs_input.send((pref, 1)); }
+
+    prefs_input.advance_to(1);
+    query.advance_to(1);
+    let timer = Instant::now();
+    root.step_while(|| probe.lt(&query.time()));
+    reporter.flush(Event::Initialized(timer.elapsed()));
+
+    let mut round = 1;
+    for command in commands.iter() {
+
+        match command {
+            StableCommand::Prefs(pref, sign) => prefs_input.send((pref, sign)),
+            StableCommand::Query(pairs, sign) => {
+                for (a, rest) in pairs {
+                    query.send(((a, rest, Product::new(RootTimestamp::new(u32::max_value()), Round::max_value()), a), sign));
+                }
+            },
+            StableCommand::Step => { },
+        }
+
+        prefs_input.advance_to(round + 1);
+        query.advance_to(round + 1);
+        let timer = Instant::now();
+        root.step_while(|| probe.lt(&query.time()));
+        reporter.flush(Event::Round(round, timer.elapsed()));
+
+        round += 1;
+    }
+}
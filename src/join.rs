@@ -0,0 +1,232 @@
+//! Specialized joins for routing requirements.
+//!
+//! Requirements are routed back to the records they name by joining on whole `(key, val)` records, which
+//! the general-purpose differential joins handle by sorting and merging. The requirement and lifted record
+//! collections only ever grow within an explanation, so a symmetric hash join over FNV-hashed keys, with its
+//! tables sized up front, does the same work with less per-round latency.
+//!
+//! `arrange` builds such a table once for a collection feeding several joins, and `join_arranged` joins against
+//! it without a table of its own for that side.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::collections::HashMap;
+
+use fnv::{FnvHasher, FnvHashMap};
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+use timely::dataflow::channels::pact::{Pipeline, Exchange};
+
+use differential_dataflow::{Data, Collection, Delta};
+use differential_dataflow::lattice::Lattice;
+
+/// Hashes `key` with FNV, which unlike the default hasher is the same on every worker and in every run.
+pub fn fnv_hash<K: Hash>(key: &K) -> u64 {
+    let mut hasher = FnvHasher::default();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Joins two collections by key, retaining each input in an FNV-hashed table pre-sized to `capacity` keys.
+///
+/// Each pair of matching records is produced once, at the least upper bound of their times, with the product of
+/// their weights. No state is ever discarded, which suits requirement routing but not general use.
+pub fn join_fnv<G, K, V1, V2>(input1: &Collection<G, (K, V1)>, input2: &Collection<G, (K, V2)>, capacity: usize) -> Collection<G, (K, V1, V2)>
+    where G: Scope, K: Data+Hash, V1: Data, V2: Data, G::Timestamp: Lattice {
+
+    let mut table1: FnvHashMap<K, Vec<(V1, G::Timestamp, Delta)>> = HashMap::with_capacity_and_hasher(capacity, Default::default());
+    let mut table2: FnvHashMap<K, Vec<(V2, G::Timestamp, Delta)>> = HashMap::with_capacity_and_hasher(capacity, Default::default());
+
+    let exchange1 = Exchange::new(|x: &((K, V1), Delta)| fnv_hash(&(x.0).0));
+    let exchange2 = Exchange::new(|x: &((K, V2), Delta)| fnv_hash(&(x.0).0));
+
+    Collection::new(input1.inner.binary_stream(&input2.inner, exchange1, exchange2, "JoinFnv", move |input1, input2, output| {
+
+        while let Some((time, data)) = input1.next() {
+            let time1 = time.time();
+            for ((key, val1), wgt1) in data.drain(..) {
+                if let Some(matches) = table2.get(&key) {
+                    for &(ref val2, ref time2, wgt2) in matches.iter() {
+                        let upper = time1.join(time2);
+                        output.session(&time.delayed(&upper))
+                              .give(((key.clone(), val1.clone(), val2.clone()), wgt1 * wgt2));
+                    }
+                }
+                table1.entry(key).or_insert(Vec::new()).push((val1, time1.clone(), wgt1));
+            }
+        }
+
+        while let Some((time, data)) = input2.next() {
+            let time2 = time.time();
+            for ((key, val2), wgt2) in data.drain(..) {
+                if let Some(matches) = table1.get(&key) {
+                    for &(ref val1, ref time1, wgt1) in matches.iter() {
+                        let upper = time2.join(time1);
+                        output.session(&time.delayed(&upper))
+                              .give(((key.clone(), val1.clone(), val2.clone()), wgt1 * wgt2));
+                    }
+                }
+                table2.entry(key).or_insert(Vec::new()).push((val2, time2.clone(), wgt2));
+            }
+        }
+    }))
+}
+
+/// The first position in `list` whose time is not less than `time`, in the total order.
+fn lower_bound<T: Ord, A, B, C>(list: &[(T, A, B, C)], time: &T) -> usize {
+    match list.binary_search_by(|x| if x.0.cmp(time) == Ordering::Less { Ordering::Less } else { Ordering::Greater }) {
+        Ok(i) => i,
+        Err(i) => i,
+    }
+}
+
+/// Joins candidates against requests by key, producing only pairs where the candidate's time is less or equal to
+/// the request's time and `valid` approves of the pair.
+///
+/// The time test is applied inside the join rather than to its output. Each key's candidates and requests are
+/// kept sorted by their times, in a total order consistent with the partial order, so that a request examines only
+/// the candidates that could precede it, and a candidate only the requests that could follow it.
+pub fn join_bounded<G, K, V, R, T, F>(candidates: &Collection<G, (K, (V, T))>, requests: &Collection<G, (K, (R, T))>, valid: F) -> Collection<G, (K, V, T, R)>
+    where G: Scope, K: Data+Hash, V: Data, R: Data, T: Data, F: Fn(&V, &R)->bool+'static, G::Timestamp: Lattice {
+
+    // for each key, a list of (time, value, stream time, weight) ordered by time.
+    let mut table1: FnvHashMap<K, Vec<(T, V, G::Timestamp, Delta)>> = HashMap::default();
+    let mut table2: FnvHashMap<K, Vec<(T, R, G::Timestamp, Delta)>> = HashMap::default();
+
+    let exchange1 = Exchange::new(|x: &((K, (V, T)), Delta)| fnv_hash(&(x.0).0));
+    let exchange2 = Exchange::new(|x: &((K, (R, T)), Delta)| fnv_hash(&(x.0).0));
+
+    Collection::new(candidates.inner.binary_stream(&requests.inner, exchange1, exchange2, "JoinBounded", move |input1, input2, output| {
+
+        while let Some((time, data)) = input1.next() {
+            let time1 = time.time();
+            for ((key, (val, t1)), wgt1) in data.drain(..) {
+                if let Some(list) = table2.get(&key) {
+                    // requests that could follow `t1` are at or after it in the total order.
+                    let start = lower_bound(&list[..], &t1);
+                    for &(ref t2, ref req, ref time2, wgt2) in list[start..].iter() {
+                        if t1 <= *t2 && valid(&val, req) {
+                            output.session(&time.delayed(&time1.join(time2)))
+                                  .give(((key.clone(), val.clone(), t1.clone(), req.clone()), wgt1 * wgt2));
+                        }
+                    }
+                }
+                let list = table1.entry(key).or_insert(Vec::new());
+                let position = lower_bound(&list[..], &t1);
+                list.insert(position, (t1, val, time1.clone(), wgt1));
+            }
+        }
+
+        while let Some((time, data)) = input2.next() {
+            let time2 = time.time();
+            for ((key, (req, t2)), wgt2) in data.drain(..) {
+                if let Some(list) = table1.get(&key) {
+                    // candidates that could precede `t2` are at or before it in the total order.
+                    for &(ref t1, ref val, ref time1, wgt1) in list.iter().take_while(|x| x.0.cmp(&t2) != Ordering::Greater) {
+                        if *t1 <= t2 && valid(val, &req) {
+                            output.session(&time.delayed(&time2.join(time1)))
+                                  .give(((key.clone(), val.clone(), t1.clone(), req.clone()), wgt1 * wgt2));
+                        }
+                    }
+                }
+                let list = table2.entry(key).or_insert(Vec::new());
+                let position = lower_bound(&list[..], &t2);
+                list.insert(position, (t2, req, time2.clone(), wgt2));
+            }
+        }
+    }))
+}
+
+/// A collection's records in an FNV-hashed table by key, shared by the joins built from it on each worker.
+///
+/// Each record is numbered in the order this worker indexed it, so that a join reading the table can tell the
+/// records it has already seen on its own input from those still on their way to it.
+pub struct Arranged<G: Scope, K: Data, V: Data> {
+    /// The indexed records, with their numbers, partitioned by key.
+    pub stream: Collection<G, ((K, V), u64)>,
+    /// This worker's table, of `(value, time, weight, number)` by key.
+    pub index: Rc<RefCell<FnvHashMap<K, Vec<(V, G::Timestamp, Delta, u64)>>>>,
+}
+
+impl<G: Scope, K: Data, V: Data> Clone for Arranged<G, K, V> {
+    fn clone(&self) -> Self {
+        Arranged { stream: self.stream.clone(), index: self.index.clone() }
+    }
+}
+
+/// Indexes `input` by key, in a table pre-sized to `capacity` keys, for use by any number of `join_arranged`s.
+///
+/// As with `join_fnv`, no state is ever discarded.
+pub fn arrange<G, K, V>(input: &Collection<G, (K, V)>, capacity: usize) -> Arranged<G, K, V>
+    where G: Scope, K: Data+Hash, V: Data {
+
+    let index: Rc<RefCell<FnvHashMap<K, Vec<(V, G::Timestamp, Delta, u64)>>>> = Rc::new(RefCell::new(HashMap::with_capacity_and_hasher(capacity, Default::default())));
+    let table = index.clone();
+    let mut next = 0;
+
+    let exchange = Exchange::new(|x: &((K, V), Delta)| fnv_hash(&(x.0).0));
+    let stream = input.inner.unary_stream(exchange, "Arrange", move |input, output| {
+        while let Some((time, data)) = input.next() {
+            let stamp = time.time();
+            let mut table = table.borrow_mut();
+            let mut session = output.session(&time);
+            for ((key, val), wgt) in data.drain(..) {
+                table.entry(key.clone()).or_insert(Vec::new()).push((val.clone(), stamp.clone(), wgt, next));
+                session.give((((key, val), next), wgt));
+                next += 1;
+            }
+        }
+    });
+
+    Arranged { stream: Collection::new(stream), index: index }
+}
+
+/// Joins an arranged collection with `input` by key, as `join_fnv` but reading the arranged side's shared table.
+///
+/// Records from the arranged side are matched against `input` as they arrive at the join, and records of `input`
+/// against the arranged records the join has already received, so that each pair is produced exactly once.
+pub fn join_arranged<G, K, V1, V2>(arranged: &Arranged<G, K, V1>, input: &Collection<G, (K, V2)>, capacity: usize) -> Collection<G, (K, V1, V2)>
+    where G: Scope, K: Data+Hash, V1: Data, V2: Data, G::Timestamp: Lattice {
+
+    let index = arranged.index.clone();
+    let mut table2: FnvHashMap<K, Vec<(V2, G::Timestamp, Delta)>> = HashMap::with_capacity_and_hasher(capacity, Default::default());
+    // the number of the last arranged record received; records arrive in the order they were numbered.
+    let mut seen = None;
+
+    let exchange2 = Exchange::new(|x: &((K, V2), Delta)| fnv_hash(&(x.0).0));
+
+    Collection::new(arranged.stream.inner.binary_stream(&input.inner, Pipeline, exchange2, "JoinArranged", move |input1, input2, output| {
+
+        while let Some((time, data)) = input1.next() {
+            let time1 = time.time();
+            for (((key, val1), number), wgt1) in data.drain(..) {
+                if let Some(matches) = table2.get(&key) {
+                    for &(ref val2, ref time2, wgt2) in matches.iter() {
+                        let upper = time1.join(time2);
+                        output.session(&time.delayed(&upper))
+                              .give(((key.clone(), val1.clone(), val2.clone()), wgt1 * wgt2));
+                    }
+                }
+                seen = Some(number);
+            }
+        }
+
+        while let Some((time, data)) = input2.next() {
+            let time2 = time.time();
+            let index = index.borrow();
+            for ((key, val2), wgt2) in data.drain(..) {
+                if let (Some(matches), Some(seen)) = (index.get(&key), seen) {
+                    for &(ref val1, ref time1, wgt1, _) in matches.iter().filter(|x| x.3 <= seen) {
+                        let upper = time2.join(time1);
+                        output.session(&time.delayed(&upper))
+                              .give(((key.clone(), val1.clone(), val2.clone()), wgt1 * wgt2));
+                    }
+                }
+                table2.entry(key).or_insert(Vec::new()).push((val2, time2.clone(), wgt2));
+            }
+        }
+    }))
+}
@@ -8,9 +8,32 @@ extern crate timely;
 extern crate timely_sort;
 extern crate graph_map;
 extern crate differential_dataflow;
+extern crate abomonation;
+
+pub mod spill;
+pub mod bench;
+pub mod accounting;
+pub mod join;
+pub mod radix;
+pub mod profile;
+pub mod diagnostics;
+pub mod testing;
+pub mod interactive;
+pub mod generic;
+pub mod replay;
+pub mod lineage;
+pub mod summary;
+pub mod speculate;
+pub mod cache;
+pub mod tenants;
+pub mod budget;
+pub mod delay;
+#[cfg(feature = "tui")]
+pub mod explorer;
 
 use std::rc::Rc;
 use std::hash::Hash;
+use std::collections::HashMap;
 
 use timely::progress::Timestamp;
 
@@ -18,6 +41,7 @@ use timely::dataflow::*;
 use timely::dataflow::scopes::Child;
 use timely::dataflow::operators::*;
 use timely::dataflow::operators::feedback::Handle;
+use timely::dataflow::channels::pact::{Pipeline, Exchange};
 use timely::progress::timestamp::RootTimestamp;
 use timely::progress::nested::product::Product;
 
@@ -27,6 +51,25 @@ use differential_dataflow::{Data, Collection, Delta};
 use differential_dataflow::operators::*;
 use differential_dataflow::lattice::Lattice;
 
+use spill::{Spill, SpillConfig};
+
+/// Counter for rounds of the correction loop and of the explanation fixpoint.
+///
+/// Interactive sessions may run for very many rounds, and `Round::max_value()` is reserved both as the loop
+/// bound and as the time of queries that ask about an output's final value, so rounds are counted in 64 bits.
+/// See `diagnostics::exhausted` for reporting queries whose correction runs on for very many rounds.
+pub type Round = u64;
+
+/// A half-open interval `[start, end)` of application time, during which a record is valid.
+pub type Interval = (u64, u64);
+
+/// The interval during which both `a` and `b` are valid, if they overlap.
+pub fn overlap(a: Interval, b: Interval) -> Option<Interval> {
+    let start = ::std::cmp::max(a.0, b.0);
+    let end = ::std::cmp::min(a.1, b.1);
+    if start < end { Some((start, end)) } else { None }
+}
+
 /// A explanation-tracking collection.
 ///
 /// A `Variable` represents a differential dataflow collection, but also two additional collections corresponding to 
@@ -43,7 +86,7 @@ where
     G: Scope, 
     K: Data+Default, 
     V: Data+Default, 
-    Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, u32>>,
+    Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
     G::Timestamp: Ord+Hash {
     /// The collection itself.
     pub stream: Collection<G, (K, V)>,
@@ -51,44 +94,873 @@ where
     pub working: Collection<G, (K, V)>,
     /// A collection of elements required for explanation.
     pub depends: MonotonicVariable<'a, Gp, (K, V, G::Timestamp, u32)>,
+    /// The lifted `stream` and `working` records, once some operator has needed them.
+    pub lifted: Option<Collection<G, ((K, V), G::Timestamp)>>,
+    /// An id naming the variable's records in derivation edges.
+    pub id: u32,
+    /// Derivation edges recorded for this variable and those connected to it; see `lineage`.
+    pub lineage: lineage::Registry<Collection<Child<'a, Gp, Round>, lineage::Edge>>,
 }
 
 impl<'a,
      G: Scope, 
      K: Data+Default, 
      V: Data+Default, 
-     Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, u32>>> 
+     Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>> 
 Variable<'a, G, K, V, Gp> where G::Timestamp: Ord+Hash {
     /// Constructs a new `Variable` from collections and the explanation-tracking scope.
     pub fn new(
         source: Collection<G, (K, V)>, 
         working: Collection<G, (K, V)>, 
-        prov: &mut Child<'a, Gp, u32>) -> Variable<'a, G, K, V, Gp> {
+        prov: &mut Child<'a, Gp, Round>) -> Variable<'a, G, K, V, Gp> {
 
         Variable {
             stream: source,
             working: working,
             depends: MonotonicVariable::new(prov),
+            lifted: None,
+            id: lineage::next_id(),
+            lineage: lineage::Registry::new(),
+        }
+    }
+
+    /// Like `new`, but with requirements accumulated in `shards` key ranges each `width` keys wide.
+    ///
+    /// Keys beyond the last range wrap around to the first. See `MonotonicVariable::new_sharded`.
+    pub fn new_sharded(
+        source: Collection<G, (K, V)>,
+        working: Collection<G, (K, V)>,
+        prov: &mut Child<'a, Gp, Round>,
+        shards: usize,
+        width: u64) -> Variable<'a, G, K, V, Gp> where K: Unsigned {
+
+        Variable {
+            stream: source,
+            working: working,
+            depends: MonotonicVariable::new_sharded(prov, shards, move |&(ref k,_,_,_)| (k.as_u64() / width) as usize),
+            lifted: None,
+            id: lineage::next_id(),
+            lineage: lineage::Registry::new(),
         }
     }
+
+    /// Introduces `queries` as initial requirements of the variable, to be explained along with any others.
+    ///
+    /// Queries are `(key, val, time, query)` records in the scope containing the explanation scope, and need not
+    /// come from an input: any collection there may supply them, for example one that watches the variable's own
+    /// outputs for anomalies and asks about each it finds. Derived queries share the fixpoint with external ones,
+    /// and so should use query identifiers the external queries do not.
+    pub fn seed(&mut self, queries: &Collection<Gp, (K, V, G::Timestamp, u32)>) where G::Timestamp: Data {
+        let scope = self.depends.scope();
+        self.depends.add(&queries.enter(&scope));
+    }
+
+    /// Introduces the `queries` that `allowed` admits, as `seed`, and reports a `Rejected` diagnostic for each other.
+    ///
+    /// Rejected queries never enter the explanation scope, so nothing is computed over the inputs they would have
+    /// reached; see `diagnostics::admit` for the split, and for predicates by key range and by client namespace.
+    pub fn seed_admitted<F>(&mut self, queries: &Collection<Gp, (K, V, G::Timestamp, u32)>, allowed: F) -> Collection<Gp, diagnostics::Diagnostic>
+        where G::Timestamp: Data, F: Fn(&(K, V, G::Timestamp, u32))->bool+'static {
+        let (admitted, rejected) = diagnostics::admit(queries, allowed);
+        self.seed(&admitted);
+        rejected
+    }
+
+    /// Records that requirements on `output` required records of `self`, as `(record, output record, query)`
+    /// `edges`, and merges the lineage of the two variables.
+    ///
+    /// Instrumented operators call this for each of their inputs; `edges` is only built with `lineage` enabled.
+    pub fn lineage_to<G2, K2, V2, F>(&self, output: &Variable<'a, G2, K2, V2, Gp>, edges: F)
+        where G2: Scope, K2: Data+Default, V2: Data+Default, G2::Timestamp: Ord+Hash,
+              F: FnOnce()->Collection<Child<'a, Gp, Round>, ((K, V), (K2, V2), u32)> {
+        output.lineage.merge(&self.lineage);
+        if lineage::enabled() {
+            output.lineage.push(lineage::edges(self.id, output.id, &edges()));
+        }
+    }
+
+    /// Records that `self`, the result of a loop body, supports the records of `variable`, the loop variable it
+    /// feeds back into.
+    ///
+    /// Loops call this as they connect their feedback, as requirements on the loop variable become requirements
+    /// on the result of the previous iteration without passing through any operator.
+    pub fn lineage_feedback(&self, variable: &Variable<'a, G, K, V, Gp>) {
+        self.lineage_to(variable, || variable.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
+    }
+
+    /// The distinct derivation edges recorded for this variable and those connected to it, as `(support,
+    /// supported, query)`; empty unless `lineage` is enabled.
+    pub fn provenance(&self) -> Collection<Child<'a, Gp, Round>, lineage::Edge> {
+        let none = self.depends.stream.filter(|_| false).map(|_| ((0, 0), (0, 0), 0));
+        self.lineage.collections()
+            .into_iter()
+            .fold(none, |all, edges| all.concat(&edges))
+            .threshold(|_, w| if w > 0 { 1 } else { 0 })
+    }
+
+    /// The derivation edges explaining why each of `asked`, `(record, query)`, was required of this variable: the
+    /// chains of requirements from the record to those `query` asked about. Empty unless `lineage` is enabled.
+    pub fn why(&self, asked: &Collection<Child<'a, Gp, Round>, ((K, V), u32)>) -> Collection<Child<'a, Gp, Round>, lineage::Edge> {
+        let id = self.id;
+        lineage::why(&self.provenance(), &asked.map(move |(x,q)| (lineage::node(id, &x), q)))
+    }
+}
+
+/// Strategies for building the indices behind an instrumented join.
+///
+/// The plan is chosen for each call to `Variable::join_u_planned`, rather than for a scope, so that each join can
+/// use whichever suits its inputs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum JoinPlan {
+    /// Separate indices for the `stream` and `working` collections of each input.
+    Separate,
+    /// One index per input, shared by the `stream` and `working` collections.
+    Shared,
+}
+
+/// Which members of a group are demanded when an output of `group_with_policy!` is explained.
+///
+/// Applications legitimately differ here: auditing a count wants every member counted, while debugging a
+/// selection is served by any one member that would produce the same output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExplanationPolicy {
+    /// Every member of the group, which reproduces the output of any grouping logic.
+    AllMembers,
+    /// The least member of the group, which names what was grouped but need not reproduce the output.
+    AnyWitness,
+    /// The shortest run of least members from which the grouping logic produces the same output.
+    MinimalSelective,
+}
+
+/// The members of a group demanded under `policy`, from its `values` and their counts in order, where `logic`
+/// computes the group's output.
+pub fn witnesses<V: Clone, V2: Eq, F: Fn(&[(V, i32)])->V2>(policy: ExplanationPolicy, values: &[(V, i32)], logic: &F) -> Vec<V> {
+    let count = match policy {
+        ExplanationPolicy::AllMembers => values.len(),
+        ExplanationPolicy::AnyWitness => ::std::cmp::min(values.len(), 1),
+        ExplanationPolicy::MinimalSelective => {
+            let output = logic(values);
+            (1 .. values.len()).find(|&count| logic(&values[.. count]) == output).unwrap_or(values.len())
+        },
+    };
+    values[.. count].iter().map(|x| x.0.clone()).collect()
+}
+
+/// A variable whose actual and working records are indexed by key, for joins to share.
+///
+/// Created by `Variable::arrange_by_key`, which it borrows, so that joins through it can add requirements to it.
+/// The indices never discard state; see `arrange_by_key` for when that costs more than it saves.
+pub struct ArrangedVariable<'v, 'a: 'v, G, K, V, Gp>
+where G: Scope,
+      K: Data+Default,
+      V: Data+Default,
+      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
+      G::Timestamp: Ord+Hash {
+    /// The arranged variable.
+    pub variable: &'v mut Variable<'a, G, K, V, Gp>,
+    /// The arranged actual records.
+    pub stream: join::Arranged<G, K, V>,
+    /// The arranged working records.
+    pub working: join::Arranged<G, K, V>,
+}
+
+impl<'v, 'a, G, K, V, Gp> ArrangedVariable<'v, 'a, G, K, V, Gp> where
+    G: Scope,
+    K: Data+Default+Hash,
+    V: Data+Default,
+    Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
+    G::Timestamp: Ord+Hash+Lattice {
+    /// Joins the arranged variable with `other`, as `Variable::join` but reusing the arranged indices.
+    pub fn join<V2>(&mut self, other: &mut Variable<'a, G, K, V2, Gp>) -> Variable<'a, G, K, (V, V2), Gp>
+        where V2: Data+Default {
+
+        let result = Variable::new(
+            join::join_arranged(&self.stream, &other.stream, 0).map(|(x,y,z)| (x,(y,z))),
+            join::join_arranged(&self.working, &other.working, 0).map(|(x,y,z)| (x,(y,z))),
+            &mut self.variable.depends.scope()
+        );
+
+        // add each component of joined results to the requirements of each input
+        self.variable.depends.add(&result.depends.stream.map(|(x,(y,_),t,q)| (x,y,t,q)));
+        other.depends.add(&result.depends.stream.map(|(x,(_,z),t,q)| (x,z,t,q)));
+        self.variable.lineage_to(&result, || result.depends.stream.map(|(x,(y,z),_,q)| ((x.clone(),y.clone()),(x,(y,z)),q)));
+        other.lineage_to(&result, || result.depends.stream.map(|(x,(y,z),_,q)| ((x.clone(),z.clone()),(x,(y,z)),q)));
+        result
+    }
+}
+
+/// Presents each record of a collection along with the time at which it changed, as explainable data.
+///
+/// The collection is consolidated first, so that each `(record, time)` pair is produced once. Output buffers are
+/// re-used across batches, and the time is read from each batch's capability once rather than per record.
+pub fn lift<G: Scope, D: Data>(collection: &Collection<G, D>) -> Collection<G, (D, G::Timestamp)> 
+    where G::Timestamp: Data {
+
+    let mut buffer = Vec::new();
+    Collection::new(
+        collection.consolidate()
+                  .inner
+                  .unary_stream(Pipeline, "lifting", move |input, output| {
+
+            while let Some((time, data)) = input.next() {
+                let stamp = time.time();
+                for (datum, _weight) in data.drain(..) {
+                    buffer.push(((datum, stamp.clone()), 1));
+                }
+                output.session(&time).give_iterator(buffer.drain(..));
+            }
+        })
+    )
+}
+
+/// Presents each change to a collection along with the time at which it happened, weighted by the change.
+///
+/// Unlike `lift`, which presents each changed record once whatever the change, the weights here accumulate: the
+/// total weight of a record's changes at times less or equal to some time is its count at that time.
+pub fn lift_weighted<G: Scope, D: Data>(collection: &Collection<G, D>) -> Collection<G, (D, G::Timestamp)>
+    where G::Timestamp: Data {
+
+    Collection::new(
+        collection.consolidate()
+                  .inner
+                  .unary_stream(Pipeline, "LiftWeighted", move |input, output| {
+
+            while let Some((time, data)) = input.next() {
+                let stamp = time.time();
+                output.session(&time).give_iterator(data.drain(..).map(|(datum, weight)| ((datum, stamp.clone()), weight)));
+            }
+        })
+    )
+}
+
+/// Reports, for each required record, its count in `input` as of the time of the query requiring it.
+///
+/// `need` holds `(key, val, time, query)` requirements and `queries` the `(.., .., time, query)` queries they
+/// were derived for. The result holds `((key, val), query)` once for each distinct required record and query,
+/// weighted by the record's count at the query's time, so that a query about an earlier epoch is checked against
+/// the input as it was then: records inserted later are not present, and records deleted later still are. For a
+/// query about the whole history this is the record's current count.
+pub fn count_at_query<G, K, V, K2, V2>(need: &Collection<G, (K, V, G::Timestamp, u32)>, input: &Collection<G, (K, V)>, queries: &Collection<G, (K2, V2, G::Timestamp, u32)>) -> Collection<G, ((K, V), u32)>
+    where G: Scope, K: Data+Default+Hash, V: Data+Default+Hash, K2: Data, V2: Data, G::Timestamp: Data+Lattice+Ord {
+
+    let times = queries.map(|(_,_,t,q)| (q,t));
+    let requests = need.map(|(k,v,_t,q)| (q,(k,v)))
+                       .threshold(|_, w| if w > 0 { 1 } else { 0 })
+                       .join_u(&times)
+                       .map(|(q,x,t)| (x,(q,t)));
+
+    // a differential join, rather than `join::join_bounded`, so that requests for withdrawn queries are compacted
+    // away instead of being kept for as long as the dataflow runs.
+    lift_weighted(input).join(&requests)
+                        .filter(|&(_, ref t1, (_, ref t2))| t1 <= t2)
+                        .map(|(x,_,(q,_))| (x,q))
+}
+
+/// Holds back `data` until some record has been observed on `flag`, on any worker.
+///
+/// The instrumented operators use this to avoid feeding lifted collections into their explanation joins before
+/// any requirement has reached them, so that computations nobody has asked about pay little for instrumentation.
+/// Data is released at the least upper bound of its time and the time of the first flag record, which preserves
+/// its visibility to requirements at or after that time. Lifted collections record their own times in their data,
+/// and so lose nothing by being released late. Data arriving after the gate opens is released at the same bound
+/// as data held back, so that when data is released does not depend on whether it arrived before the flag.
+pub fn gate<G: Scope, D: Data, F: Data>(data: &Collection<G, D>, flag: &Collection<G, F>) -> Collection<G, D> 
+    where G::Timestamp: Lattice {
+
+    // each worker announces its first flag record to all workers.
+    let mut announced = false;
+    let flag = flag.inner.unary_stream(Pipeline, "GateFlag", move |input, output| {
+        while let Some((time, data)) = input.next() {
+            if !announced && data.len() > 0 {
+                output.session(&time).give(());
+                announced = true;
+            }
+        }
+    });
+
+    // the time of the first flag record, once one has been seen.
+    let mut opened: Option<G::Timestamp> = None;
+    let mut buffer = Vec::new();
+    Collection::new(data.inner.binary_stream(&flag.broadcast(), Pipeline, Pipeline, "Gate", move |input1, input2, output| {
+
+        while let Some((time, _)) = input2.next() {
+            if opened.is_none() {
+                opened = Some(time.time());
+                for (stamp, batch) in buffer.drain(..) {
+                    let stamp: G::Timestamp = stamp;
+                    let upper = stamp.join(&time.time());
+                    output.session(&time.delayed(&upper)).give_iterator(Vec::into_iter(batch));
+                }
+            }
+        }
+
+        while let Some((time, data)) = input1.next() {
+            if let Some(ref flag_time) = opened {
+                let upper = time.time().join(flag_time);
+                output.session(&time.delayed(&upper)).give_iterator(data.drain(..));
+            }
+            else {
+                buffer.push((time.time(), data.drain(..).collect::<Vec<_>>()));
+            }
+        }
+    }))
+}
+
+/// Reports the identifiers of withdrawn queries, once the epochs in which they were withdrawn are complete.
+///
+/// A query is withdrawn by retracting its seed record. Only once the input frontier has passed the retraction
+/// can we be sure that no further requirements will be demanded on its behalf, at which point its identifier
+/// is produced so that the state it accumulated can be retired. Retirement lasts only until the identifier is
+/// asked again: the epoch in which it is re-asked retracts it, so that the new query accumulates requirements
+/// as any other would, rather than having them retired as they arrive.
+pub fn retired_queries<G: Scope, D: Data, F: Fn(&D)->u32+'static>(queries: &Collection<G, D>, id: F) -> Collection<G, u32>
+    where G::Timestamp: Ord {
+
+    // each identifier is tracked at one worker, which sees all of its seed records.
+    let id = Rc::new(id);
+    let route = id.clone();
+    let exchange = Exchange::new(move |x: &(D, i32)| route(&x.0) as u64);
+
+    // accumulated weights of each identifier, identifiers currently retired, and updates awaiting completion.
+    let mut counts = HashMap::<u32, i32>::new();
+    let mut retired = Vec::<u32>::new();
+    let mut pending = Vec::<(G::Timestamp, Vec<(u32, i32)>)>::new();
+    Collection::new(queries.inner.unary_notify(exchange, "RetiredQueries", vec![], move |input, output, notificator| {
+        while let Some((time, data)) = input.next() {
+            let updates = data.drain(..).map(|(x,w)| (id(&x), w)).collect::<Vec<_>>();
+            if let Some(position) = pending.iter().position(|x| x.0 == time.time()) {
+                pending[position].1.extend(updates);
+            }
+            else {
+                pending.push((time.time(), updates));
+            }
+            notificator.notify_at(time);
+        }
+
+        // epochs completing together are applied in order, as each builds on the counts before it.
+        let mut ready = Vec::new();
+        while let Some((time, _count)) = notificator.next() { ready.push(time); }
+        ready.sort_by(|x, y| x.time().cmp(&y.time()));
+
+        for time in ready {
+            if let Some(position) = pending.iter().position(|x| x.0 == time.time()) {
+                let (_, mut batch) = pending.swap_remove(position);
+                batch.sort();
+                let mut session = output.session(&time);
+                for &(query, weight) in batch.iter() {
+                    *counts.entry(query).or_insert(0) += weight;
+                }
+                let mut queries = batch.iter().map(|x| x.0).collect::<Vec<_>>();
+                queries.dedup();
+                for query in queries {
+                    let count = counts.get(&query).map(|&c| c).unwrap_or(0);
+                    let position = retired.iter().position(|&q| q == query);
+                    match (count > 0, position) {
+                        (false, None) => { retired.push(query); session.give((query, 1)); },
+                        (true, Some(position)) => { retired.swap_remove(position); session.give((query, -1)); },
+                        _ => { },
+                    }
+                    if count == 0 { counts.remove(&query); }
+                }
+            }
+        }
+    }))
+}
+
+/// Collapses queries asking the same `(key, val, time)` question onto a single representative query.
+///
+/// Returns the representative queries, each carrying the least identifier among the queries asking its question,
+/// and `(representative, query)` pairs relating each representative to every query it stands for, itself
+/// included. Explaining only the representatives shares one fixpoint between identical queries, and `fan_out`
+/// then reports the results to each of them. Query times are assumed totally ordered, as in a streaming scope;
+/// withdrawing a representative promotes the next least identifier in its place.
+pub fn dedup_queries<G, K, V, T>(queries: &Collection<G, (K, V, T, u32)>) -> (Collection<G, (K, V, T, u32)>, Collection<G, (u32, u32)>)
+    where G: Scope, K: Data+Hash, V: Data+Hash, T: Data+Hash, G::Timestamp: Ord {
+
+    // accumulated weights of each question's queries, and updates awaiting their time's completion.
+    let mut counts = HashMap::<(K, V, T), Vec<(u32, i32)>>::new();
+    let mut pending = Vec::<(G::Timestamp, Vec<((K, V, T, u32), i32)>)>::new();
+
+    let exchange = Exchange::new(|x: &((K, V, T, u32), i32)| { let (ref k, ref v, ref t, _) = x.0; join::fnv_hash(&(k, v, t)) });
+    let aliased = queries.inner.unary_notify(exchange, "DedupQueries", vec![], move |input, output, notificator| {
+
+        while let Some((time, data)) = input.next() {
+            if let Some(position) = pending.iter().position(|x| x.0 == time.time()) {
+                pending[position].1.extend(data.drain(..));
+            }
+            else {
+                pending.push((time.time(), data.drain(..).collect()));
+            }
+            notificator.notify_at(time);
+        }
+
+        // several epochs may complete at once, and must be applied in order, as each builds on the counts before it.
+        let mut ready = Vec::new();
+        while let Some((time, _count)) = notificator.next() { ready.push(time); }
+        ready.sort_by(|x, y| x.time().cmp(&y.time()));
+
+        for time in ready {
+            if let Some(position) = pending.iter().position(|x| x.0 == time.time()) {
+                let (_, mut batch) = pending.swap_remove(position);
+                batch.sort();
+                let mut session = output.session(&time);
+                let mut index = 0;
+                while index < batch.len() {
+
+                    // apply each question's updates, retracting its old aliases and producing its new ones.
+                    let question = { let (ref k, ref v, ref t, _) = batch[index].0; (k.clone(), v.clone(), t.clone()) };
+                    let mut list = counts.remove(&question).unwrap_or(Vec::new());
+                    let old = aliases(&list);
+                    while index < batch.len() && { let (ref k, ref v, ref t, _) = batch[index].0; (k, v, t) == (&question.0, &question.1, &question.2) } {
+                        let (q, w) = ((batch[index].0).3, batch[index].1);
+                        match list.iter().position(|x| x.0 == q) {
+                            Some(position) => list[position].1 += w,
+                            None => list.push((q, w)),
+                        }
+                        index += 1;
+                    }
+                    list.retain(|x| x.1 != 0);
+                    let new = aliases(&list);
+                    for &(rep, q) in old.iter().filter(|x| !new.contains(x)) {
+                        session.give(((question.0.clone(), question.1.clone(), question.2.clone(), rep, q), -1));
+                    }
+                    for &(rep, q) in new.iter().filter(|x| !old.contains(x)) {
+                        session.give(((question.0.clone(), question.1.clone(), question.2.clone(), rep, q), 1));
+                    }
+                    if !list.is_empty() { counts.insert(question, list); }
+                }
+            }
+        }
+    });
+
+    let aliased = Collection::new(aliased);
+    let representatives = aliased.filter(|x| x.3 == x.4).map(|(k,v,t,rep,_)| (k,v,t,rep));
+    let aliases = aliased.map(|(_,_,_,rep,q)| (rep,q));
+    (representatives, aliases)
+}
+
+/// Maintains explanations of `hot` outputs, and answers queries about them from the maintained requirements.
+///
+/// Outputs queried over and over, such as the labels of popular nodes, are better explained once and kept up to
+/// date than explained afresh for each query. Each of `hot`, `(key, val, id)`, is asked as a query about the
+/// output's whole history under `id`, for as long as it is hot, so that its requirements are maintained as the
+/// inputs change. Queries about the whole history of a hot output are then answered by its requirements, without
+/// reaching the correction loop, and other queries pass through. Returns the queries to explain, and
+/// `(query, query)` pairs with which `fan_out` reports the requirements of each query, relating hot identifiers to
+/// the queries they answer; requirements of the hot identifiers themselves are not reported. An output hot under
+/// several identifiers answers its queries with the requirements of each.
+pub fn serve_hot<G, K, V>(queries: &Collection<G, (K, V, Product<Product<RootTimestamp, u32>, Round>, u32)>, hot: &Collection<G, (K, V, u32)>)
+    -> (Collection<G, (K, V, Product<Product<RootTimestamp, u32>, Round>, u32)>, Collection<G, (u32, u32)>)
+    where G: Scope, K: Data+Default+Hash, V: Data+Default+Hash, G::Timestamp: Lattice+Ord {
+
+    let history = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+
+    // queries about the whole history of a hot output are served by its requirements.
+    let served = queries.filter(move |x| x.2 == history)
+                        .map(|(k,v,_,q)| ((k,v),q))
+                        .join(&hot.map(|(k,v,h)| ((k,v),h)))
+                        .map(|((k,v),q,h)| (k,v,q,h))
+                        .threshold(|_, w| if w > 0 { 1 } else { 0 });
+
+    // an output hot under several identifiers still serves each query once, and withdraws it from the loop once.
+    let answered = served.map(|(k,v,q,_)| (k,v,q)).threshold(|_, w| if w > 0 { 1 } else { 0 });
+    let cold = queries.concat(&answered.map(move |(k,v,q)| (k,v,history,q)).negate());
+    let standing = hot.map(move |(k,v,h)| (k,v,history,h));
+    let aliases = served.map(|(_,_,q,h)| (h,q)).concat(&cold.map(|x| (x.3,x.3)));
+    (cold.concat(&standing), aliases)
+}
+
+/// Asks standing subscriptions afresh as the inputs change, so that their explanations follow the inputs.
+///
+/// A query about the whole history of an output accumulates requirements as the inputs change, and never sheds
+/// those that no longer matter. Subscriptions `(key, val, subscription)` are instead asked as of the epoch they
+/// arrive at, and asked again as of each later epoch at which `changes`, any collection updated whenever the
+/// explained inputs are, such as the inputs themselves, has updates; the query asked before is then withdrawn.
+/// Subscriptions are not asked again at epochs without changes, as their explanations would not move. Returns the
+/// queries, and `(query, subscription)` pairs relating each to its subscription, with which `fan_out` reports
+/// requirements under the subscription's identifier. As each query replaces the last, subscribers see additions
+/// and retractions that leave them holding the current explanation.
+///
+/// Query identifiers are counted up from `base`, and must not be used by other queries. The identifier of a
+/// withdrawn query is reused from the next epoch on, so that identifiers in use stay few however long the
+/// subscriptions run.
+pub fn subscribe<G, K, V, D>(subscriptions: &Collection<G, (K, V, u32)>, changes: &Collection<G, D>, base: u32) -> (Collection<G, (K, V, Product<Product<RootTimestamp, u32>, Round>, u32)>, Collection<G, (u32, u32)>)
+    where G: Scope<Timestamp=Product<RootTimestamp, u32>>, K: Data, V: Data, D: Data {
+
+    // each worker marks the epochs at which it saw changes once for each batch, rather than sending them on.
+    let marks = changes.inner.unary_stream(Pipeline, "SubscribeChanges", |input, output| {
+        while let Some((time, data)) = input.next() {
+            if data.len() > 0 { output.session(&time).give(()); }
+        }
+    });
+
+    // accumulated subscription weights, updates awaiting their time's completion, epochs with changes, the
+    // standing queries with their subscriptions, identifiers free for reuse, and the next fresh identifier.
+    let mut active = Vec::<((K, V, u32), i32)>::new();
+    let mut pending = Vec::<(Product<RootTimestamp, u32>, Vec<((K, V, u32), i32)>)>::new();
+    let mut changed = Vec::<Product<RootTimestamp, u32>>::new();
+    let mut issued = Vec::<(K, V, Product<Product<RootTimestamp, u32>, Round>, u32, u32)>::new();
+    let mut free = Vec::<u32>::new();
+    let mut next = base;
+
+    // identifiers are issued at one worker, so that no two workers issue the same one.
+    let exchange1 = Exchange::new(|_: &((K, V, u32), i32)| 0);
+    let exchange2 = Exchange::new(|_: &()| 0);
+    let issued_stream = subscriptions.inner.binary_notify(&marks, exchange1, exchange2, "Subscribe", vec![], move |input1, input2, output, notificator| {
+
+        while let Some((time, data)) = input1.next() {
+            pending.push((time.time(), data.drain(..).collect()));
+            notificator.notify_at(time);
+        }
+        while let Some((time, _data)) = input2.next() {
+            changed.push(time.time());
+            notificator.notify_at(time);
+        }
+
+        // several epochs may complete at once, and are handled in order.
+        let mut ready = Vec::new();
+        while let Some((time, _count)) = notificator.next() { ready.push(time); }
+        ready.sort_by(|x, y| x.time().cmp(&y.time()));
+
+        for time in ready {
+            for entry in pending.iter_mut().filter(|x| x.0 == time.time()) {
+                for (subscription, weight) in entry.1.drain(..) {
+                    match active.iter().position(|x| x.0 == subscription) {
+                        Some(position) => active[position].1 += weight,
+                        None => active.push((subscription, weight)),
+                    }
+                }
+            }
+            pending.retain(|x| !x.1.is_empty());
+            active.retain(|x| x.1 != 0);
+            let inputs_changed = changed.iter().any(|x| x == &time.time());
+            changed.retain(|x| x != &time.time());
+
+            // withdraw the queries of cancelled subscriptions, and of all subscriptions if the inputs changed.
+            let mut session = output.session(&time);
+            let mut withdrawn = Vec::new();
+            let mut standing = Vec::new();
+            for query in issued.drain(..) {
+                let live = active.iter().any(|x| x.1 > 0 && (x.0).0 == query.0 && (x.0).1 == query.1 && (x.0).2 == query.4);
+                if live && !inputs_changed { standing.push(query); }
+                else {
+                    withdrawn.push(query.3);
+                    session.give((query, -1));
+                }
+            }
+            issued = standing;
+
+            // ask each subscription without a standing query as of this epoch.
+            let stamp = Product::new(time.time(), Round::max_value());
+            for &((ref key, ref val, subscription), _) in active.iter().filter(|x| x.1 > 0) {
+                if !issued.iter().any(|x| &x.0 == key && &x.1 == val && x.4 == subscription) {
+                    let id = free.pop().unwrap_or_else(|| { next += 1; next - 1 });
+                    let query = (key.clone(), val.clone(), stamp.clone(), id, subscription);
+                    session.give((query.clone(), 1));
+                    issued.push(query);
+                }
+            }
+
+            // identifiers withdrawn at this epoch are reused from the next one on.
+            free.extend(withdrawn);
+        }
+    });
+
+    let issued = Collection::new(issued_stream);
+    (issued.map(|(k,v,t,q,_)| (k,v,t,q)), issued.map(|(_,_,_,q,s)| (q,s)))
+}
+
+/// Withdraws each query `ttl(query)` epochs after it was last asked, unless it was withdrawn sooner.
+///
+/// Updates to `queries` pass through unchanged, while the operator tracks each query's accumulated weight and when
+/// it was last asked. A query still outstanding when its time to live lapses is withdrawn, by retracting its
+/// accumulated weight; a query withdrawn sooner has nothing left to retract, and a later withdrawal of an expired
+/// query is absorbed rather than passed on, so the result never holds a negative query. Asking an expired query
+/// again reinstates it, with a fresh time to live. Forgotten interactive queries then retire on their own; with
+/// `Variable::retire` and `retired_queries` downstream, their requirements are released as well.
+pub fn expire<G, K, V, T, F>(queries: &Collection<G, (K, V, T, u32)>, ttl: F) -> Collection<G, (K, V, T, u32)>
+    where G: Scope<Timestamp=Product<RootTimestamp, u32>>, K: Data+Hash, V: Data+Hash, T: Data+Hash, F: Fn(&(K, V, T, u32))->u32+'static {
+
+    // each query is tracked at one worker, which sees all of its updates.
+    let exchange = Exchange::new(|x: &((K, V, T, u32), i32)| (x.0).3 as u64);
+
+    // accumulated weight, whether expired, and epoch of expiry of each query; updates awaiting completion.
+    let mut state = HashMap::<(K, V, T, u32), (i32, bool, u32)>::new();
+    let mut pending = Vec::<(Product<RootTimestamp, u32>, Vec<((K, V, T, u32), i32)>)>::new();
+    Collection::new(queries.inner.unary_notify(exchange, "Expire", vec![], move |input, output, notificator| {
+
+        while let Some((time, data)) = input.next() {
+            if let Some(position) = pending.iter().position(|x| x.0 == time.time()) {
+                pending[position].1.extend(data.drain(..));
+            }
+            else {
+                pending.push((time.time(), data.drain(..).collect()));
+            }
+            notificator.notify_at(time);
+        }
+
+        // epochs completing together are applied in order, as each builds on the state before it.
+        let mut ready = Vec::new();
+        while let Some((time, _count)) = notificator.next() { ready.push(time); }
+        ready.sort_by(|x, y| x.time().cmp(&y.time()));
+
+        for time in ready {
+            let epoch = time.time().inner;
+            let mut session = output.session(&time);
+
+            // apply this epoch's updates, passing on their effect on the visible weight of each query.
+            if let Some(position) = pending.iter().position(|x| x.0 == time.time()) {
+                let (_, batch) = pending.swap_remove(position);
+                for (query, weight) in batch {
+                    let (before, after, expiry) = {
+                        let entry = state.entry(query.clone()).or_insert((0, false, 0));
+                        let before = if entry.1 { 0 } else { ::std::cmp::max(entry.0, 0) };
+                        entry.0 += weight;
+                        if weight > 0 {
+                            entry.1 = false;
+                            entry.2 = epoch.saturating_add(ttl(&query));
+                        }
+                        let after = if entry.1 { 0 } else { ::std::cmp::max(entry.0, 0) };
+                        (before, after, if weight > 0 { Some(entry.2) } else { None })
+                    };
+                    if after != before { session.give((query.clone(), after - before)); }
+                    if let Some(expiry) = expiry {
+                        notificator.notify_at(time.delayed(&RootTimestamp::new(expiry)));
+                    }
+                    if state.get(&query).map(|x| x.0 <= 0).unwrap_or(false) { state.remove(&query); }
+                }
+            }
+
+            // queries whose time to live lapses now are withdrawn, if still outstanding.
+            for (query, entry) in state.iter_mut() {
+                if !entry.1 && entry.2 == epoch {
+                    entry.1 = true;
+                    if entry.0 > 0 { session.give((query.clone(), -entry.0)); }
+                }
+            }
+        }
+    }))
+}
+
+// the `(representative, query)` pairs for a question's accumulated query weights.
+fn aliases(list: &[(u32, i32)]) -> Vec<(u32, u32)> {
+    let present = list.iter().filter(|x| x.1 > 0).map(|x| x.0).collect::<Vec<_>>();
+    match present.iter().min() {
+        Some(&rep) => present.iter().map(|&q| (rep, q)).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Reports results derived for representative queries to each query they stand for.
+///
+/// `results` are `(record, representative)` pairs, and `aliases` the `(representative, query)` pairs produced by
+/// `dedup_queries`; the result is the `(record, query)` pairs, as if each query had been explained separately.
+pub fn fan_out<G: Scope, D: Data>(results: &Collection<G, (D, u32)>, aliases: &Collection<G, (u32, u32)>) -> Collection<G, (D, u32)>
+    where G::Timestamp: Lattice+Ord {
+    results.map(|(x,rep)| (rep,x))
+           .join_u(aliases)
+           .map(|(_,x,q)| (x,q))
+}
+
+/// Separates user metadata from queries submitted as `(key, val, time, query, metadata)`.
+///
+/// Metadata is whatever small payload a driver wants back with its answers, such as a request id, a user name,
+/// or a correlation token, and plays no part in the explanation. Returns the queries without their metadata, to be
+/// explained as usual, and `(query, metadata)` pairs for `echo_metadata` to attach to the results. Withdrawing
+/// a query withdraws its metadata along with it.
+pub fn strip_metadata<G, K, V, T, M>(queries: &Collection<G, (K, V, T, u32, M)>) -> (Collection<G, (K, V, T, u32)>, Collection<G, (u32, M)>)
+    where G: Scope, K: Data, V: Data, T: Data, M: Data {
+    (queries.map(|(k,v,t,q,_)| (k,v,t,q)), queries.map(|(_,_,_,q,m)| (q,m)))
+}
+
+/// Attaches to each `(record, query)` result the metadata its query was submitted with by `strip_metadata`.
+///
+/// Results of queries submitted without metadata are dropped, and a query submitted several times with different
+/// metadata has its results reported once with each.
+pub fn echo_metadata<G: Scope, D: Data, M: Data+Default>(results: &Collection<G, (D, u32)>, metadata: &Collection<G, (u32, M)>) -> Collection<G, (D, (u32, M))>
+    where G::Timestamp: Lattice+Ord {
+    results.map(|(x,q)| (q,x))
+           .join_u(metadata)
+           .map(|(q,x,m)| (x,(q,m)))
+}
+
+/// Forwards requirements on an intermediate collection as queries to the dataflow producing it.
+///
+/// When one instrumented dataflow's input is another's output, as when connected components labels feed some
+/// downstream aggregation, the downstream explanation ends at the intermediate records it requires. These
+/// `((key, val), query)` requirements become upstream queries `(key, val, time, query)`, where `time` is that of
+/// the downstream query in `queries` they were derived for, as the downstream explanation is of the records as
+/// they were then. Chains of more dataflows forward at each link.
+///
+/// Upstream queries are identified by `namespaces.id(link, query)`, so that they cannot collide with queries the
+/// upstream dataflow is asked directly, which should come from other clients' namespaces; `namespaces.local`
+/// recovers the downstream query's identifier from the upstream explanation.
+///
+/// Returns the upstream queries, and the identifiers of downstream queries too large for the link's namespace,
+/// whose requirements are not forwarded. Panics if `link` is not a client of `namespaces`.
+pub fn stitch<G, K, V, K2, V2, T>(must: &Collection<G, ((K, V), u32)>, queries: &Collection<G, (K2, V2, T, u32)>, namespaces: tenants::Namespaces, link: u32) -> (Collection<G, (K, V, T, u32)>, Collection<G, u32>)
+    where G: Scope, K: Data, V: Data, K2: Data, V2: Data, T: Data, G::Timestamp: Lattice+Ord {
+
+    // checked here, rather than for each query within the dataflow.
+    namespaces.id(link, 0);
+
+    let times = queries.filter(move |x| namespaces.fits(x.3)).map(|(_,_,t,q)| (q,t));
+    let forwarded = must.map(|((k,v),q)| (q,(k,v)))
+                        .join(&times)
+                        .map(move |(q,(k,v),t)| (k,v,t,namespaces.id(link, q)));
+    let unforwarded = queries.filter(move |x| !namespaces.fits(x.3)).map(|(_,_,_,q)| q);
+    (forwarded, unforwarded)
+}
+
+/// Separates requirements shared by several queries from those particular to one query.
+///
+/// `must` holds distinct `(record, query)` requirements, as explanations report them. Returns the shared core,
+/// as `(record, sharers)` for each record required by more than one query, with the queries requiring it in
+/// order, and the extras, as `(record, query)` for each record required by its query alone. Queries that share
+/// most of their witnesses, as queries about one connected component do, then report and retain their common
+/// records once, rather than once for each query.
+pub fn share<G, D>(must: &Collection<G, (D, u32)>) -> (Collection<G, (D, Vec<u32>)>, Collection<G, (D, u32)>)
+    where G: Scope, D: Data+Default+Hash, G::Timestamp: Lattice+Ord {
+    let sharers = must.group(|_x, s, t| t.push((s.map(|(q,_)| *q).collect::<Vec<_>>(), 1)));
+    let core = sharers.filter(|x| x.1.len() > 1);
+    let extras = must.semijoin(&sharers.filter(|x| x.1.len() == 1).map(|(x,_)| x));
+    (core, extras)
+}
+
+/// Reports `(query, epoch)` once the requirements of each query asked at `epoch` are complete.
+///
+/// `must` is any collection of requirements that have left the correction scope, such as those `cc::explain`
+/// returns; its frontier passing an epoch means every correction round for that epoch has finished. Each query
+/// asked at some epoch is then marked done at that epoch, and the marker is retracted when the query is withdrawn,
+/// so that downstream operators can react to completed queries without a probe held by the driver. A query asked
+/// again while still asked is marked once for each ask, and each withdrawal retracts the latest ask's marker, or
+/// cancels it if it is yet to be marked.
+pub fn completed<G, K, V, T, D>(queries: &Collection<G, (K, V, T, u32)>, must: &Collection<G, D>) -> Collection<G, (u32, u32)>
+    where G: Scope<Timestamp=Product<RootTimestamp, u32>>, K: Data, V: Data, T: Data, D: Data {
+
+    // queries awaiting their epoch's completion, and the epochs each marked query was marked at, in order.
+    let mut pending = Vec::<(Product<RootTimestamp, u32>, u32)>::new();
+    let mut marked = HashMap::<u32, Vec<u32>>::new();
+
+    let exchange = Exchange::new(|x: &((K, V, T, u32), i32)| (x.0).3 as u64);
+    Collection::new(queries.inner.binary_notify(&must.inner, exchange, Pipeline, "Completed", vec![], move |input1, input2, output, notificator| {
+
+        // requirements matter only for their frontier.
+        while let Some(_) = input2.next() { }
+
+        while let Some((time, data)) = input1.next() {
+            let mut session = output.session(&time);
+            for &((_, _, _, q), weight) in data.iter() {
+                for _ in 0 .. weight.abs() {
+                    if weight > 0 {
+                        pending.push((time.time(), q));
+                    }
+                    else if let Some(position) = pending.iter().rposition(|x| x.1 == q) {
+                        pending.remove(position);
+                    }
+                    else if let Some(epoch) = marked.get_mut(&q).and_then(|epochs| epochs.pop()) {
+                        session.give(((q, epoch), -1));
+                    }
+                }
+                if marked.get(&q).map_or(false, |epochs| epochs.is_empty()) {
+                    marked.remove(&q);
+                }
+            }
+            notificator.notify_at(time);
+        }
+
+        while let Some((time, _count)) = notificator.next() {
+            let mut session = output.session(&time);
+            for &(_, q) in pending.iter().filter(|x| x.0 == time.time()) {
+                marked.entry(q).or_insert_with(Vec::new).push(time.time().inner);
+                session.give(((q, time.time().inner), 1));
+            }
+            pending.retain(|x| x.0 != time.time());
+        }
+    }))
+}
+
+/// Reports, for each query, the first correction round in which its requirements did not change.
+///
+/// `must` is a collection of requirements inside a correction loop, and `query` extracts the query each record
+/// belongs to. A query whose requirements stop changing for a round may yet change again, once requirements
+/// elsewhere in the loop reach it, so queries are only reported once the loop has drained for some outer time:
+/// each query with changes at that time is then reported as `(query, round)`, the round after its last change.
+pub fn converged<G, D, F>(must: &Collection<Child<G, Round>, D>, query: F) -> Collection<Child<G, Round>, (u32, Round)> 
+    where G: Scope, D: Data, F: Fn(&D)->u32+'static {
+
+    // (outer time, query, last round with changes)
+    let mut active = Vec::<(G::Timestamp, u32, Round)>::new();
+    Collection::new(must.inner.unary_notify(Pipeline, "Converged", vec![], move |input, output, notificator| {
+
+        // no round of the loop can be at or after the last before its bound, so a notification there arrives once
+        // every round for the outer time is complete.
+        while let Some((time, data)) = input.next() {
+            let round = time.time().inner;
+            for &(ref datum, _) in data.iter() {
+                let q = query(datum);
+                if let Some(entry) = active.iter_mut().find(|x| x.0 == time.time().outer && x.1 == q) {
+                    if entry.2 < round { entry.2 = round; }
+                    continue;
+                }
+                active.push((time.time().outer, q, round));
+            }
+            let drained = Product::new(time.time().outer, Round::max_value() - 1);
+            notificator.notify_at(time.delayed(&drained));
+        }
+
+        while let Some((time, _count)) = notificator.next() {
+            let outer = time.time().outer;
+            let mut session = output.session(&time);
+            for &(_, q, round) in active.iter().filter(|x| x.0 == outer) {
+                session.give(((q, round + 1), 1));
+            }
+            active.retain(|x| x.0 != outer);
+        }
+    }))
+}
+
+/// Attaches to each result the round in which its query's requirements last converged.
+///
+/// `results` are records each naming a query, which `query` extracts, and `converged` the `(query, round)` markers
+/// reported by `converged` once left from the correction scope. A query re-explained in later epochs converges
+/// again, and its results carry the latest of its rounds; results of queries yet to converge are held back until
+/// they do. The rounds are a measure of how deep a query's chains of requirements run, and worth watching when a
+/// few queries keep the correction loop busy.
+pub fn with_rounds<G, D, F>(results: &Collection<G, D>, converged: &Collection<G, (u32, Round)>, query: F) -> Collection<G, (D, Round)>
+    where G: Scope, D: Data, F: Fn(&D)->u32+'static, G::Timestamp: Lattice+Ord {
+    let rounds = converged.group_u(|_, s, t| if let Some(round) = s.map(|(r,_)| *r).max() { t.push((round, 1)) });
+    results.map(move |x| (query(&x), x))
+           .join_u(&rounds)
+           .map(|(_,x,r)| (x,r))
+}
+
+/// Maps requirements at loop iteration `i + 1` to requirements on the loop's result at iteration `i`.
+///
+/// Requirements at the first iteration are discarded, as they are met by whatever entered the loop. The test and
+/// the remapping happen in one pass that moves each record along, rather than one operator (and buffer) each.
+pub fn retreat<G, K, V, T>(depends: &Collection<G, (K, V, Product<T, u32>, u32)>) -> Collection<G, (K, V, Product<T, u32>, u32)> 
+    where G: Scope, K: Data, V: Data, T: Timestamp+Data {
+    depends.flat_map(|(x,y,t,q)| if t.inner > 0 { Some((x,y,Product::new(t.outer, t.inner - 1),q)) } else { None })
 }
 
 #[macro_export]
 macro_rules! lift {
     ($stream:expr) => {{
-        Collection::new(
-            $stream.consolidate()
-                   .inner
-                   .unary_stream(timely::dataflow::channels::pact::Pipeline, "lifting", |input, output| {
-
-                while let Some((time, data)) = input.next() {
-                    let mut session = output.session(&time);
-                    for &(ref datum, _weight) in data.iter() {
-                        session.give(((datum.clone(), time.time()), 1));
-                    }
-                }
-            })
-        )
+        $crate::lift(&$stream)
+    }}
+}
+
+/// Rearranges the fields of a variable's records, as `map_inverse` with the inverse derived from the mapping.
+///
+/// The mapping is written as a pattern of names and an expression of the same names, for example
+/// `permute!(edges, (x,(y,l)) => (y,(l,x)))`, and the inverse is the expression read as a pattern and the
+/// pattern as an expression. A mapping that drops a field or uses one twice is not invertible, and does not
+/// compile: the inverse would use a name its pattern does not bind, or bind a name twice.
+#[macro_export]
+macro_rules! permute {
+    ($var:expr, $from:tt => $to:tt) => {{
+        $var.map_inverse(|$from| $to, |$to| $from)
     }}
 }
 
@@ -97,7 +969,7 @@ impl<'a, G, K, V, Gp> Variable<'a, G, K, V, Gp> where
     G: Scope, 
     K: Data+Default, 
     V: Data+Default, 
-    Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, u32>>,
+    Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
     G::Timestamp: Ord+Hash+Lattice {
     /// Joins two collections using an unsigned key.
     pub fn join_u<V2>(&mut self, other: &mut Variable<'a, G, K, V2, Gp>) -> Variable<'a, G, K, (V, V2), Gp> 
@@ -112,8 +984,88 @@ impl<'a, G, K, V, Gp> Variable<'a, G, K, V, Gp> where
         // add each component of joined results to the requirements of each input
         self.depends.add(&result.depends.stream.map(|(x,(y,_),t,q)| (x,y,t,q)));
         other.depends.add(&result.depends.stream.map(|(x,(_,z),t,q)| (x,z,t,q)));
+        self.lineage_to(&result, || result.depends.stream.map(|(x,(y,z),_,q)| ((x.clone(),y.clone()),(x,(y,z)),q)));
+        other.lineage_to(&result, || result.depends.stream.map(|(x,(y,z),_,q)| ((x.clone(),z.clone()),(x,(y,z)),q)));
+        result
+
+    }
+
+    /// Joins two collections using an unsigned key, with the indices built according to `plan`.
+    ///
+    /// With `JoinPlan::Shared` the actual and working records of each input are tagged and joined through a
+    /// single index per input, rather than one index for each of `stream` and `working`. The shared index holds
+    /// the same records as the two it replaces, so this saves the per-index overhead rather than the records,
+    /// at the cost of producing (and discarding) the mixed stream/working matches.
+    pub fn join_u_planned<V2>(&mut self, other: &mut Variable<'a, G, K, V2, Gp>, plan: JoinPlan) -> Variable<'a, G, K, (V, V2), Gp> 
+        where K : Unsigned, V2: Unsigned+Default+Data {
+
+        match plan {
+            JoinPlan::Separate => self.join_u(other),
+            JoinPlan::Shared => {
+
+                let tagged1 = self.stream.map(|(x,y)| (x,(y,false))).concat(&self.working.map(|(x,y)| (x,(y,true))));
+                let tagged2 = other.stream.map(|(x,z)| (x,(z,false))).concat(&other.working.map(|(x,z)| (x,(z,true))));
+                let joined = tagged1.join_u(&tagged2).filter(|&(_,(_,w1),(_,w2))| w1 == w2);
+
+                let result = Variable::new(
+                    joined.filter(|&(_,(_,w),_)| !w).map(|(x,(y,_),(z,_))| (x,(y,z))),
+                    joined.filter(|&(_,(_,w),_)| w).map(|(x,(y,_),(z,_))| (x,(y,z))),
+                    &mut self.depends.scope()
+                );
+
+                self.depends.add(&result.depends.stream.map(|(x,(y,_),t,q)| (x,y,t,q)));
+                other.depends.add(&result.depends.stream.map(|(x,(_,z),t,q)| (x,z,t,q)));
+                self.lineage_to(&result, || result.depends.stream.map(|(x,(y,z),_,q)| ((x.clone(),y.clone()),(x,(y,z)),q)));
+                other.lineage_to(&result, || result.depends.stream.map(|(x,(y,z),_,q)| ((x.clone(),z.clone()),(x,(y,z)),q)));
+                result
+            }
+        }
+    }
+
+    /// Joins two collections using a hashed key, for keys that are not unsigned integers.
+    pub fn join<V2>(&mut self, other: &mut Variable<'a, G, K, V2, Gp>) -> Variable<'a, G, K, (V, V2), Gp>
+        where K: Hash, V2: Data+Default {
+
+        let result = Variable::new(
+            self.stream.join(&other.stream).map(|(x,y,z)| (x,(y,z))),
+            self.working.join(&other.working).map(|(x,y,z)| (x,(y,z))),
+            &mut self.depends.scope()
+        );
+
+        // add each component of joined results to the requirements of each input
+        self.depends.add(&result.depends.stream.map(|(x,(y,_),t,q)| (x,y,t,q)));
+        other.depends.add(&result.depends.stream.map(|(x,(_,z),t,q)| (x,z,t,q)));
+        self.lineage_to(&result, || result.depends.stream.map(|(x,(y,z),_,q)| ((x.clone(),y.clone()),(x,(y,z)),q)));
+        other.lineage_to(&result, || result.depends.stream.map(|(x,(y,z),_,q)| ((x.clone(),z.clone()),(x,(y,z)),q)));
         result
+    }
 
+    /// Joins two collections on keys derived from their records, as `join` after re-keying each side.
+    ///
+    /// Each side is keyed by `key1` or `key2` of its records, which are carried whole into the output, so that
+    /// requirements map back to the input records without an inverse; joining edges by destination is
+    /// `edges.join_on(&mut labels, |e| e.1, |l| l.0)`.
+    pub fn join_on<K2, V2, J, F1, F2>(&mut self, other: &mut Variable<'a, G, K2, V2, Gp>, key1: F1, key2: F2) -> Variable<'a, G, J, ((K, V), (K2, V2)), Gp>
+        where K2: Data+Default, V2: Data+Default, J: Data+Default+Hash,
+              F1: Fn(&(K,V))->J+'static, F2: Fn(&(K2,V2))->J+'static {
+        let mut keyed1 = self.map_inverse(move |x| (key1(&x), x), |(_,x)| x);
+        let mut keyed2 = other.map_inverse(move |x| (key2(&x), x), |(_,x)| x);
+        keyed1.join(&mut keyed2)
+    }
+
+    /// Indexes the actual and working records by key once, for any number of joins to share.
+    ///
+    /// Each `join` builds its own indices of both inputs; a variable feeding several joins is better arranged
+    /// first, and joined through the result. See `join::arrange`.
+    ///
+    /// The shared indices are not those `join` builds: they are hash tables that start empty and only grow, keeping
+    /// every update they receive, retractions included, and every join through them keeps a table of its other
+    /// input that grows the same way. Differential's indices compact updates that cancel, so a variable whose
+    /// records change often is better joined directly, however many joins it feeds.
+    pub fn arrange_by_key<'v>(&'v mut self) -> ArrangedVariable<'v, 'a, G, K, V, Gp> where K: Hash {
+        let stream = join::arrange(&self.stream, 0);
+        let working = join::arrange(&self.working, 0);
+        ArrangedVariable { variable: self, stream: stream, working: working }
     }
 
     /// Maps elements of one collection to another using an invertible function (and its inverse).
@@ -133,14 +1085,66 @@ impl<'a, G, K, V, Gp> Variable<'a, G, K, V, Gp> where
             &mut self.depends.scope()
         );
 
+        let inverse = Rc::new(inverse);
+        let clone3 = inverse.clone();
         self.depends.add(&result.depends.stream.map(move |(k2,v2,t,u)| {
             let (k, v) = inverse((k2, v2));
             (k, v, t, u)
         }));
+        {
+            let requests = &result.depends.stream;
+            self.lineage_to(&result, move || requests.map(move |(k2,v2,_,q)| (clone3((k2.clone(), v2.clone())), (k2,v2), q)));
+        }
         result
 
     }
 
+    /// Maps elements as `map_inverse`, with a mapping that may fail.
+    ///
+    /// Records for which `logic` returns `Err` are not mapped, but appear in the second variable returned as
+    /// `(key, (val, error))`, so that bad records are reported, and explained by the record that failed, rather
+    /// than taking down the dataflow. `inverse` need only invert the successful mappings. `logic` sees each record
+    /// by reference, so that a failed record moves to the errors as it is, and runs once per record.
+    pub fn map_fallible<K2, V2, E, F1, F2>(&mut self, logic: F1, inverse: F2) -> (Variable<'a, G, K2, V2, Gp>, Variable<'a, G, K, (V, E), Gp>)
+        where K2: Data+Default, V2: Data+Default, E: Data+Default,
+              F1: Fn(&(K,V))->Result<(K2,V2),E>+'static, F2: Fn((K2,V2))->(K,V)+'static {
+
+        // each record is mapped once, and its outcome tagged as a success or a failure for the split that follows.
+        let logic = Rc::new(logic);
+        let split = |logic: Rc<F1>| move |x: (K,V)| match logic(&x) {
+            Ok(y) => (Some(y), None),
+            Err(e) => (None, Some((x.0, (x.1, e)))),
+        };
+        let stream = self.stream.map(split(logic.clone()));
+        let working = self.working.map(split(logic));
+
+        let result = Variable::new(
+            stream.flat_map(|(y,_)| y),
+            working.flat_map(|(y,_)| y),
+            &mut self.depends.scope()
+        );
+        let errors = Variable::new(
+            stream.flat_map(|(_,e)| e),
+            working.flat_map(|(_,e)| e),
+            &mut self.depends.scope()
+        );
+
+        // successful records are explained through `inverse`, and failed records by themselves.
+        let inverse = Rc::new(inverse);
+        let clone = inverse.clone();
+        self.depends.add(&result.depends.stream.map(move |(k2,v2,t,q)| {
+            let (k, v) = inverse((k2, v2));
+            (k, v, t, q)
+        }));
+        self.depends.add(&errors.depends.stream.map(|(k,(v,_),t,q)| (k,v,t,q)));
+        {
+            let requests = &result.depends.stream;
+            self.lineage_to(&result, move || requests.map(move |(k2,v2,_,q)| (clone((k2.clone(), v2.clone())), (k2,v2), q)));
+        }
+        self.lineage_to(&errors, || errors.depends.stream.map(|(k,(v,e),_,q)| ((k.clone(),v.clone()),(k,(v,e)),q)));
+        (result, errors)
+    }
+
     /// Concatenates two collections.
     pub fn concat(&mut self, other: &mut Variable<'a, G, K, V, Gp>) -> Variable<'a, G, K, V, Gp> {
         let result = Variable::new(
@@ -150,11 +1154,35 @@ impl<'a, G, K, V, Gp> Variable<'a, G, K, V, Gp> where
         );
 
         self.depends.add(&result.depends.stream);
+        self.lineage_to(&result, || result.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
         other.depends.add(&result.depends.stream);
+        other.lineage_to(&result, || result.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
         result
     }
 
 
+    /// Concatenates any number of collections, at least one, as one operator rather than a chain of `concat`s.
+    ///
+    /// The actual and working records are each merged by a single `concatenate`, and every input is handed the same
+    /// requirements.
+    pub fn concat_many<'v, I>(variables: I) -> Variable<'a, G, K, V, Gp>
+        where I: IntoIterator<Item=&'v mut Variable<'a, G, K, V, Gp>>, 'a: 'v, G: 'v, K: 'v, V: 'v, Gp: 'v {
+
+        let mut variables = variables.into_iter().collect::<Vec<_>>();
+        assert!(!variables.is_empty(), "concat_many needs at least one variable");
+
+        let scope = variables[0].stream.scope();
+        let stream = scope.concatenate(variables.iter().map(|x| x.stream.inner.clone()).collect());
+        let working = scope.concatenate(variables.iter().map(|x| x.working.inner.clone()).collect());
+        let result = Variable::new(Collection::new(stream), Collection::new(working), &mut variables[0].depends.scope());
+
+        for variable in variables.iter_mut() {
+            variable.depends.add(&result.depends.stream);
+            variable.lineage_to(&result, || result.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
+        }
+        result
+    }
+
     /// Concatenates two collections.
     pub fn except(&mut self, other: &mut Variable<'a, G, K, V, Gp>) -> Variable<'a, G, K, V, Gp> {
         let result = Variable::new(
@@ -164,7 +1192,9 @@ impl<'a, G, K, V, Gp> Variable<'a, G, K, V, Gp> where
         );
 
         self.depends.add(&result.depends.stream);
+        self.lineage_to(&result, || result.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
         other.depends.add(&result.depends.stream);
+        other.lineage_to(&result, || result.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
         result
     }
 
@@ -172,6 +1202,7 @@ impl<'a, G, K, V, Gp> Variable<'a, G, K, V, Gp> where
     pub fn enter<'b, T: Timestamp+Data>(&mut self, child: &Child<'b, G, T>) -> Variable<'a, Child<'b,G,T>, K, V, Gp> {
         let result = Variable::new( self.stream.enter(child), self.working.enter(child), &mut self.depends.scope() );
         self.depends.add(&result.depends.stream.map(|(x,y,t,q)| (x,y,t.outer,q)));
+        self.lineage_to(&result, || result.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
         result
     }
 
@@ -190,9 +1221,79 @@ impl<'a, G, K, V, Gp> Variable<'a, G, K, V, Gp> where
         );
 
         self.depends.add(&result.depends.stream.map(|(x,y,t,q)| (x,y,t.outer,q)));
+        self.lineage_to(&result, || result.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
         result
     }
 
+    /// Brings a collection into a loop, each record entering at the round `delay` assigns its `priority`.
+    ///
+    /// Priorities are whatever orders the records usefully, such as the node of a label or the degree of a node.
+    /// See the `delay` module for the strategies, and for why requirements need no inverse of them.
+    pub fn enter_delayed<'b, F>(&mut self, child: &Child<'b, G, u32>, delay: delay::Delay, priority: F) -> Variable<'a, Child<'b, G, u32>, K, V, Gp>
+        where F: Fn(&(K,V))->u64+'static {
+        self.enter_at(child, move |x| delay.at(priority(&x.0)))
+    }
+
+    /// The records of `stream` and `working` with the times at which they change, shared by all users.
+    pub fn lifted(&mut self) -> Collection<G, ((K, V), G::Timestamp)> where G::Timestamp: Data {
+        if self.lifted.is_none() {
+            self.lifted = Some(lift(&self.stream.concat(&self.working)));
+        }
+        self.lifted.as_ref().unwrap().clone()
+    }
+
+    /// Registers this collection's actual, working, and required tuples with `accountant` under `name`.
+    pub fn account(&self, name: &str, accountant: &accounting::Accountant) {
+        accountant.observe(name, accounting::Part::Stream, &self.stream);
+        accountant.observe(name, accounting::Part::Working, &self.working);
+        accountant.observe(name, accounting::Part::Depends, &self.depends.stream);
+    }
+
+    /// Panics, naming the operator `name` and the offending record, if a record is ever more present in the
+    /// working collection than in the actual collection.
+    ///
+    /// Working records are produced from required inputs, which are themselves drawn from the actual inputs, so
+    /// each step that is monotone in its inputs, such as `map_inverse`, `concat`, `filter`, and `join`, should
+    /// preserve `working ⊆ stream`; a violation points at the step whose requirement logic went wrong. Steps that
+    /// are not monotone do not preserve it: the working minimum of `min!` may be a value absent from the actual
+    /// collection, as may any record derived from it, so such variables should not be checked. The check arranges
+    /// the difference of the two collections, and so is meant to be enabled while debugging rather than left in place.
+    pub fn check_working(&self, name: &str) {
+        let name = name.to_owned();
+        self.working
+            .concat(&self.stream.negate())
+            .threshold(|_, w| if w > 0 { w } else { 0 })
+            .inner
+            .inspect_batch(move |time, data| {
+                if let Some(&(ref record, excess)) = data.iter().find(|x| x.1 > 0) {
+                    panic!("{}: working collection exceeds stream by {} of {:?} at {:?}", name, excess, record, time);
+                }
+            });
+    }
+
+    /// Times the paths from `input` to this collection with `profiler`, as the operator called `name`.
+    ///
+    /// The actual and working paths are timed from `input` to this collection, and the requirements path from
+    /// this collection back to `input`, so this should be called once the operator has added its requirements.
+    pub fn profile<K2: Data+Default, V2: Data+Default>(&self, input: &Variable<'a, G, K2, V2, Gp>, name: &str, profiler: &profile::Profiler) {
+        profiler.start(name, accounting::Part::Stream, &input.stream);
+        profiler.stop(name, accounting::Part::Stream, &self.stream);
+        profiler.start(name, accounting::Part::Working, &input.working);
+        profiler.stop(name, accounting::Part::Working, &self.working);
+        profiler.start(name, accounting::Part::Depends, &self.depends.stream);
+        profiler.stop(name, accounting::Part::Depends, &input.depends.current);
+    }
+
+    /// Routes requirements on this collection to the worker holding their key, as `join_u` partitions its inputs.
+    pub fn route_by_key(&mut self) where K: Unsigned {
+        self.depends.route_by(|&(ref k,_,_,_)| k.as_u64());
+    }
+
+    /// Removes requirements of `retired` queries from this collection's accumulated requirements.
+    pub fn retire(&mut self, retired: &Collection<Child<'a, Gp, Round>, u32>) {
+        self.depends.retire_by(retired, |x| x.3);
+    }
+
     pub fn consolidate(&mut self) -> Self {
         let result = Variable::new(
             self.stream.consolidate(), 
@@ -201,91 +1302,560 @@ impl<'a, G, K, V, Gp> Variable<'a, G, K, V, Gp> where
         );
 
         self.depends.add(&result.depends.stream);
+        self.lineage_to(&result, || result.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
+        result
+    }
+
+    /// Retains one record for each distinct key `selector` derives, the least record with that key.
+    ///
+    /// A retained record is explained by itself alone, however many records share its key, so that explanations
+    /// of projection-style deduplication stay small. The working collection retains the least of its own records
+    /// with each key, which is the same record only where the working collection holds no lesser one.
+    pub fn distinct_by<J, F>(&mut self, selector: F) -> Self
+        where J: Data+Default+Hash, F: Fn(&(K,V))->J+'static {
+        let selector = Rc::new(selector);
+        let clone1 = selector.clone();
+        let clone2 = selector.clone();
+        let result = Variable::new(
+            self.stream.map(move |x| (clone1(&x), x)).group(|_j, s, t| if let Some((x, _)) = s.next() { t.push(((*x).clone(), 1)) }).map(|(_,x)| x),
+            self.working.map(move |x| (clone2(&x), x)).group(|_j, s, t| if let Some((x, _)) = s.next() { t.push(((*x).clone(), 1)) }).map(|(_,x)| x),
+            &mut self.depends.scope()
+        );
+
+        self.depends.add(&result.depends.stream);
+        self.lineage_to(&result, || result.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
+        result
+    }
+
+    /// Retains only the records satisfying `logic`.
+    ///
+    /// A retained record is explained by itself, so requirements pass through unchanged.
+    pub fn filter<F: Fn(&(K,V))->bool+'static>(&mut self, logic: F) -> Self {
+        let logic = Rc::new(logic);
+        let clone1 = logic.clone();
+        let clone2 = logic.clone();
+        let result = Variable::new(
+            self.stream.filter(move |x| clone1(x)),
+            self.working.filter(move |x| clone2(x)),
+            &mut self.depends.scope()
+        );
+
+        self.depends.add(&result.depends.stream);
+        self.lineage_to(&result, || result.depends.stream.map(|(x,y,_,q)| ((x.clone(),y.clone()),(x,y),q)));
         result
     }
 }
 
+impl<'a, G, K, V, Gp> Variable<'a, G, K, (V, Interval), Gp> where
+    G: Scope,
+    K: Data+Default+Hash,
+    V: Data+Default,
+    Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
+    G::Timestamp: Ord+Hash+Lattice {
+    /// Joins records valid over intervals, producing a pair of records only where their intervals overlap.
+    ///
+    /// Each output carries both input records, intervals included, so that it is explained by exactly the two
+    /// records whose overlap it stands for, and then the `overlap` of their intervals, during which it is valid.
+    /// A question about the pairs valid at some application time names the outputs whose overlap covers it, and
+    /// so demands only the records valid then, rather than every version of the records with the same key; see
+    /// `query_at_instant`.
+    pub fn join_interval<V2>(&mut self, other: &mut Variable<'a, G, K, (V2, Interval), Gp>) -> Variable<'a, G, K, ((V, Interval), (V2, Interval), Interval), Gp>
+        where V2: Data+Default {
+        let mut joined = self.join(other);
+        let mut overlapping = joined.filter(|&(_, ((_, a), (_, b)))| overlap(a, b).is_some());
+        overlapping.map_inverse(
+            |(k,((v,a),(v2,b)))| { let o = overlap(a, b).unwrap(); (k,((v,a),(v2,b),o)) },
+            |(k,(x,y,_))| (k,(x,y))
+        )
+    }
+}
+
+impl<'a, K, V1, V2, Gp> Variable<'a, Gp, K, (V1, V2, Interval), Gp> where
+    K: Data+Default+Hash,
+    V1: Data+Default,
+    V2: Data+Default,
+    Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+    /// Seeds requirements from queries naming a key and an instant of application time, such as "which pairs for
+    /// key 7 were valid at instant 30", against outputs of `join_interval`.
+    ///
+    /// Queries are `(key, instant, time, query)`, and each is resolved to the outputs with its key whose interval
+    /// covers the instant, as they stood at `time`: outputs first produced after `time` are not seeded. An output
+    /// produced by `time` is seeded even if retracted by then, so that the seeds of a query only grow.
+    pub fn query_at_instant(&mut self, queries: &Collection<Gp, (K, u64, Product<Product<RootTimestamp, u32>, Round>, u32)>) {
+        let outputs = lift(&self.stream).map(|((k,v),t)| (k,(v,t)));
+        let requests = queries.map(|(k,i,t,q)| (k,((i,t.clone(),q),t)));
+        let seeds = join::join_bounded(&outputs, &requests, |&(_, _, (start, end)), &(instant, _, _)| start <= instant && instant < end)
+                        .map(|(k,v,_,(_,t,q))| (k,v,t,q));
+        let scope = self.depends.scope();
+        self.depends.add(&seeds.enter(&scope));
+    }
+}
+
+impl<'a, K, V, Gp> Variable<'a, Gp, K, V, Gp> where
+    K: Data+Default, 
+    V: Data+Default, 
+    Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+    /// Seeds requirements from queries that name only the key of the outputs they ask about.
+    ///
+    /// The queries are joined against the outputs by key, which keeps an index of the outputs keyed by the fields
+    /// queries address, so that each query is seeded by looking up its key rather than matched against all outputs.
+    /// Each output record ever associated with a queried key is required, at the time it changed.
+    pub fn query_by_key(&mut self, queries: &Collection<Gp, (K, u32)>) {
+        let seeds = lift(&queries.join(&self.stream)).map(|((k,q,v),t)| (k,v,t,q));
+        let scope = self.depends.scope();
+        self.depends.add(&seeds.enter(&scope));
+    }
+
+    /// Seeds requirements from queries that name records of a summarized view of the outputs.
+    ///
+    /// Drivers often consolidate or project outputs before presenting them, as the connected components example
+    /// consolidates labels by key, and would rather ask about the records they were shown than the outputs those
+    /// came from. `view` maps each output to its record in the view, and queries are `(record, time, query)`; each
+    /// is resolved to the outputs `view` maps to its record as they stood at `time`, which are seeded as if queried
+    /// at `time` themselves. Outputs first produced after `time` are not seeded, and an output produced by `time` is
+    /// seeded even if retracted by then, so that the seeds of a query only grow.
+    pub fn query_view<D, F>(&mut self, queries: &Collection<Gp, (D, Product<Product<RootTimestamp, u32>, Round>, u32)>, view: F)
+        where D: Data+Default+Hash, F: Fn(&(K,V))->D+'static {
+        let outputs = lift(&self.stream).map(move |(x,t)| (view(&x),(x,t)));
+        let requests = queries.map(|(d,t,q)| (d,((t.clone(),q),t)));
+        let seeds = join::join_bounded(&outputs, &requests, |_, _| true)
+                        .map(|(_,(k,v),_,(t,q))| (k,v,t,q));
+        let scope = self.depends.scope();
+        self.depends.add(&seeds.enter(&scope));
+    }
+
+    /// Pairs each queried output with the outputs for its key reproduced from the required inputs alone.
+    ///
+    /// The `working` collection is the computation applied to only the records some query requires, and once the
+    /// correction loop settles it holds the outputs those records still produce. The result holds `(queried,
+    /// reproduced, query)` for each value `reproduced` the working collection has for the queried output's key, so
+    /// that a driver can show that with just the explaining inputs, node 17 still receives label 0; a queried
+    /// output with no such record has no reproduction at all.
+    ///
+    /// There is one working collection for all queries, computed from the union of every outstanding query's
+    /// required records, as they are now rather than at each query's time. A reproduction shows that the union
+    /// suffices; where several queries are outstanding, it does not show that any one query's records would.
+    pub fn reproduced(&self, queries: &Collection<Gp, (K, V, Product<Product<RootTimestamp, u32>, Round>, u32)>) -> Collection<Gp, ((K, V), V, u32)> {
+        queries.map(|(k,v,_,q)| (k,(v,q)))
+               .join(&self.working)
+               .map(|(k,(v,q),v2)| ((k,v),v2,q))
+    }
+}
+
 #[macro_export]
 macro_rules! min {
     ($var:expr, $logic:expr, $scope:expr) => {{
+        min!($var, $logic, $scope, false)
+    }};
+    ($var:expr, $logic:expr, $scope:expr, $ties:expr) => {{
 
         // compute the minimums for both the actual and working data collections.
-        let min1 = $var.stream.group_u(|_k, s, t| t.push(((*s.next().unwrap().0), 1)));
-        let min2 = $var.working.group_u(|_k, s, t| t.push(((*s.next().unwrap().0), 1)));
+        // records tied under `$logic` are ordered by the records themselves, so the same witness is chosen
+        // regardless of the order in which the group presents them.
+        // a group whose records have all been retracted has no minimum, and produces nothing.
+        let min1 = $var.stream.group_u(|_k, s, t| {
+            if let Some(min) = s.map(|(v,_)| v).min_by_key(|v| ($logic((*v).clone()), (*v).clone())) {
+                t.push((($logic(min.clone()), min.clone()), 1))
+            }
+        });
+        let min2 = $var.working.group_u(|_k, s, t| {
+            if let Some(min) = s.map(|(v,_)| v).min_by_key(|v| ($logic((*v).clone()), (*v).clone())) {
+                t.push((($logic(min.clone()), min.clone()), 1))
+            }
+        });
 
         // construct a new variable from these minimums.
         let var_min = Variable::new(
-            min1.map(|(k,v)| (k,$logic(v))),
-            min2.map(|(k,v)| (k,$logic(v))),
+            min1.map(|(k,(l,_))| (k,l)),
+            min2.map(|(k,(l,_))| (k,l)),
             &mut $scope
         );
 
         // extract minimums and presents them as explainable data, in the explanation scope.
-        let temp = lift!(min1.concat(&min2)).leave().enter(&$scope).map(|((x,val),t)| (x,(val,t)));
+        // with `$ties`, every input record is a candidate, so that all records tied with the chosen witness
+        // are demanded along with it; this explains the minimum value, rather than the one record chosen.
+        // nothing is lifted into the explanation scope until some requirement reaches this operator.
+        // candidates carry the value `$logic` assigns them, computed once for each rather than for each request.
+        let candidates = if $ties { $var.lifted().map(|((x,val),t)| ((x,($logic(val.clone()),val)),t)) }
+                         else { lift!(min1.concat(&min2)) };
+        let temp = $crate::gate(&candidates.leave().enter(&$scope), &var_min.depends.stream)
+                       .map(|((x,val),t)| (x,(val,t)));
 
         // set explanation requirements from requests by
-        //  (i)     joining requests against actual minimums, 
-        //  (ii)    filtering records to only those with less or equal time,
-        //  (iii)   filtering records to only those with less or equal value,
-        $var.depends.add(
-            &temp.join_u(&var_min.depends.stream.map(|(x,l,t,q)| (x,(l,t,q))))  // (i)
-                 .filter(|&(_,(_,t1),(_,t2,_))| t1 <= t2)                       // (ii)
-                 .filter(|&(_,(val,_),(l2,_,_))| $logic(val) <= l2)             // (iii)
-                 .map(|(x,(val,t),(_,_,q))| (x,val,t,q))                        // reformatting
+        //  (i)     joining requests against candidate records, 
+        //  (ii)    restricted to records with less or equal time, within the join,
+        //  (iii)   and to records with less or equal value,
+        let routed = $crate::join::join_bounded(
+            &temp,                                                              // (i)
+            &var_min.depends.stream.map(|(x,l,t,q)| (x,((l,q),t))),
+            |&(ref l1, _), &(ref l2, _)| l1 <= l2                               // (ii), (iii)
         );
+        $var.depends.add(&routed.map(|(x,(_,val),t,(_,q))| (x,val,t,q)));      // reformatting
+        $var.lineage_to(&var_min, || routed.map(|(x,(_,val),_,(l,q))| ((x.clone(),val),(x,l),q)));
 
         var_min
     }}
 }
 
+/// As `min!`, but presenting each record to the minimization no earlier than the loop round `$priority` assigns it.
+///
+/// Prioritized iteration gets its speed from letting good candidates settle before others arrive; within a loop,
+/// `$priority` maps each `(key, val)` record to the round from which it is considered, for example by a `Delay`
+/// of its value, so that small values win before large ones need comparing. Once every record's round has passed
+/// the minimums are those of `min!`, as are the witnesses. This is `reduce_with_witnesses!` over the delayed
+/// records, so a request for a minimum demands the witnesses chosen for it, each at the round it was first
+/// considered, at which it is present in the input as well.
+#[macro_export]
+macro_rules! min_prioritized {
+    ($var:expr, $logic:expr, $priority:expr, $scope:expr) => {{
+
+        // present each record from its priority's round on, retractions included, so that a record retracted
+        // before its round never appears at all.
+        let delayed = |collection: &Collection<_, _>| Collection::new(collection.inner.delay(move |x, t| {
+            let mut t = t.clone();
+            let round = $priority(&x.0);
+            if t.inner < round { t.inner = round; }
+            t
+        }));
+
+        // the minimum of the delayed records, ordering ties as `min!` does, witnessed by the record holding it.
+        reduce_with_witnesses!(@grouped $var, delayed(&$var.stream), delayed(&$var.working), _key, values, {
+            values.iter()
+                  .map(|&(ref v, _)| v)
+                  .min_by_key(|v| ($logic((*v).clone()), (*v).clone()))
+                  .map(|min| ($logic(min.clone()), Some(min.clone())))
+        }, $scope)
+    }}
+}
+
+/// As `min!`, but ordering records by `$order` and producing `$payload` of the least record.
+///
+/// Records tied under `$order` are ordered by the records themselves, as in `min!`. This is
+/// `reduce_with_witnesses!` with the least record as the only witness of its payload, so requests for an output
+/// demand the least records whose payload it is, as they were chosen at less or equal times.
+#[macro_export]
+macro_rules! min_by_key {
+    ($var:expr, $order:expr, $payload:expr, $scope:expr) => {{
+        reduce_with_witnesses!(@group $var, _key, values, {
+            values.iter()
+                  .map(|&(ref v, _)| v)
+                  .min_by_key(|v| ($order((*v).clone()), (*v).clone()))
+                  .map(|min| ($payload(min.clone()), Some(min.clone())))
+        }, $scope)
+    }}
+}
+
+/// Sums the contributions `$logic` assigns each record of a group, saturating at `u64::max_value()`.
+///
+/// Requests for a sum demand the `$k` largest contributions to it as they stood at the request's time, rather than
+/// every member, so that explanations of large sums stay bounded at the cost of not reproducing the sum exactly.
+#[macro_export]
+macro_rules! top_sum {
+    ($var:expr, $logic:expr, $k:expr, $scope:expr) => {{
+
+        // sum the contributions `$logic` assigns each record, for both the actual and working data collections.
+        // sums saturate rather than overflow, so a group too large to sum reports `u64::max_value()`.
+        let sum1 = $var.stream.group_u(|_k, s, t| {
+            let sum = s.fold(0u64, |sum, (v,w)| sum.saturating_add($logic((*v).clone()).saturating_mul(w as u64)));
+            t.push((sum, 1))
+        });
+        let sum2 = $var.working.group_u(|_k, s, t| {
+            let sum = s.fold(0u64, |sum, (v,w)| sum.saturating_add($logic((*v).clone()).saturating_mul(w as u64)));
+            t.push((sum, 1))
+        });
+
+        // the `$k` largest contributions to each sum, ordered by the records themselves among equals, are the
+        // only records a sum can demand.
+        let k = $k;
+        let top1 = $var.stream.group_u(move |_k, s, t| {
+            let mut vals = s.map(|(v,_)| (*v).clone()).collect::<Vec<_>>();
+            vals.sort_by(|a, b| match $logic(b.clone()).cmp(&$logic(a.clone())) { ::std::cmp::Ordering::Equal => a.cmp(b), other => other });
+            for val in vals.into_iter().take(k) { t.push((val, 1)) }
+        });
+        let top2 = $var.working.group_u(move |_k, s, t| {
+            let mut vals = s.map(|(v,_)| (*v).clone()).collect::<Vec<_>>();
+            vals.sort_by(|a, b| match $logic(b.clone()).cmp(&$logic(a.clone())) { ::std::cmp::Ordering::Equal => a.cmp(b), other => other });
+            for val in vals.into_iter().take(k) { t.push((val, 1)) }
+        });
+
+        let var_sum = Variable::new(sum1, sum2, &mut $scope);
+
+        // requests for a sum demand its largest contributions as they stood at the request's time: candidates
+        // enter and leave the top `$k` with weighted changes, and those that left by the request's time cancel
+        // their entry, as they are stamped with the request's time rather than their own. unlike `min!`, this does
+        // not reproduce the sum exactly: it trades an explanation of the whole sum, which may need every record,
+        // for one of bounded size naming the records that mattered most.
+        let candidates = $crate::lift_weighted(&top1.concat(&top2));
+        let temp = $crate::gate(&candidates.leave().enter(&$scope), &var_sum.depends.stream)
+                       .map(|((x,val),t)| (x,(val,t)));
+        let routed = $crate::join::join_bounded(
+            &temp,
+            &var_sum.depends.stream.map(|(x,s,t,q)| (x,((s,t.clone(),q),t))),
+            |_, _| true
+        );
+        $var.depends.add(&routed.map(|(x,val,_,(_,t,q))| (x,val,t,q)));
+        $var.lineage_to(&var_sum, || routed.map(|(x,val,_,(s,_,q))| ((x.clone(),val),(x,s),q)));
+
+        var_sum
+    }}
+}
+
+/// Groups records by key, producing `$logic` of each group's values and counts, in order, as its output.
+///
+/// Requests for a group's output demand the members `$policy` selects, at less or equal times; see
+/// `ExplanationPolicy`. Under `AllMembers` every member ever in the group is a candidate, while the other policies
+/// are `reduce_with_witnesses!` with the members `witnesses` chooses for each output, so that large groups lift
+/// little into the explanation and each output demands only its own witnesses.
+#[macro_export]
+macro_rules! group_with_policy {
+    ($var:expr, $logic:expr, $policy:expr, $scope:expr) => {{
+
+        let policy: $crate::ExplanationPolicy = $policy;
+        match policy {
+            $crate::ExplanationPolicy::AllMembers => {
+
+                // compute each group's output for both the actual and working data collections.
+                let out1 = $var.stream.group_u(|_k, s, t| {
+                    let values = s.map(|(v,w)| ((*v).clone(), w)).collect::<Vec<_>>();
+                    t.push(($logic(&values[..]), 1))
+                });
+                let out2 = $var.working.group_u(|_k, s, t| {
+                    let values = s.map(|(v,w)| ((*v).clone(), w)).collect::<Vec<_>>();
+                    t.push(($logic(&values[..]), 1))
+                });
+
+                let var_group = Variable::new(out1, out2, &mut $scope);
+
+                // requests for a group's output demand every member ever in the group at less or equal times.
+                let temp = $crate::gate(&$var.lifted().leave().enter(&$scope), &var_group.depends.stream)
+                               .map(|((x,val),t)| (x,(val,t)));
+                let routed = $crate::join::join_bounded(
+                    &temp,
+                    &var_group.depends.stream.map(|(x,o,t,q)| (x,((o,q),t))),
+                    |_, _| true
+                );
+                $var.depends.add(&routed.map(|(x,val,t,(_,q))| (x,val,t,q)));
+                $var.lineage_to(&var_group, || routed.map(|(x,val,_,(o,q))| ((x.clone(),val),(x,o),q)));
+
+                var_group
+            },
+            _ => reduce_with_witnesses!(@group $var, _key, values, Some(($logic(values), $crate::witnesses(policy, values, &$logic))), $scope),
+        }
+    }}
+}
+
+/// Groups records by key with user-supplied logic for both the outputs and their requirements.
+///
+/// `$logic(key, values)` is passed each group's values and counts, in order, and returns the group's output
+/// values and counts. `$demand(key, members, requirements)` is passed, for each key with requirements, the group's
+/// members as `(value, time)` for each time at which a member changed, and the requirements on the group's outputs
+/// as `(output, time, query)`, and returns the members to demand as `(value, time, output, query)`: the member as
+/// of `time`, on behalf of the requirement for `output` by `query`. Nothing stops `$demand` from demanding members
+/// at times after the requirement, or too few members to reproduce the output; the operator trusts it, which is
+/// what makes it an escape hatch for operators whose provenance the other operators do not capture.
+#[macro_export]
+macro_rules! reduce_core {
+    ($var:expr, $logic:expr, $demand:expr, $scope:expr) => {{
+
+        // compute each group's outputs for both the actual and working data collections.
+        let out1 = $var.stream.group_u(|k, s, t| {
+            let values = s.map(|(v,w)| ((*v).clone(), w)).collect::<Vec<_>>();
+            for (out, w) in $logic(k, &values[..]) { t.push((out, w)) }
+        });
+        let out2 = $var.working.group_u(|k, s, t| {
+            let values = s.map(|(v,w)| ((*v).clone(), w)).collect::<Vec<_>>();
+            for (out, w) in $logic(k, &values[..]) { t.push((out, w)) }
+        });
+
+        let result = Variable::new(out1, out2, &mut $scope);
+
+        // members and requirements meet by key, and `$demand` chooses among the members.
+        let members = $crate::gate(&$var.lifted().leave().enter(&$scope), &result.depends.stream)
+                          .map(|((k,v),t)| (k,(Some((v,t)),None)));
+        let requirements = result.depends.stream.map(|(k,o,t,q)| (k,(None,Some((o,t,q)))));
+        let demanded = members.concat(&requirements).group(|k, s, t| {
+            let mut members = Vec::new();
+            let mut requirements = Vec::new();
+            for (x, _) in s {
+                match *x {
+                    (Some(ref member), _) => members.push(member.clone()),
+                    (_, Some(ref requirement)) => requirements.push(requirement.clone()),
+                    _ => { },
+                }
+            }
+            if !requirements.is_empty() {
+                for demand in $demand(k, &members[..], &requirements[..]) { t.push((demand, 1)) }
+            }
+        });
+        $var.depends.add(&demanded.map(|(k,(v,t,_,q))| (k,v,t,q)));
+        $var.lineage_to(&result, || demanded.map(|(k,(v,_,o,q))| ((k.clone(),v),(k,o),q)));
+
+        result
+    }}
+}
+
+/// Groups records by key, with `$logic` choosing for each output the members that explain it.
+///
+/// `$logic(key, values)` is passed each group's values and counts, in order, and returns the group's distinct
+/// output values, each with the values to demand when it is explained. Requests for an output demand its witnesses
+/// as they were chosen at less or equal times, which is exact for any aggregation whose logic names witnesses that
+/// alone reproduce the output.
+#[macro_export]
+macro_rules! reduce_with_witnesses {
+    // the outputs and witnesses of each group are `$body`, evaluated with its key bound to `$key` and its values
+    // and counts to `$values`, so that macros built on this one need not name the types of either.
+    (@group $var:expr, $key:ident, $values:ident, $body:expr, $scope:expr) => {{
+        reduce_with_witnesses!(@grouped $var, $var.stream, $var.working, $key, $values, $body, $scope)
+    }};
+    // as `@group`, grouping `$stream` and `$working` in place of the variable's own actual and working records,
+    // which they must be derived from, for example by delaying them.
+    (@grouped $var:expr, $stream:expr, $working:expr, $key:ident, $values:ident, $body:expr, $scope:expr) => {{
+
+        // compute each group's outputs, and each output's witnesses, for both the actual and working collections.
+        // `$body` runs once per group: each output is recorded without a witness, and then once with each of its
+        // witnesses, and the outputs and witnesses are separated afterwards.
+        let wit1 = $stream.group_u(move |$key, s, t| {
+            let $values = s.map(|(v,w)| ((*v).clone(), w)).collect::<Vec<_>>();
+            let $values = &$values[..];
+            for (out, witnesses) in $body {
+                t.push(((out.clone(), None), 1));
+                for witness in witnesses { t.push(((out.clone(), Some(witness)), 1)) }
+            }
+        });
+        let wit2 = $working.group_u(move |$key, s, t| {
+            let $values = s.map(|(v,w)| ((*v).clone(), w)).collect::<Vec<_>>();
+            let $values = &$values[..];
+            for (out, witnesses) in $body {
+                t.push(((out.clone(), None), 1));
+                for witness in witnesses { t.push(((out.clone(), Some(witness)), 1)) }
+            }
+        });
+        let out1 = wit1.filter(|&(_,(_,ref w))| w.is_none()).map(|(k,(o,_))| (k,o));
+        let out2 = wit2.filter(|&(_,(_,ref w))| w.is_none()).map(|(k,(o,_))| (k,o));
+        let wit1 = wit1.flat_map(|(k,(o,w))| w.map(|w| (k,(o,w))));
+        let wit2 = wit2.flat_map(|(k,(o,w))| w.map(|w| (k,(o,w))));
+
+        let result = Variable::new(out1, out2, &mut $scope);
+
+        // requests for an output demand the witnesses chosen for it at less or equal times.
+        let candidates = lift!(wit1.concat(&wit2));
+        let temp = $crate::gate(&candidates.leave().enter(&$scope), &result.depends.stream)
+                       .map(|((k,(o,v)),t)| ((k,o),(v,t)));
+        let routed = $crate::join::join_bounded(
+            &temp,
+            &result.depends.stream.map(|(k,o,t,q)| ((k,o),(q,t))),
+            |_, _| true
+        );
+        $var.depends.add(&routed.map(|((k,_),v,t,q)| (k,v,t,q)));
+        $var.lineage_to(&result, || routed.map(|((k,o),v,_,q)| ((k.clone(),v),(k,o),q)));
+
+        result
+    }};
+    ($var:expr, $logic:expr, $scope:expr) => {{
+        reduce_with_witnesses!(@group $var, key, values, $logic(key, values), $scope)
+    }};
+}
+
 #[macro_export]
 macro_rules! except {
     ($var1:expr, $var2:expr, $scope:expr) => {{
 
-        // let result = Variable::new(
-        //     $var1.stream.concat(&$var2.stream.negate()), 
-        //     $var1.working.concat(&$var2.working.negate()), 
-        //     &mut $scope
-        // );
-        // $var1.depends.add(
-        //     &result.depends.stream
-        //         .map(|(x,y,t,q)| ((x,y),(t,q)))
-        //         .join(&lift!($var1.stream.concat(&$var1.working)).leave().enter(&$scope))
-        //         .filter(|&(_,(t1,_),t2)| t1 >= t2)
-        //         .map(|((x,y),(_,q),t)| (x,y,t,q))
-        // );
-        // $var2.depends.add(
-        //     &result.depends.stream
-        //         .map(|(x,y,t,q)| ((x,y),(t,q)))
-        //         .join(&lift!($var2.stream.concat(&$var2.working)).leave().enter(&$scope))
-        //         .filter(|&(_,(t1,_),t2)| t1 >= t2)
-        //         .map(|((x,y),(_,q),t)| (x,y,t,q))
-        // );
-
         let result = Variable::new(
             $var1.stream.concat(&$var2.stream.negate()), 
             $var1.working.concat(&$var2.working.negate()), 
             &mut $scope
         );
 
-        $var1.depends.add(&result.depends.stream);
-        $var2.depends.add(&result.depends.stream);
+        // a record is present in the difference at some time because it is present in `$var1` at a time
+        // less or equal, and no more present in `$var2`. requirements demand
+        //  (i)     presence: the records of `$var1` matching the request at less or equal times,
+        //  (ii)    absence: the records of `$var2` matching the request at less or equal times, whose
+        //          absence from the working collection would otherwise fail to cancel them out.
+        // neither side is lifted into the explanation scope until some requirement reaches this operator.
+        let requests = result.depends.stream.map(|(x,y,t,q)| ((x,y),(q,t)));
+        let present = $crate::join::join_bounded(
+            &$crate::gate(&$var1.lifted().leave().enter(&$scope), &result.depends.stream).map(|(x,t)| (x,((),t))),
+            &requests,
+            |_, _| true
+        );
+        let absent = $crate::join::join_bounded(
+            &$crate::gate(&$var2.lifted().leave().enter(&$scope), &result.depends.stream).map(|(x,t)| (x,((),t))),
+            &requests,
+            |_, _| true
+        );
+        $var1.depends.add(&present.map(|((x,y),(),t,q)| (x,y,t,q)));           // (i)
+        $var2.depends.add(&absent.map(|((x,y),(),t,q)| (x,y,t,q)));            // (ii)
+        $var1.lineage_to(&result, || present.map(|((x,y),(),_,q)| ((x.clone(),y.clone()),(x,y),q)));
+        $var2.lineage_to(&result, || absent.map(|((x,y),(),_,q)| ((x.clone(),y.clone()),(x,y),q)));
 
         result
     }}
 }
 
+/// The records of `$var1` whose unsigned keys are absent from `$var2`.
+///
+/// As with `except!`, an output record is explained by its presence in `$var1` and by the records of `$var2`
+/// that might have cancelled it: those with its key, at less or equal times, whose absence from the working
+/// collection would otherwise let it through where it was once suppressed.
+#[macro_export]
+macro_rules! antijoin_u {
+    ($var1:expr, $var2:expr, $scope:expr) => {{
+
+        // records of `$var1`, and keys of `$var2`, grouped by key; a group with any key of `$var2` produces nothing.
+        let anti1 = $var1.stream.map(|(k,v)| (k,Some(v))).concat(&$var2.stream.map(|(k,_)| (k,None)))
+                         .group_u(|_k, s, t| {
+                             let values = s.collect::<Vec<_>>();
+                             if values.iter().all(|x| x.0.is_some()) {
+                                 for (v,w) in values { t.push(((*v).clone().unwrap(), w)) }
+                             }
+                         });
+        let anti2 = $var1.working.map(|(k,v)| (k,Some(v))).concat(&$var2.working.map(|(k,_)| (k,None)))
+                         .group_u(|_k, s, t| {
+                             let values = s.collect::<Vec<_>>();
+                             if values.iter().all(|x| x.0.is_some()) {
+                                 for (v,w) in values { t.push(((*v).clone().unwrap(), w)) }
+                             }
+                         });
+
+        let result = Variable::new(anti1, anti2, &mut $scope);
+
+        // requirements demand
+        //  (i)     presence: the records of `$var1` matching the request at less or equal times,
+        //  (ii)    absence: the records of `$var2` with the request's key at less or equal times.
+        // neither side is lifted into the explanation scope until some requirement reaches this operator.
+        let present = $crate::join::join_bounded(
+            &$crate::gate(&$var1.lifted().leave().enter(&$scope), &result.depends.stream).map(|(x,t)| (x,((),t))),
+            &result.depends.stream.map(|(x,y,t,q)| ((x,y),(q,t))),
+            |_, _| true
+        );
+        let absent = $crate::join::join_bounded(
+            &$crate::gate(&$var2.lifted().leave().enter(&$scope), &result.depends.stream).map(|((x,z),t)| (x,(z,t))),
+            &result.depends.stream.map(|(x,y,t,q)| (x,((y,q),t))),
+            |_, _| true
+        );
+        $var1.depends.add(&present.map(|((x,y),(),t,q)| (x,y,t,q)));           // (i)
+        $var2.depends.add(&absent.map(|(x,z,t,(_,q))| (x,z,t,q)));             // (ii)
+        $var1.lineage_to(&result, || present.map(|((x,y),(),_,q)| ((x.clone(),y.clone()),(x,y),q)));
+        $var2.lineage_to(&result, || absent.map(|(x,z,_,(y,q))| ((x.clone(),z),(x,y),q)));
+
+        result
+    }}
+}
 
 #[macro_export]
 macro_rules! leave {
     ($var:expr, $scope:expr) => {{
         let result = Variable::new( $var.stream.leave(), $var.working.leave(), &mut $scope );
-        $var.depends.add(
-            &result.depends.stream
-                .map(|(x,y,t,q)| ((x,y),(t,q)))
-                .join(&lift!($var.stream.concat(&$var.working)).leave().enter(&$scope))
-                .map(|((x,y),(_,q),t)| (x,y,t,q))
+        // a record inside the loop can only explain a request on the output at times its outer time
+        // is less or equal to; records from later outer times are not demanded.
+        let routed = $crate::join::join_bounded(
+            &$crate::gate(&$var.lifted().leave().enter(&$scope), &result.depends.stream)
+                    .map(|(x,t)| (x,(t.clone(),t.outer))),
+            &result.depends.stream.map(|(x,y,t,q)| ((x,y),(q,t))),
+            |_, _| true
         );
+        $var.depends.add(&routed.map(|((x,y),t,_,q)| (x,y,t,q)));
+        $var.lineage_to(&result, || routed.map(|((x,y),_,_,q)| ((x.clone(),y.clone()),(x,y),q)));
         result
     }}
 }
@@ -293,56 +1863,174 @@ macro_rules! leave {
 /// A collection defined by multiple mutually recursive rules.
 pub struct MonotonicVariable<'a, G: Scope, D: Data+Default>
 where G::Timestamp: Lattice {
-    pub feedback: Option<Handle<G::Timestamp, u32,(D, i32)>>,
-    pub stream:  Collection<Child<'a, G, u32>, D>,
-    pub current:  Collection<Child<'a, G, u32>, D>,
+    pub feedback: Vec<Handle<G::Timestamp, Round, (D, i32)>>,
+    pub stream:  Collection<Child<'a, G, Round>, D>,
+    pub current:  Collection<Child<'a, G, Round>, D>,
+    /// Optional disk staging in front of the thresholding arrangement.
+    pub spill: Option<SpillConfig>,
+    /// Optional routing function applied to added records.
+    pub route: Option<Rc<Fn(&D)->u64>>,
+    /// Optional retired query identifiers, and a function extracting the query identifier of a record.
+    pub retire: Option<(Collection<Child<'a, G, Round>, u32>, Rc<Fn(&D)->u32>)>,
+    /// Optional function assigning each record to one of the feedback edges.
+    pub shard: Option<Rc<Fn(&D)->usize>>,
+    /// Optional cap on the records of each query, a function extracting the query identifier of a record, and
+    /// the feedback edge reporting queries that reached the cap.
+    pub cap: Option<(usize, Rc<Fn(&D)->u32>, Handle<G::Timestamp, Round, (u32, i32)>)>,
 }
 
 impl<'a, G: Scope, D: Data+Default> MonotonicVariable<'a, G, D> where G::Timestamp: Lattice {
     /// Creates a new `Variable` and a `Stream` representing its output, from a supplied `source` stream.
-    pub fn new(scope: &mut Child<'a, G, u32>) -> MonotonicVariable<'a, G, D> {
-        let (feedback, cycle) = scope.loop_variable(u32::max_value(), 1);
+    pub fn new(scope: &mut Child<'a, G, Round>) -> MonotonicVariable<'a, G, D> {
+        let (feedback, cycle) = scope.loop_variable(Round::max_value(), 1);
         let cycle = Collection::new(cycle);
-        MonotonicVariable { feedback: Some(feedback), stream: cycle.clone(), current: cycle.clone() }
+        MonotonicVariable { feedback: vec![feedback], stream: cycle.clone(), current: cycle.clone(), spill: None, route: None, retire: None, shard: None, cap: None }
+    }
+    /// Creates a new `Variable` whose accumulated records recirculate along `shards` feedback edges.
+    ///
+    /// Each record is thresholded and fed back along the edge `shard` indicates (modulo `shards`). A single edge
+    /// means a single thresholding arrangement holding every requirement; with a shard function that assigns key
+    /// ranges to edges, each arrangement holds only its range, and can be scheduled separately. The edges share
+    /// the loop's rounds, so no round completes before every edge has finished it, and the slowest range still
+    /// paces the whole variable; each thresholding operator is also exchanged across workers by record, as it
+    /// would be with a single edge.
+    pub fn new_sharded<F: Fn(&D)->usize+'static>(scope: &mut Child<'a, G, Round>, shards: usize, shard: F) -> MonotonicVariable<'a, G, D> {
+        assert!(shards > 0);
+        let (handle, cycle) = scope.loop_variable(Round::max_value(), 1);
+        let mut feedback = vec![handle];
+        let mut cycle = Collection::new(cycle);
+        for _ in 1 .. shards {
+            let (handle, other) = scope.loop_variable(Round::max_value(), 1);
+            feedback.push(handle);
+            cycle = cycle.concat(&Collection::new(other));
+        }
+        MonotonicVariable { feedback: feedback, stream: cycle.clone(), current: cycle.clone(), spill: None, route: None, retire: None, shard: Some(Rc::new(shard)), cap: None }
+    }
+    /// Stages additions through disk, according to `config`, before they are thresholded.
+    ///
+    /// The thresholded requirements themselves stay in memory; see the `spill` module.
+    pub fn spill_to(&mut self, config: SpillConfig) {
+        self.spill = Some(config);
+    }
+    /// Routes subsequently added records to the worker indicated by `route`.
+    ///
+    /// By default records stay on the worker that produced them, and are only exchanged when some operator
+    /// requires it. Routing requirements the same way the primary computation partitions its inputs means that
+    /// requirements arrive at the worker already holding the records they name.
+    pub fn route_by<F: Fn(&D)->u64+'static>(&mut self, route: F) {
+        self.route = Some(Rc::new(route));
+    }
+    /// Removes records belonging to `retired` queries from the accumulated state.
+    ///
+    /// Requirements are otherwise retained forever, as each round feeds back what the previous round required,
+    /// even once the query that demanded them has been withdrawn. See `retired_queries` for a source of retired
+    /// query identifiers driven by the progress of the query input, which releases an identifier asked again.
+    pub fn retire_by<F: Fn(&D)->u32+'static>(&mut self, retired: &Collection<Child<'a, G, Round>, u32>, query: F) {
+        self.retire = Some((retired.clone(), Rc::new(query)));
+    }
+    /// Limits each query to `cap` accumulated records, returning the queries whose requirements reached it.
+    ///
+    /// Records beyond a query's cap are dropped as they are added, so that its requirements stop expanding, and the
+    /// query appears in the returned collection from the round after, so that drivers can report an explanation
+    /// too large to complete rather than wait on it. The records kept are chosen as by `budget::limit`.
+    pub fn cap_by<F: Fn(&D)->u32+'static>(&mut self, cap: usize, query: F) -> Collection<Child<'a, G, Round>, u32> {
+        let mut scope = self.scope();
+        let (handle, overflow) = scope.loop_variable(Round::max_value(), 1);
+        self.cap = Some((cap, Rc::new(query), handle));
+        Collection::new(overflow)
     }
     /// Adds a new source of data to the `Variable`.
-    pub fn add(&mut self, source: &Collection<Child<'a, G, u32>, D>) {
-        self.current = self.current.concat(source);
+    pub fn add(&mut self, source: &Collection<Child<'a, G, Round>, D>) {
+        if let Some(ref route) = self.route {
+            let route = route.clone();
+            let routed = source.inner.exchange(move |x| route(&x.0));
+            self.current = self.current.concat(&Collection::new(routed));
+        }
+        else {
+            self.current = self.current.concat(source);
+        }
+    }
+    /// Adds a new source of data to the `Variable`, each record delayed by `priority` iterations.
+    ///
+    /// As with prioritized label propagation, introducing likely-decisive requirements before speculative ones
+    /// lets the fixpoint settle on them first, so that long chains of derivations are not re-worked as each
+    /// competing requirement arrives in the same iteration.
+    pub fn add_at<F: Fn(&D)->u32+'static>(&mut self, source: &Collection<Child<'a, G, Round>, D>, priority: F) {
+        let delayed = source.inner.delay(move |x, t| Product::new(t.outer.clone(), t.inner + priority(&x.0) as Round));
+        self.add(&Collection::new(delayed));
     }
-    pub fn scope(&self) -> Child<'a, G, u32> {
+    pub fn scope(&self) -> Child<'a, G, Round> {
         self.current.scope()
     }
 }
 
 impl<'a, G: Scope, D: Data+Default> Drop for MonotonicVariable<'a, G, D> where G::Timestamp: Lattice {
     fn drop(&mut self) {
-        if let Some(feedback) = self.feedback.take() {
-            self.current.threshold(|_, w| if w > 0 { 1 } else { 0 })
-                        .inner
-                        .connect_loop(feedback);
+        if !self.feedback.is_empty() {
+            // compact the concatenated additions within each round, so that the thresholding state
+            // grows with the distinct requirements rather than with every copy produced by each source.
+            self.current = self.current.consolidate();
+            if let Some(config) = self.spill.take() {
+                self.current = self.current.spill(&config);
+            }
+            if let Some((cap, query, handle)) = self.cap.take() {
+                let route = query.clone();
+                let distinct = self.current.threshold(|_, w| if w > 0 { 1 } else { 0 });
+                let (kept, dropped) = budget::limit(&distinct, move |x| route(x), move |_| budget::Budget { records: cap, rounds: Round::max_value() });
+                self.current = kept;
+                dropped.map(move |x| query(&x))
+                       .threshold(|_, w| if w > 0 { 1 } else { 0 })
+                       .inner
+                       .connect_loop(handle);
+            }
+            let retire = self.retire.take().map(|(retired, query)| (retired.threshold(|_, w| if w > 0 { 1 } else { 0 }), query));
+            let shards = self.feedback.len();
+            for (index, feedback) in self.feedback.drain(..).enumerate() {
+                let current = match self.shard {
+                    Some(ref shard) => { let shard = shard.clone(); self.current.filter(move |x| shard(x) % shards == index) },
+                    None => self.current.clone(),
+                };
+                // thresholding looks only at each record's accumulated count, whatever order its copies arrived in.
+                let mut next = current.threshold(|_, w| if w > 0 { 1 } else { 0 });
+                if let Some((ref retired, ref query)) = retire {
+                    let query = query.clone();
+                    let dropped = next.map(move |x| (query(&x), x)).semijoin(retired).map(|(_,x)| x);
+                    next = next.concat(&dropped.negate());
+                }
+                next.inner.connect_loop(feedback);
+            }
         }
     }
 }
 
 /// Container for feedback edges for a explanation-traced variable.
-pub struct VariableFeedback<'a, G, K, V, Gp> 
+///
+/// Several feedback variables may share one loop scope, for mutually recursive definitions: each is used in the
+/// definitions of the others before any is `set`, and each `set` retreats the requirements on its own variable to
+/// its source, whichever variables those requirements were derived through. `family` and `set_all` create and
+/// connect such a group together. A feedback variable dropped without being set panics, as its loop would
+/// otherwise never close.
+///
+/// The loop scope, of lifetime `'b`, is usually created by `scoped` within the closure building the loop, and so
+/// lives for less time than the explanation scope, of lifetime `'a`, which the loop's requirements are routed to.
+pub struct VariableFeedback<'a, 'b, G, K, V, Gp> 
 where G: Scope, 
       K: Data+Default, 
       V: Data+Default, 
-      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, u32>>,
+      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
       G::Timestamp: Ord+Hash {
     handles: Option<(Handle<G::Timestamp, u32, ((K,V), i32)>,
                      Handle<G::Timestamp, u32, ((K,V), i32)>)>,
-    variable: Variable<'a, Child<'a, G, u32>, K, V, Gp>,
+    variable: Variable<'a, Child<'b, G, u32>, K, V, Gp>,
 }
 
-impl<'a, G, K, V, Gp> VariableFeedback<'a, G, K, V, Gp>
+impl<'a, 'b, G, K, V, Gp> VariableFeedback<'a, 'b, G, K, V, Gp>
 where G: Scope, 
       K: Data+Default, 
       V: Data+Default, 
-      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, u32>>,
+      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
       G::Timestamp: Ord+Hash {
-    pub fn new(scope: &mut Child<'a, G, u32>, explanation_scope: &mut Child<'a, Gp, u32>) -> Self {
+    pub fn new(scope: &mut Child<'b, G, u32>, explanation_scope: &mut Child<'a, Gp, Round>) -> Self {
         let (handle1, cycle1) = scope.loop_variable(u32::max_value(), 1); let cycle1 = Collection::new(cycle1);
         let (handle2, cycle2) = scope.loop_variable(u32::max_value(), 1); let cycle2 = Collection::new(cycle2);
         VariableFeedback { 
@@ -350,39 +2038,87 @@ where G: Scope,
             variable: Variable::new(cycle1, cycle2, explanation_scope), 
         }
     }
-    pub fn set(&mut self, source: &mut Variable<'a, Child<'a, G, u32>, K, V, Gp>) {  
+    /// Creates `count` feedback variables in `scope`, for mutually recursive definitions.
+    pub fn family(scope: &mut Child<'b, G, u32>, explanation_scope: &mut Child<'a, Gp, Round>, count: usize) -> Vec<Self> {
+        (0 .. count).map(|_| VariableFeedback::new(scope, explanation_scope)).collect()
+    }
+    pub fn set(&mut self, source: &mut Variable<'a, Child<'b, G, u32>, K, V, Gp>) {  
+        self.connect(source, |requirements| requirements.consolidate());
+    }
+    /// Connects `source` to the loop, with `consolidate` compacting the requirements it is handed back.
+    fn connect<F>(&mut self, source: &mut Variable<'a, Child<'b, G, u32>, K, V, Gp>, consolidate: F)
+        where F: FnOnce(&Collection<Child<'a, Gp, Round>, (K, V, Product<G::Timestamp, u32>, u32)>)->Collection<Child<'a, Gp, Round>, (K, V, Product<G::Timestamp, u32>, u32)> {
         if let Some((handle1, handle2)) =  self.handles.take() {
             source.stream.inner.connect_loop(handle1);
             source.working.inner.connect_loop(handle2);
-            source.depends.add(
-                &self.variable.depends.stream
-                .filter(|&(_,_,t,_)| t.inner > 0)
-                .map(|(x,l,t,q)| (x,l,Product::new(t.outer, t.inner - 1),q))
-            );
+            // consolidate requirements before they go around the loop again; without this each
+            // round re-circulates every copy of each requirement that any operator produced.
+            source.depends.add(&consolidate(&retreat(&self.variable.depends.stream)));
+            source.lineage_feedback(&self.variable);
+        }
+    }
+    /// Sets each of `feedbacks` to the source at the same position in `sources`.
+    pub fn set_all(feedbacks: &mut [Self], sources: &mut [Variable<'a, Child<'b, G, u32>, K, V, Gp>]) {
+        assert_eq!(feedbacks.len(), sources.len(), "each feedback variable needs exactly one source");
+        for (feedback, source) in feedbacks.iter_mut().zip(sources.iter_mut()) {
+            feedback.set(source);
         }
     }
 }
 
-impl<'a, G, K, V, Gp> ::std::ops::Deref for VariableFeedback<'a, G, K, V, Gp>
+impl<'a, 'b, G, K, V, Gp> VariableFeedback<'a, 'b, G, K, V, Gp>
+where G: Scope, 
+      K: Unsigned+Data+Default, 
+      V: Data+Default, 
+      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
+      G::Timestamp: Ord+Hash {
+    /// As `set`, but consolidating requirements by radix sorting their unsigned keys, as the `_u` operators do.
+    pub fn set_u(&mut self, source: &mut Variable<'a, Child<'b, G, u32>, K, V, Gp>) {
+        self.connect(source, |requirements| radix::consolidate_u(requirements, |x| x.0.clone()));
+    }
+}
+
+impl<'a, 'b, G, K, V, Gp> Drop for VariableFeedback<'a, 'b, G, K, V, Gp>
+where G: Scope, 
+      K: Data+Default, 
+      V: Data+Default, 
+      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
+      G::Timestamp: Ord+Hash {
+    fn drop(&mut self) {
+        if self.handles.is_some() && !::std::thread::panicking() {
+            panic!("feedback variable dropped without being set");
+        }
+    }
+}
+
+impl<'a, 'b, G, K, V, Gp> ::std::ops::Deref for VariableFeedback<'a, 'b, G, K, V, Gp>
 where G: Scope, 
       K: Data+Default, 
       V: Data+Default, 
-      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, u32>>,
+      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
       G::Timestamp: Ord+Hash {
-        type Target = Variable<'a, Child<'a, G, u32>, K, V, Gp>;
+        type Target = Variable<'a, Child<'b, G, u32>, K, V, Gp>;
         fn deref(&self) -> &Self::Target {
             &self.variable
         }
 }
 
 
-impl<'a, G, K, V, Gp> ::std::ops::DerefMut for VariableFeedback<'a, G, K, V, Gp>
+impl<'a, 'b, G, K, V, Gp> ::std::ops::DerefMut for VariableFeedback<'a, 'b, G, K, V, Gp>
 where G: Scope, 
       K: Data+Default, 
       V: Data+Default, 
-      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, u32>>,
+      Gp: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
       G::Timestamp: Ord+Hash {
         fn deref_mut(&mut self) -> &mut Self::Target {
             &mut self.variable
         }
 }
+
+// instrumented computations use the macros above, and so must be declared after them.
+pub mod algos;
+pub mod datalog;
+pub mod relational;
+pub mod sql;
+pub mod cc;
+pub mod stable;
@@ -0,0 +1,148 @@
+//! Derivation edges between required records, as a queryable collection.
+//!
+//! With the `lineage` feature enabled, each instrumented operator records, as it routes requirements on its output
+//! records back to the input records explaining them, an edge from each such input record to the output record it
+//! supports, tagged by query. Variables hold records of different types, so records are identified by the id of
+//! the variable holding them and a hash of the record. The edges of variables connected by operators, or by the
+//! feedback of a loop, are gathered in one registry, and `Variable::provenance` presents all of them as an ordinary
+//! collection in the explanation scope. Further queries may then be run over the provenance itself, for example
+//! counting, for each input record, the distinct outputs it supports.
+//!
+//! Following the edges forward from a required input record, for the query that required it, answers why it was
+//! required: `why` returns the chains of requirements leading from the record to the outputs the query asked about,
+//! and `named` recovers the records behind the nodes of a chain.
+//!
+//! Without the feature no edges are recorded, and `provenance` is empty.
+
+use std::rc::Rc;
+use std::hash::Hash;
+use std::cell::{Cell, RefCell};
+
+use timely::dataflow::*;
+
+use differential_dataflow::{Data, Collection};
+use differential_dataflow::operators::*;
+use differential_dataflow::lattice::Lattice;
+
+use join::fnv_hash;
+
+/// A record, as the id of the variable holding it and the hash of the record.
+pub type Node = (u32, u64);
+
+/// A derivation edge, as `(support, supported, query)`: the requirement of `supported` by `query` required
+/// `support`.
+pub type Edge = (Node, Node, u32);
+
+thread_local!(static NEXT_ID: Cell<u32> = Cell::new(0));
+
+/// A fresh variable id.
+///
+/// Ids are assigned per worker in order of construction, and so agree across workers building the same dataflow.
+pub fn next_id() -> u32 {
+    NEXT_ID.with(|next| { let id = next.get(); next.set(id + 1); id })
+}
+
+/// Whether operators record derivation edges.
+pub fn enabled() -> bool {
+    cfg!(feature = "lineage")
+}
+
+/// The node naming `record` of the variable with id `id`.
+pub fn node<D: Hash>(id: u32, record: &D) -> Node {
+    (id, fnv_hash(record))
+}
+
+/// The records of the variable with id `id`, each with the node naming it.
+pub fn named<G: Scope, D: Data>(id: u32, records: &Collection<G, D>) -> Collection<G, (Node, D)> {
+    records.map(move |x| (node(id, &x), x))
+}
+
+/// The edges on chains of derivations from each of `asked`, as `(node, query)`, to the records `query` asked about.
+///
+/// Edges are followed from support to supported and only for the same query, so that each edge returned pairs a
+/// requirement with the requirement that demanded it, ending at the queried records.
+pub fn why<G: Scope>(provenance: &Collection<G, Edge>, asked: &Collection<G, (Node, u32)>) -> Collection<G, Edge>
+    where G::Timestamp: Lattice+Ord {
+    let edges = provenance.map(|(s,t,q)| ((s,q),t));
+    let reached = asked.iterate(|inner| {
+        let edges = edges.enter(&inner.scope());
+        let asked = asked.enter(&inner.scope());
+        edges.semijoin(inner)
+             .map(|((_,q),t)| (t,q))
+             .concat(&asked)
+             .threshold(|_, w| if w > 0 { 1 } else { 0 })
+    });
+    edges.semijoin(&reached).map(|((s,q),t)| (s,t,q))
+}
+
+/// Edges from records of variable `from` to records of variable `to`, from `(support, supported, query)` records.
+pub fn edges<G: Scope, D1: Data, D2: Data>(from: u32, to: u32, pairs: &Collection<G, (D1, D2, u32)>) -> Collection<G, Edge> {
+    pairs.map(move |(x,y,q)| (node(from, &x), node(to, &y), q))
+}
+
+/// A set of edge collections shared by connected variables.
+///
+/// Registries merge when operators connect their variables, after which all of their handles see the same set.
+pub struct Registry<C> {
+    link: Rc<RefCell<Link<C>>>,
+}
+
+// a registry either holds its collections, or has been merged into another that does.
+enum Link<C> {
+    Root(Vec<C>),
+    Forward(Rc<RefCell<Link<C>>>),
+}
+
+impl<C: Clone> Registry<C> {
+    /// A new, empty registry.
+    pub fn new() -> Registry<C> {
+        Registry { link: Rc::new(RefCell::new(Link::Root(Vec::new()))) }
+    }
+    /// Adds `collection` to the registry.
+    pub fn push(&self, collection: C) {
+        if let Link::Root(ref mut collections) = *self.root().borrow_mut() {
+            collections.push(collection);
+        }
+    }
+    /// Merges `other` into this registry, so that handles to either see the collections of both.
+    pub fn merge(&self, other: &Registry<C>) {
+        let mine = self.root();
+        let theirs = other.root();
+        if &*mine as *const _ != &*theirs as *const _ {
+            let taken = match ::std::mem::replace(&mut *theirs.borrow_mut(), Link::Forward(mine.clone())) {
+                Link::Root(collections) => collections,
+                Link::Forward(_) => unreachable!(),
+            };
+            if let Link::Root(ref mut collections) = *mine.borrow_mut() {
+                collections.extend(taken);
+            }
+        }
+    }
+    /// The collections in the registry.
+    pub fn collections(&self) -> Vec<C> {
+        let root = self.root();
+        let collections = match *root.borrow() {
+            Link::Root(ref collections) => collections.clone(),
+            Link::Forward(_) => unreachable!(),
+        };
+        collections
+    }
+    // the link holding the collections, following any merges.
+    fn root(&self) -> Rc<RefCell<Link<C>>> {
+        let mut link = self.link.clone();
+        loop {
+            let next = match *link.borrow() {
+                Link::Forward(ref next) => next.clone(),
+                Link::Root(_) => break,
+            };
+            link = next;
+        }
+        link
+    }
+}
+
+impl<C> Clone for Registry<C> {
+    fn clone(&self) -> Registry<C> {
+        Registry { link: self.link.clone() }
+    }
+}
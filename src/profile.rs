@@ -0,0 +1,119 @@
+//! Estimates of the time instrumented operators spend on each of their paths.
+//!
+//! An instrumented operator does its primary work on the actual records, and its explanation work on the
+//! working records and on the requirements flowing back to its inputs. A `Profiler` times each path by noting
+//! when a batch enters it and adding the time elapsed when the next batch leaves it. Within a worker, timely
+//! schedules operators in the order they were constructed, so the interval covers the operators between the
+//! two points along with any unrelated operators scheduled among them; figures are estimates, not measurements
+//! of individual operators. Nothing is timed unless an operator is registered with `Variable::profile`.
+
+use std::fmt;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+
+use differential_dataflow::{Data, Collection};
+
+use accounting::Part;
+
+/// Time spent on each path of one instrumented operator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Timings {
+    /// Time spent on the actual records.
+    pub stream: Duration,
+    /// Time spent on the working records.
+    pub working: Duration,
+    /// Time spent routing requirements back to the operator's inputs.
+    pub depends: Duration,
+}
+
+impl Default for Timings {
+    fn default() -> Timings {
+        Timings { stream: Duration::new(0, 0), working: Duration::new(0, 0), depends: Duration::new(0, 0) }
+    }
+}
+
+impl Timings {
+    /// Explanation work relative to primary work, or `None` if no primary work was timed.
+    pub fn overhead(&self) -> Option<f64> {
+        let stream = seconds(self.stream);
+        if stream > 0.0 { Some((seconds(self.working) + seconds(self.depends)) / stream) } else { None }
+    }
+    fn get(&mut self, part: Part) -> &mut Duration {
+        match part {
+            Part::Stream => &mut self.stream,
+            Part::Working => &mut self.working,
+            Part::Depends => &mut self.depends,
+        }
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "stream {:?}\tworking {:?}\tdepends {:?}", self.stream, self.working, self.depends));
+        if let Some(overhead) = self.overhead() {
+            try!(write!(f, "\toverhead {:.2}x", overhead));
+        }
+        Ok(())
+    }
+}
+
+/// A shared registry of per-operator path timings, by name.
+#[derive(Clone, Default)]
+pub struct Profiler {
+    timings: Rc<RefCell<BTreeMap<String, Timings>>>,
+    started: Rc<RefCell<BTreeMap<(String, u8), Instant>>>,
+}
+
+impl Profiler {
+    /// Creates a new, empty profiler.
+    pub fn new() -> Profiler {
+        Profiler { timings: Rc::new(RefCell::new(BTreeMap::new())), started: Rc::new(RefCell::new(BTreeMap::new())) }
+    }
+    /// Notes batches of `collection` as entering `part` of the operator called `name`.
+    pub fn start<G: Scope, D: Data>(&self, name: &str, part: Part, collection: &Collection<G, D>) {
+        let started = self.started.clone();
+        let key = (name.to_owned(), part as u8);
+        collection.inner.inspect_batch(move |_time, _data| {
+            started.borrow_mut().entry(key.clone()).or_insert(Instant::now());
+        });
+    }
+    /// Notes batches of `collection` as leaving `part` of the operator called `name`.
+    pub fn stop<G: Scope, D: Data>(&self, name: &str, part: Part, collection: &Collection<G, D>) {
+        let started = self.started.clone();
+        let timings = self.timings.clone();
+        let key = (name.to_owned(), part as u8);
+        collection.inner.inspect_batch(move |_time, _data| {
+            if let Some(start) = started.borrow_mut().remove(&key) {
+                let mut timings = timings.borrow_mut();
+                *timings.entry(key.0.clone()).or_insert(Timings::default()).get(part) += start.elapsed();
+            }
+        });
+    }
+    /// Current timings for the operator called `name`.
+    pub fn get(&self, name: &str) -> Timings {
+        self.timings.borrow().get(name).cloned().unwrap_or(Timings::default())
+    }
+    /// Current timings for all operators, ordered by name.
+    pub fn report(&self) -> Vec<(String, Timings)> {
+        self.timings.borrow().iter().map(|(name, timings)| (name.clone(), *timings)).collect()
+    }
+    /// Current timings for all operators, one per line, most expensive explanation work first.
+    pub fn breakdown(&self) -> String {
+        let mut report = self.report();
+        report.sort_by(|x, y| ((y.1).working + (y.1).depends).cmp(&((x.1).working + (x.1).depends)));
+        let mut result = String::new();
+        for (name, timings) in report {
+            result.push_str(&format!("{}:\t{}\n", name, timings));
+        }
+        result
+    }
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + (duration.subsec_nanos() as f64 / 1000000000.0)
+}
@@ -0,0 +1,83 @@
+//! Radix-sort based consolidation for collections with small unsigned keys.
+//!
+//! Requirement tuples are dominated by `u32` keys, for which a least-significant-byte radix sort is much cheaper
+//! than the comparison sort the general `consolidate` uses. Only records with equal keys are then compared in full.
+
+use std::rc::Rc;
+
+use timely_sort::{Unsigned, LSBRadixSorter};
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+use timely::dataflow::channels::pact::Exchange;
+
+use differential_dataflow::{Data, Collection, Delta};
+
+/// Consolidates the updates to a collection at each time, using `key` to radix sort the records.
+///
+/// Records are exchanged by key, so like `consolidate` the result has at most one update for each record at
+/// each time. Updates are held until their time is complete.
+pub fn consolidate_u<G, D, U, F>(collection: &Collection<G, D>, key: F) -> Collection<G, D>
+    where G: Scope, D: Data, U: Unsigned, F: Fn(&D)->U+'static {
+
+    let key = Rc::new(key);
+    let route = key.clone();
+    let exchange = Exchange::new(move |x: &(D, Delta)| route(&x.0).as_u64());
+
+    let mut stash = Vec::<(G::Timestamp, Vec<(D, Delta)>)>::new();
+    let mut sorter = LSBRadixSorter::new();
+
+    Collection::new(collection.inner.unary_notify(exchange, "ConsolidateU", vec![], move |input, output, notificator| {
+
+        while let Some((time, data)) = input.next() {
+            if let Some(position) = stash.iter().position(|x| x.0 == time.time()) {
+                stash[position].1.extend(data.drain(..));
+            }
+            else {
+                stash.push((time.time(), data.drain(..).collect()));
+            }
+            notificator.notify_at(time);
+        }
+
+        while let Some((time, _count)) = notificator.next() {
+            if let Some(position) = stash.iter().position(|x| x.0 == time.time()) {
+
+                let (_, batch) = stash.swap_remove(position);
+                sorter.push_batch(batch, &|x: &(D, Delta)| key(&x.0));
+                let mut sorted = Vec::new();
+                for mut chunk in sorter.finish(&|x: &(D, Delta)| key(&x.0)) {
+                    sorted.extend(chunk.drain(..));
+                }
+
+                // records are now ordered by key; sort and accumulate each run of equal keys.
+                let mut session = output.session(&time);
+                let mut lower = 0;
+                while lower < sorted.len() {
+                    let run_key = key(&sorted[lower].0).as_u64();
+                    let mut upper = lower + 1;
+                    while upper < sorted.len() && key(&sorted[upper].0).as_u64() == run_key {
+                        upper += 1;
+                    }
+
+                    let run = &mut sorted[lower .. upper];
+                    run.sort_by(|x, y| x.0.cmp(&y.0));
+                    let mut cursor = 0;
+                    while cursor < run.len() {
+                        let mut weight = run[cursor].1;
+                        let mut next = cursor + 1;
+                        while next < run.len() && run[next].0 == run[cursor].0 {
+                            weight += run[next].1;
+                            next += 1;
+                        }
+                        if weight != 0 {
+                            session.give((run[cursor].0.clone(), weight));
+                        }
+                        cursor = next;
+                    }
+
+                    lower = upper;
+                }
+            }
+        }
+    }))
+}
@@ -0,0 +1,254 @@
+//! Relational algebra over instrumented collections, with named columns.
+//!
+//! A `Relation` is a `Variable` whose records are rows of `u32` values, along with the names of its columns. Its
+//! methods assemble instrumented operators, keeping track of the columns each produces, so that plans can be
+//! built by name rather than by tuple position:
+//!
+//! ```text
+//! let mut edges = Relation::from_pairs("src", "dst", &mut var_edges);
+//! let mut other = Relation::from_pairs("mid", "far", &mut var_edges);
+//! let two_hop = edges.join(&mut other, &[("dst", "mid")]).project(&["src", "far"]);
+//! ```
+//!
+//! Relations live directly in a correction scope, and build no loops of their own. Operators that collapse several
+//! rows into one, projection and aggregation, explain each result row by the rows it was computed from: one
+//! witness for projection, minimum, and maximum, and every contributing row for count and sum.
+
+use std::rc::Rc;
+use std::ops::Range;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+
+use {Variable, Round, lift, gate};
+use join;
+
+/// An aggregation applied to one column of each group of rows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Aggregate {
+    /// The least value, explained by one row holding it.
+    Min,
+    /// The greatest value, explained by one row holding it.
+    Max,
+    /// The number of rows, explained by all of them.
+    Count,
+    /// The sum of the values, explained by all rows.
+    Sum,
+}
+
+/// A collection of rows with named columns.
+///
+/// Rows are the keys of `rows`, whose values are always empty.
+pub struct Relation<'a, G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+    /// The names of the columns, in order.
+    pub schema: Vec<String>,
+    /// The rows, as keys with empty values.
+    pub rows: Variable<'a, G, Vec<u32>, Vec<u32>, G>,
+}
+
+impl<'a, G> Relation<'a, G> where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+    /// A relation from rows whose columns are named by `schema`.
+    pub fn new(schema: &[&str], rows: Variable<'a, G, Vec<u32>, Vec<u32>, G>) -> Self {
+        Relation { schema: schema.iter().map(|x| (*x).to_owned()).collect(), rows: rows }
+    }
+
+    /// A two-column relation from the pairs of `pairs`, with columns named `first` and `second`.
+    pub fn from_pairs(first: &str, second: &str, pairs: &mut Variable<'a, G, u32, u32, G>) -> Self {
+        Relation::new(&[first, second], pairs.map_inverse(|(x,y)| (vec![x,y], vec![]), |(row,_)| (row[0], row[1])))
+    }
+
+    /// The position of the column `name`.
+    ///
+    /// Panics if there is no such column, as a plan naming one is malformed.
+    pub fn column(&self, name: &str) -> usize {
+        match self.schema.iter().position(|x| x == name) {
+            Some(position) => position,
+            None => panic!("no column {} in {:?}", name, self.schema),
+        }
+    }
+
+    /// The rows satisfying `predicate`, which is passed the values of `columns` in order.
+    pub fn select<F: Fn(&[u32])->bool+'static>(&mut self, columns: &[&str], predicate: F) -> Relation<'a, G> {
+        let positions = columns.iter().map(|x| self.column(x)).collect::<Vec<_>>();
+        let rows = self.rows.filter(move |&(ref row, _)| predicate(&positions.iter().map(|&p| row[p]).collect::<Vec<_>>()));
+        Relation { schema: self.schema.clone(), rows: rows }
+    }
+
+    /// The distinct values of `columns`, each explained by one row holding them. A column may be listed twice.
+    pub fn project(&mut self, columns: &[&str]) -> Relation<'a, G> {
+        let positions = columns.iter().map(|x| self.column(x)).collect::<Vec<_>>();
+        let schema = columns.iter().map(|x| (*x).to_owned()).collect();
+        let mut keyed = self.keyed(&positions);
+        Relation { schema: schema, rows: reduce(&mut keyed, 0, |_| (vec![], 0 .. 1)) }
+    }
+
+    /// Pairs of rows agreeing on each `(left, right)` pair of columns in `on`.
+    ///
+    /// The result has this relation's columns followed by the other's, less the other's columns in `on`.
+    pub fn join(&mut self, other: &mut Relation<'a, G>, on: &[(&str, &str)]) -> Relation<'a, G> {
+
+        let left = on.iter().map(|x| self.column(x.0)).collect::<Vec<_>>();
+        let right = on.iter().map(|x| other.column(x.1)).collect::<Vec<_>>();
+
+        // each side keyed by its join columns, its remaining columns as values.
+        let mut keyed1 = self.keyed(&left);
+        let mut keyed2 = other.keyed(&right);
+
+        // output rows are this side's row, then the other's remaining columns, as the inverse must rebuild it.
+        let width1 = self.schema.len();
+        let left1 = left.clone();
+        let left2 = left.clone();
+        let rows = keyed1.join(&mut keyed2).map_inverse(
+            move |(key,(rest1,rest2))| (unkey(&left1, &key, &rest1).into_iter().chain(rest2.into_iter()).collect(), vec![]),
+            move |(row,_)| {
+                let (key, rest1) = rekey(&left2, &row[.. width1]);
+                (key, (rest1, row[width1 ..].to_vec()))
+            }
+        );
+
+        let mut schema = self.schema.clone();
+        schema.extend(other.schema.iter().enumerate().filter(|x| !right.contains(&x.0)).map(|x| x.1.clone()));
+        Relation { schema: schema, rows: rows }
+    }
+
+    /// The rows of both relations, which must have the same columns; rows in both appear twice.
+    pub fn union(&mut self, other: &mut Relation<'a, G>) -> Relation<'a, G> {
+        assert_eq!(self.schema, other.schema);
+        Relation { schema: self.schema.clone(), rows: self.rows.concat(&mut other.rows) }
+    }
+
+    /// The rows of this relation less those of `other`, which must have the same columns.
+    pub fn difference(&mut self, other: &mut Relation<'a, G>) -> Relation<'a, G> {
+        assert_eq!(self.schema, other.schema);
+        Relation { schema: self.schema.clone(), rows: self.rows.except(&mut other.rows) }
+    }
+
+    /// Groups rows by `group`, and aggregates `column` of each group with `aggregate` into a column named `name`.
+    ///
+    /// The result has the `group` columns followed by the aggregate. Sums and counts are accumulated in 64 bits,
+    /// and saturate at `u32::max_value()` rather than wrap. Panics if `column` is also a `group` column, whose
+    /// aggregate would be the column itself, as a plan asking for one is malformed.
+    pub fn aggregate(&mut self, group: &[&str], aggregate: Aggregate, column: &str, name: &str) -> Relation<'a, G> {
+
+        let positions = group.iter().map(|x| self.column(x)).collect::<Vec<_>>();
+        let value = self.column(column);
+        if positions.contains(&value) {
+            panic!("aggregated column {} is also grouped by in {:?}", column, group);
+        }
+
+        // the aggregated column's position among the remaining columns, which `rekey` keeps in order.
+        let value = (0 .. self.schema.len()).filter(|p| !positions.contains(p)).position(|p| p == value).unwrap();
+
+        let mut schema = group.iter().map(|x| (*x).to_owned()).collect::<Vec<_>>();
+        schema.push(name.to_owned());
+
+        let mut keyed = self.keyed(&positions);
+        let rows = match aggregate {
+            Aggregate::Min => reduce(&mut keyed, 1, move |rests| {
+                let (index, min) = rests.iter().enumerate().min_by_key(|x| ((x.1).0[value], x.0)).map(|x| (x.0, (x.1).0[value])).unwrap();
+                (vec![min], index .. index + 1)
+            }),
+            Aggregate::Max => reduce(&mut keyed, 1, move |rests| {
+                let (index, max) = rests.iter().enumerate().max_by_key(|x| ((x.1).0[value], !x.0)).map(|x| (x.0, (x.1).0[value])).unwrap();
+                (vec![max], index .. index + 1)
+            }),
+            Aggregate::Count => reduce(&mut keyed, 1, |rests| {
+                (vec![saturate(rests.iter().map(|x| x.1 as u64).sum())], 0 .. rests.len())
+            }),
+            Aggregate::Sum => reduce(&mut keyed, 1, move |rests| {
+                (vec![saturate(rests.iter().fold(0u64, |sum, x| sum.saturating_add((x.0)[value] as u64 * x.1 as u64)))], 0 .. rests.len())
+            }),
+        };
+
+        Relation { schema: schema, rows: rows }
+    }
+
+    // the rows keyed by the columns at `positions`, with the remaining columns as values.
+    fn keyed(&mut self, positions: &[usize]) -> Variable<'a, G, Vec<u32>, Vec<u32>, G> {
+        let positions1 = positions.to_vec();
+        let positions2 = positions.to_vec();
+        self.rows.map_inverse(move |(row,_)| rekey(&positions1, &row), move |(key,rest)| (unkey(&positions2, &key, &rest), vec![]))
+    }
+}
+
+// `value` as a column value, saturating at the largest.
+fn saturate(value: u64) -> u32 {
+    if value > u32::max_value() as u64 { u32::max_value() } else { value as u32 }
+}
+
+// splits `row` into the values at `positions`, and the rest.
+fn rekey(positions: &[usize], row: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let key = positions.iter().map(|&p| row[p]).collect();
+    let rest = row.iter().enumerate().filter(|x| !positions.contains(&x.0)).map(|x| *x.1).collect();
+    (key, rest)
+}
+
+// reassembles the row `rekey` split. a column listed at several positions, as by `project(&["src", "src"])`, is
+// in the key several times but in the row once, so the row is as wide as the rest and the distinct positions.
+fn unkey(positions: &[usize], key: &[u32], rest: &[u32]) -> Vec<u32> {
+    let distinct = positions.iter().enumerate().filter(|&(i, p)| !positions[.. i].contains(p)).count();
+    let width = distinct + rest.len();
+    let mut row = Vec::with_capacity(width);
+    let mut rest = rest.iter();
+    for index in 0 .. width {
+        match positions.iter().position(|&p| p == index) {
+            Some(k) => row.push(key[k]),
+            None => row.push(*rest.next().unwrap()),
+        }
+    }
+    row
+}
+
+// groups `keyed` by key and presents `key ++ value` for each group as a row, where `logic` is passed the group's
+// values and counts, in order, and returns `value`, of `width` columns, and the range of values that explain it.
+//
+// requests for a row demand its group's explaining values, at less or equal times, and no others: a minimum is
+// explained by the one value holding it, wherever it falls in the group, and projection by the least value.
+fn reduce<'a, G, F>(keyed: &mut Variable<'a, G, Vec<u32>, Vec<u32>, G>, width: usize, logic: F) -> Variable<'a, G, Vec<u32>, Vec<u32>, G>
+    where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>>,
+          F: Fn(&[(Vec<u32>, i32)])->(Vec<u32>, Range<usize>)+'static {
+
+    let logic = Rc::new(logic);
+
+    // each group's result, and the values explaining it.
+    let results = |collection: &Collection<G, (Vec<u32>, Vec<u32>)>| {
+        let logic = logic.clone();
+        collection.group(move |_k, s, t| {
+            let values = s.map(|(v,w)| (v.clone(), w)).collect::<Vec<_>>();
+            t.push((logic(&values).0, 1));
+        })
+    };
+    let witnesses = |collection: &Collection<G, (Vec<u32>, Vec<u32>)>| {
+        let logic = logic.clone();
+        collection.group(move |_k, s, t| {
+            let values = s.map(|(v,w)| (v.clone(), w)).collect::<Vec<_>>();
+            for &(ref value, _) in values[logic(&values).1].iter() { t.push((value.clone(), 1)); }
+        })
+    };
+
+    let mut scope = keyed.depends.scope();
+    let result = Variable::new(
+        results(&keyed.stream).map(|(k,v)| (k.into_iter().chain(v.into_iter()).collect(), vec![])),
+        results(&keyed.working).map(|(k,v)| (k.into_iter().chain(v.into_iter()).collect(), vec![])),
+        &mut scope
+    );
+
+    // requests for a row are requests for its group, whose key is the row less the result's value.
+    let candidates = lift(&witnesses(&keyed.stream).concat(&witnesses(&keyed.working)));
+    let candidates = gate(&candidates.enter(&scope), &result.depends.stream).map(|((k,v),t)| (k,(v,t)));
+    let routed = join::join_bounded(
+        &candidates,
+        &result.depends.stream.map(move |(row,_,t,q)| (row[.. row.len() - width].to_vec(),((row,q),t))),
+        |_, _| true
+    );
+    keyed.depends.add(&routed.map(|(k,v,t,(_,q))| (k,v,t,q)));
+    keyed.lineage_to(&result, || routed.map(|(k,v,_,(row,q))| ((k,v),(row,vec![]),q)));
+
+    result
+}
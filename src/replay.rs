@@ -0,0 +1,115 @@
+//! Standalone verification of explanations, by replaying them.
+//!
+//! An explanation claims that its required input records suffice to reproduce the queried outputs. This module
+//! checks the claim directly: it exports each query's must-set from the `(input, record, query)` records an
+//! explanation reports, builds a fresh and uninstrumented dataflow over only those records, and reports whether
+//! each queried output reappears. The computation is described once, by implementing `Computation`.
+
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+
+use differential_dataflow::{Data, Collection};
+use differential_dataflow::lattice::Lattice;
+
+/// An uninstrumented computation, to be replayed on explanations.
+pub trait Computation: Clone+Send+Sync+'static {
+    /// The type of input records.
+    type Data: Data+Send;
+    /// The type of output records.
+    type Output: Data+Send+Hash;
+    /// Builds the computation from its `inputs`, and returns its outputs.
+    fn build<G: Scope>(&self, inputs: &[Collection<G, Self::Data>]) -> Collection<G, Self::Output> where G::Timestamp: Lattice+Ord;
+}
+
+/// The outcome of replaying one query's explanation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replayed<D> {
+    /// The query replayed.
+    pub query: u32,
+    /// The number of input records the explanation required.
+    pub records: usize,
+    /// Whether the queried output reappeared.
+    pub reproduced: bool,
+    /// The queried output.
+    pub output: D,
+}
+
+/// The records `query` requires from each of `inputs` inputs, from `(input, record, query)` must-set records.
+///
+/// A record naming an input past the last is an error, as it would be left out of any replay.
+pub fn export<D: Clone>(must: &[(u32, D, u32)], inputs: usize, query: u32) -> Result<Vec<Vec<D>>, String> {
+    let mut exported = (0 .. inputs).map(|_| Vec::new()).collect::<Vec<_>>();
+    for &(input, ref record, q) in must.iter() {
+        if q == query {
+            match exported.get_mut(input as usize) {
+                Some(records) => records.push(record.clone()),
+                None => return Err(format!("query {} requires a record of input {}, of only {} inputs", query, input, inputs)),
+            }
+        }
+    }
+    Ok(exported)
+}
+
+/// Runs `computation` on just the supplied records of each input, and returns those `queries` that it does not
+/// reproduce.
+///
+/// An empty result means the records are sufficient to explain the queried outputs. The computation runs on a
+/// single worker in the calling thread, and so is intended for the small inputs explanations produce.
+pub fn missing<C: Computation>(computation: &C, inputs: &[Vec<C::Data>], queries: &[C::Output]) -> Vec<C::Output> {
+
+    let computation = Arc::new(computation.clone());
+    let inputs = inputs.to_vec();
+    let count = inputs.len();
+    let results = Arc::new(Mutex::new(HashMap::new()));
+    let shared = results.clone();
+
+    ::timely::execute(::timely::Configuration::Thread, move |root| {
+
+        let results = shared.clone();
+        let computation = computation.clone();
+        let (mut handles, probe) = root.scoped::<u32, _, _>(move |scope| {
+            let mut handles = Vec::new();
+            let mut collections = Vec::new();
+            for _ in 0 .. count {
+                let (handle, stream) = scope.new_input();
+                handles.push(handle);
+                collections.push(Collection::new(stream));
+            }
+            let probe = computation.build(&collections[..])
+                                   .inspect(move |&(ref x, w)| *results.lock().unwrap().entry(x.clone()).or_insert(0) += w)
+                                   .probe().0;
+            (handles, probe)
+        });
+
+        for (handle, records) in handles.iter_mut().zip(inputs.iter()) {
+            for record in records.iter() { handle.send((record.clone(), 1)); }
+            handle.advance_to(1);
+        }
+        root.step_while(|| handles.iter().any(|x| probe.lt(&x.time())));
+
+    }).unwrap();
+
+    let results = results.lock().unwrap();
+    queries.iter().filter(|x| results.get(x).map(|&w| w <= 0).unwrap_or(true)).cloned().collect()
+}
+
+/// Replays the explanation of each of `queries`, `(output, query)`, from the `(input, record, query)` records of
+/// a completed must-set over `inputs` inputs.
+///
+/// Each query is replayed on its own records alone, so that one query's explanation cannot prop up another's.
+/// Fails as `export` does, if some record names an input past the last.
+pub fn verify<C: Computation>(computation: &C, inputs: usize, must: &[(u32, C::Data, u32)], queries: &[(C::Output, u32)]) -> Result<Vec<Replayed<C::Output>>, String> {
+    queries.iter().map(|&(ref output, query)| {
+        let exported = try!(export(must, inputs, query));
+        Ok(Replayed {
+            query: query,
+            records: exported.iter().map(|x| x.len()).sum(),
+            reproduced: missing(computation, &exported, &[output.clone()]).is_empty(),
+            output: output.clone(),
+        })
+    }).collect()
+}
@@ -0,0 +1,48 @@
+//! Speculative updates: which outputs would change, were some hypothetical update applied.
+//!
+//! Explanations look backwards, from outputs to the inputs they require. `what_if` looks forwards: given a
+//! hypothetical change to the inputs, such as deleting an edge, it reports the changes to the outputs that would
+//! follow, without applying the change to the inputs themselves. The computation is rebuilt in a nested scope
+//! whose timestamp is a shadow time: the inputs enter at shadow time zero and the hypothetical changes at shadow
+//! time one, so that the output changes at shadow time one are exactly the impact of the hypothetical changes.
+//! Both are maintained as the inputs and the hypothetical changes evolve.
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+use timely::dataflow::channels::pact::Pipeline;
+
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+use differential_dataflow::lattice::Lattice;
+
+use replay::Computation;
+
+/// The changes to the outputs of `computation` that applying `hypothetical` to `inputs` would cause.
+///
+/// `hypothetical` holds a collection of changes for each input, whose weights are the changes to apply; retracting
+/// a hypothetical change withdraws its impact. Each result is an output record, with weight the change in its count
+/// were the hypothetical changes applied, so that an output that would vanish has a negative weight.
+pub fn what_if<G, C>(computation: &C, inputs: &[Collection<G, C::Data>], hypothetical: &[Collection<G, C::Data>]) -> Collection<G, C::Output>
+    where G: Scope, C: Computation, G::Timestamp: Lattice+Ord {
+
+    let mut scope = inputs[0].inner.scope();
+    scope.scoped::<u32,_,_>(|shadow| {
+
+        // inputs as they stand at shadow time zero, and with the hypothetical changes at shadow time one.
+        let entered = inputs.iter()
+                            .zip(hypothetical.iter())
+                            .map(|(input, changes)| input.enter(shadow).concat(&changes.enter_at(shadow, |_| 1)))
+                            .collect::<Vec<_>>();
+
+        // only the changes at shadow time one are the impact; those at zero are the outputs as they stand.
+        let outputs = computation.build(&entered[..]);
+        Collection::new(outputs.inner.unary_stream(Pipeline, "Hypothetical", |input, output| {
+            while let Some((time, data)) = input.next() {
+                if time.time().inner == 1 {
+                    output.session(&time).give_iterator(data.drain(..));
+                }
+            }
+        }))
+        .leave()
+    })
+}
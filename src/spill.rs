@@ -0,0 +1,122 @@
+//! Disk-backed staging of provenance collections.
+//!
+//! The requirement collections maintained by the explanation infrastructure can grow much larger than the
+//! computations they explain. The `spill` operator sits in front of the arrangement that thresholds them: it
+//! holds updates until their times are complete, and once more than a configured number of records are held it
+//! writes batches out to files, reading them back only when the time they belong to is complete.
+//!
+//! Only the staged updates are spilled. The thresholding arrangement itself, which holds every accumulated
+//! requirement and dominates memory once an explanation is large, stays resident; spilling bounds the memory
+//! held by bursts of additions within a round, not the size of the must-set.
+//!
+//! Each spilling operator names its files by worker and by an identifier unique within the process, so that any
+//! number of operators may share one directory.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+use abomonation::{Abomonation, encode, decode};
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+use timely::dataflow::channels::pact::Pipeline;
+
+use differential_dataflow::{Data, Collection, Delta};
+
+// identifiers for spilling operators, distinguishing the files of operators sharing a directory.
+static NEXT_SPILL: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Configuration for spilling staged updates to disk.
+#[derive(Clone, Debug)]
+pub struct SpillConfig {
+    /// Directory in which spill files are written.
+    pub directory: PathBuf,
+    /// Number of records held in memory before a batch is written out.
+    pub threshold: usize,
+}
+
+impl SpillConfig {
+    /// Creates a new configuration spilling to `directory` whenever more than `threshold` records are held.
+    pub fn new<P: Into<PathBuf>>(directory: P, threshold: usize) -> SpillConfig {
+        SpillConfig {
+            directory: directory.into(),
+            threshold: threshold,
+        }
+    }
+}
+
+/// Extension trait for staging a collection through disk.
+pub trait Spill<G: Scope, D: Data> {
+    /// Holds updates until their times are complete, writing them to disk under memory pressure.
+    fn spill(&self, config: &SpillConfig) -> Collection<G, D>;
+}
+
+impl<G: Scope, D: Data+Abomonation> Spill<G, D> for Collection<G, D> {
+    fn spill(&self, config: &SpillConfig) -> Collection<G, D> {
+
+        let config = config.clone();
+        let worker = self.inner.scope().index();
+        let operator = NEXT_SPILL.fetch_add(1, Ordering::SeqCst);
+
+        // staged batches and spill files, by time; there are rarely more than a few open times.
+        let mut resident = Vec::<(G::Timestamp, Vec<(D, Delta)>)>::new();
+        let mut spilled = Vec::<(G::Timestamp, PathBuf)>::new();
+        let mut held = 0;
+        let mut files = 0;
+
+        Collection::new(self.inner.unary_notify(Pipeline, "Spill", vec![], move |input, output, notificator| {
+
+            while let Some((time, data)) = input.next() {
+                held += data.len();
+                if let Some(position) = resident.iter().position(|x| x.0 == time.time()) {
+                    resident[position].1.extend(data.drain(..));
+                }
+                else {
+                    resident.push((time.time(), data.drain(..).collect()));
+                }
+                notificator.notify_at(time);
+            }
+
+            // write out resident batches, largest first, until back under the threshold.
+            while held > config.threshold {
+                let largest = (0 .. resident.len()).max_by_key(|&i| resident[i].1.len());
+                if let Some(position) = largest {
+                    let (time, batch) = resident.swap_remove(position);
+                    held -= batch.len();
+
+                    let path = config.directory.join(format!("spill-{}-{}-{}", worker, operator, files));
+                    files += 1;
+
+                    let mut bytes = Vec::new();
+                    unsafe { encode(&batch, &mut bytes); }
+                    File::create(&path).and_then(|mut file| file.write_all(&bytes[..]))
+                                       .expect("failed to write spill file");
+
+                    spilled.push((time, path));
+                }
+                else { break; }
+            }
+
+            while let Some((time, _count)) = notificator.next() {
+                let mut session = output.session(&time);
+                if let Some(position) = resident.iter().position(|x| x.0 == time.time()) {
+                    let (_, batch) = resident.swap_remove(position);
+                    held -= batch.len();
+                    for record in batch { session.give(record); }
+                }
+                while let Some(position) = spilled.iter().position(|x| x.0 == time.time()) {
+                    let (_, path) = spilled.swap_remove(position);
+                    let mut bytes = Vec::new();
+                    File::open(&path).and_then(|mut file| file.read_to_end(&mut bytes))
+                                     .expect("failed to read spill file");
+                    if let Some((batch, _)) = unsafe { decode::<Vec<(D, Delta)>>(&mut bytes[..]) } {
+                        for record in batch.iter() { session.give(record.clone()); }
+                    }
+                    let _ = ::std::fs::remove_file(&path);
+                }
+            }
+        }))
+    }
+}
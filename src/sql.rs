@@ -0,0 +1,466 @@
+//! A small subset of SQL, compiled to instrumented dataflow through the `relational` builder.
+//!
+//! Queries have the form
+//!
+//! ```text
+//! SELECT a.src, MIN(b.dst)
+//! FROM edges a, edges AS b
+//! WHERE a.dst = b.src AND b.dst > 10
+//! GROUP BY a.src
+//! ```
+//!
+//! Tables are declared up front, with named `u32` columns. Columns may be written `alias.column`, or `column` alone
+//! where only one table in the `FROM` clause has it. `WHERE` conditions are conjunctions of comparisons between
+//! columns, or between a column and a number; equalities between tables are used as join conditions, in the
+//! order the tables are listed. Selected items are columns and `MIN`, `MAX`, `SUM`, or `COUNT` of columns; with
+//! aggregates or a `GROUP BY`, every selected column must be grouped, and no aggregated column may be.
+//!
+//! Results are sets, as if written `SELECT DISTINCT`. Each result row is explained by one row of each table it
+//! was joined from, except that a minimum or maximum is explained by one row holding it, and a sum or count by
+//! every row of its group. `summary::contributions` breaks a sum or count down by the rows explaining it.
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use Round;
+use relational::{Relation, Aggregate};
+use diagnostics::{self, Diagnostic};
+use generic;
+
+/// A table the query may read, with its columns in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+    /// The name of the table.
+    pub name: String,
+    /// The names of its columns.
+    pub columns: Vec<String>,
+}
+
+impl Table {
+    /// A table named `name` with columns `columns`.
+    pub fn new(name: &str, columns: &[&str]) -> Table {
+        Table { name: name.to_owned(), columns: columns.iter().map(|x| (*x).to_owned()).collect() }
+    }
+}
+
+/// A selected item, naming columns as `alias.column`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    /// A column.
+    Column(String),
+    /// The least value of a column in each group.
+    Min(String),
+    /// The greatest value of a column in each group.
+    Max(String),
+    /// The sum of a column in each group.
+    Sum(String),
+    /// The number of rows in each group, with the column counted named.
+    Count(String),
+}
+
+/// A comparison between two values.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl Comparison {
+    /// Whether `left` compares to `right` this way.
+    pub fn holds(&self, left: u32, right: u32) -> bool {
+        match *self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterEqual => left >= right,
+        }
+    }
+}
+
+/// The right-hand side of a condition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operand {
+    /// A column, as `alias.column`.
+    Column(String),
+    /// A constant.
+    Value(u32),
+}
+
+/// A condition a result row must satisfy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Condition {
+    /// A column, as `alias.column`.
+    pub left: String,
+    /// How the column compares to `right`.
+    pub comparison: Comparison,
+    /// A column or constant.
+    pub right: Operand,
+}
+
+/// A parsed query, with its columns resolved to `alias.column`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Query {
+    /// The selected items, in order.
+    pub select: Vec<Item>,
+    /// The tables read, as indices into the declared tables, and their aliases.
+    pub from: Vec<(usize, String)>,
+    /// The conditions, all of which must hold.
+    pub conditions: Vec<Condition>,
+    /// The grouping columns.
+    pub group_by: Vec<String>,
+}
+
+impl Query {
+    /// Parses and checks a query over `tables`, returning a description of the first problem found.
+    pub fn parse(text: &str, tables: &[Table]) -> Result<Query, String> {
+
+        let tokens = tokenize(text);
+        let mut tokens = tokens.iter().map(|x| &x[..]).peekable();
+
+        // SELECT items, with columns as written until the FROM clause names the tables.
+        try!(expect(&mut tokens, "SELECT"));
+        let mut select = Vec::new();
+        loop {
+            let item = match tokens.next() {
+                Some(token) if ["MIN", "MAX", "SUM", "COUNT"].iter().any(|x| keyword(token, x)) => {
+                    try!(expect(&mut tokens, "("));
+                    let column = try!(name(tokens.next()));
+                    try!(expect(&mut tokens, ")"));
+                    if keyword(token, "MIN") { Item::Min(column) }
+                    else if keyword(token, "MAX") { Item::Max(column) }
+                    else if keyword(token, "SUM") { Item::Sum(column) }
+                    else { Item::Count(column) }
+                },
+                token => Item::Column(try!(name(token))),
+            };
+            select.push(item);
+            if tokens.peek() == Some(&",") { tokens.next(); } else { break; }
+        }
+
+        // FROM tables, each with an optional alias.
+        try!(expect(&mut tokens, "FROM"));
+        let mut from = Vec::new();
+        loop {
+            let table = try!(name(tokens.next()));
+            let index = try!(tables.iter().position(|x| x.name == table).ok_or(format!("unknown table: {}", table)));
+            if tokens.peek().map(|x| keyword(x, "AS")) == Some(true) { tokens.next(); }
+            let alias = match tokens.peek().map(|x| *x) {
+                Some(token) if !is_keyword(token) && is_name(token) => { tokens.next(); token.to_owned() },
+                _ => table.clone(),
+            };
+            if from.iter().any(|x: &(usize, String)| x.1 == alias) { return Err(format!("alias used twice: {}", alias)); }
+            from.push((index, alias));
+            if tokens.peek() == Some(&",") { tokens.next(); } else { break; }
+        }
+
+        let resolve = |column: &str| qualify(column, tables, &from);
+
+        // WHERE conditions, joined by AND.
+        let mut conditions = Vec::new();
+        if tokens.peek().map(|x| keyword(x, "WHERE")) == Some(true) {
+            tokens.next();
+            loop {
+                let left = try!(resolve(&try!(name(tokens.next()))));
+                let comparison = match tokens.next() {
+                    Some("=") => Comparison::Equal,
+                    Some("<>") | Some("!=") => Comparison::NotEqual,
+                    Some("<") => Comparison::Less,
+                    Some("<=") => Comparison::LessEqual,
+                    Some(">") => Comparison::Greater,
+                    Some(">=") => Comparison::GreaterEqual,
+                    token => return Err(format!("expected a comparison, found {:?}", token)),
+                };
+                let right = match tokens.next() {
+                    Some(token) if token.chars().all(|c| c.is_digit(10)) => {
+                        Operand::Value(try!(token.parse().map_err(|_| format!("bad number: {}", token))))
+                    },
+                    token => Operand::Column(try!(resolve(&try!(name(token))))),
+                };
+                conditions.push(Condition { left: left, comparison: comparison, right: right });
+                if tokens.peek().map(|x| keyword(x, "AND")) == Some(true) { tokens.next(); } else { break; }
+            }
+        }
+
+        // GROUP BY columns.
+        let mut group_by = Vec::new();
+        if tokens.peek().map(|x| keyword(x, "GROUP")) == Some(true) {
+            tokens.next();
+            try!(expect(&mut tokens, "BY"));
+            loop {
+                group_by.push(try!(resolve(&try!(name(tokens.next())))));
+                if tokens.peek() == Some(&",") { tokens.next(); } else { break; }
+            }
+        }
+
+        if tokens.peek() == Some(&";") { tokens.next(); }
+        if let Some(token) = tokens.next() { return Err(format!("unexpected {}", token)); }
+
+        let mut resolved = Vec::new();
+        for item in select.into_iter() {
+            resolved.push(match item {
+                Item::Column(column) => Item::Column(try!(resolve(&column))),
+                Item::Min(column) => Item::Min(try!(resolve(&column))),
+                Item::Max(column) => Item::Max(try!(resolve(&column))),
+                Item::Sum(column) => Item::Sum(try!(resolve(&column))),
+                Item::Count(column) => Item::Count(try!(resolve(&column))),
+            });
+        }
+
+        let query = Query { select: resolved, from: from.clone(), conditions: conditions, group_by: group_by };
+        if query.aggregates() || !query.group_by.is_empty() {
+            for item in query.select.iter() {
+                if let Item::Column(ref column) = *item {
+                    if !query.group_by.contains(column) {
+                        return Err(format!("{} must be grouped", column));
+                    }
+                }
+            }
+            for item in query.select.iter() {
+                match *item {
+                    Item::Column(_) => { },
+                    Item::Min(ref column) | Item::Max(ref column) | Item::Sum(ref column) | Item::Count(ref column) => {
+                        if query.group_by.contains(column) {
+                            return Err(format!("{} is grouped, and cannot be aggregated", column));
+                        }
+                    },
+                }
+            }
+        }
+
+        Ok(query)
+    }
+
+    // whether any selected item is an aggregate.
+    fn aggregates(&self) -> bool {
+        self.select.iter().any(|x| match *x { Item::Column(_) => false, _ => true })
+    }
+
+    /// The names of the result's columns: selected columns as `alias.column`, and aggregates as `min(alias.column)`,
+    /// `max(alias.column)`, `sum(alias.column)`, and `count(alias.column)`.
+    pub fn columns(&self) -> Vec<String> {
+        self.select.iter().map(|item| match *item {
+            Item::Column(ref column) => column.clone(),
+            Item::Min(ref column) => format!("min({})", column),
+            Item::Max(ref column) => format!("max({})", column),
+            Item::Sum(ref column) => format!("sum({})", column),
+            Item::Count(ref column) => format!("count({})", column),
+        }).collect()
+    }
+
+    /// Builds the query's result from `tables`, one relation for each declared table, in order.
+    ///
+    /// Tables are joined in the order of the `FROM` clause, each on the equalities relating it to those before it.
+    /// Other conditions then filter the joined rows, grouping and aggregation follow, and the result is projected
+    /// onto the selected items.
+    pub fn build<'a, G>(&self, tables: &mut [Relation<'a, G>]) -> Relation<'a, G>
+        where G: Scope<Timestamp=Product<Product<RootTimestamp, u32>, Round>> {
+
+        // each table's rows, with columns named by alias.
+        let mut relations = self.from.iter().map(|&(index, ref alias)| {
+            let table = &mut tables[index];
+            let schema = table.schema.iter().map(|x| format!("{}.{}", alias, x)).collect();
+            Relation { schema: schema, rows: table.rows.map_inverse(|x| x, |x| x) }
+        }).collect::<Vec<_>>().into_iter();
+
+        // joins drop the right-hand columns of their conditions, which are then found as their left-hand columns.
+        let mut renamed = Vec::<(String, String)>::new();
+        let rename = |renamed: &Vec<(String, String)>, column: &str| {
+            renamed.iter().find(|x| x.0 == column).map(|x| x.1.clone()).unwrap_or(column.to_owned())
+        };
+
+        let mut result = relations.next().unwrap();
+        for mut relation in relations {
+            // a column equal to several earlier columns joins on the first, and the rest filter the joined rows.
+            let mut on = Vec::<(String, String)>::new();
+            for condition in self.conditions.iter() {
+                if let (Comparison::Equal, &Operand::Column(ref right)) = (condition.comparison, &condition.right) {
+                    let left = rename(&renamed, &condition.left);
+                    let right = rename(&renamed, right);
+                    let pair = if result.schema.contains(&left) && relation.schema.contains(&right) { Some((left, right)) }
+                               else if result.schema.contains(&right) && relation.schema.contains(&left) { Some((right, left)) }
+                               else { None };
+                    if let Some(pair) = pair {
+                        if !on.iter().any(|x| x.1 == pair.1) { on.push(pair); }
+                    }
+                }
+            }
+            let pairs = on.iter().map(|x| (&x.0[..], &x.1[..])).collect::<Vec<_>>();
+            result = result.join(&mut relation, &pairs[..]);
+            renamed.extend(on.into_iter().map(|(left, right)| (right, left)));
+        }
+
+        // the remaining conditions filter the joined rows; equalities the joins applied now compare a column to itself.
+        for condition in self.conditions.iter() {
+            let left = rename(&renamed, &condition.left);
+            let comparison = condition.comparison;
+            result = match condition.right {
+                Operand::Column(ref right) => {
+                    let right = rename(&renamed, right);
+                    if comparison == Comparison::Equal && left == right { continue; }
+                    result.select(&[&left[..], &right[..]], move |x| comparison.holds(x[0], x[1]))
+                },
+                Operand::Value(value) => result.select(&[&left[..]], move |x| comparison.holds(x[0], value)),
+            };
+        }
+
+        let columns = self.columns();
+        let mut result = if !self.aggregates() && self.group_by.is_empty() {
+            let selected = self.select.iter().map(|item| match *item {
+                Item::Column(ref column) => rename(&renamed, column),
+                _ => unreachable!(),
+            }).collect::<Vec<_>>();
+            result.project(&selected.iter().map(|x| &x[..]).collect::<Vec<_>>())
+        }
+        else {
+            // each aggregate is computed by group, and the aggregates joined on the group's columns.
+            let group = self.group_by.iter().map(|x| rename(&renamed, x)).collect::<Vec<_>>();
+            let group = group.iter().map(|x| &x[..]).collect::<Vec<_>>();
+            let on = group.iter().map(|x| (*x, *x)).collect::<Vec<_>>();
+            let mut grouped = None;
+            for (item, name) in self.select.iter().zip(columns.iter()) {
+                let mut aggregated = match *item {
+                    Item::Column(_) => continue,
+                    Item::Min(ref column) => result.aggregate(&group, Aggregate::Min, &rename(&renamed, column), name),
+                    Item::Max(ref column) => result.aggregate(&group, Aggregate::Max, &rename(&renamed, column), name),
+                    Item::Sum(ref column) => result.aggregate(&group, Aggregate::Sum, &rename(&renamed, column), name),
+                    Item::Count(ref column) => result.aggregate(&group, Aggregate::Count, &rename(&renamed, column), name),
+                };
+                grouped = Some(match grouped {
+                    Some(mut grouped) => Relation::join(&mut grouped, &mut aggregated, &on[..]),
+                    None => aggregated,
+                });
+            }
+            let mut grouped = grouped.unwrap_or_else(|| result.project(&group));
+            let selected = self.select.iter().zip(columns.iter()).map(|(item, name)| match *item {
+                Item::Column(ref column) => rename(&renamed, column),
+                _ => name.clone(),
+            }).collect::<Vec<_>>();
+            grouped.project(&selected.iter().map(|x| &x[..]).collect::<Vec<_>>())
+        };
+
+        result.schema = columns;
+        result
+    }
+}
+
+/// Explains the result rows of `query` over `tables`, for a collection of queries.
+///
+/// `inputs` hold the rows of each declared table, in order. Queries are `(row, time, query)`, naming a result row
+/// as its values in the order of `query.columns()`, and are explained by table rows as they were at `time`.
+/// Returns the required table rows as `(table, row, query)`, markers for the first correction round in which each
+/// query's requirements stopped changing, and diagnostics for queries naming absent rows.
+pub fn explain<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(
+    streaming: &mut G,
+    query: &Query,
+    tables: &[Table],
+    inputs: &[Collection<G, Vec<u32>>],
+    queries: &Collection<G, (Vec<u32>, Product<Product<RootTimestamp, u32>, Round>, u32)>)
+-> (Collection<G, (u32, Vec<u32>, u32)>, Collection<G, (u32, Round)>, Collection<G, Diagnostic>) {
+
+    let plan = query.clone();
+    let tables = tables.to_vec();
+
+    // inputs are rows with empty values, and queries name rows the same way.
+    let inputs = inputs.iter().map(|x| x.map(|row| (row, vec![]))).collect::<Vec<_>>();
+    let queries = queries.map(|(row,t,q)| (row,vec![],t,q));
+
+    let (musts, converged, diagnostics) = generic::drive(streaming, &inputs[..], &queries, move |vars, queries, _explanation_scope| {
+
+        let mut relations = tables.iter().zip(vars.into_iter()).map(|(table, var)| {
+            let columns = table.columns.iter().map(|x| &x[..]).collect::<Vec<_>>();
+            Relation::new(&columns, var)
+        }).collect::<Vec<_>>();
+
+        let mut result = plan.build(&mut relations[..]);
+
+        // introduce queries as initial dependences, and report those naming absent rows.
+        result.rows.seed(queries);
+        diagnostics::not_found(queries, &result.rows.stream)
+    });
+
+    (musts.map(|(index,(row,_),q)| (index,row,q)), converged, diagnostics)
+}
+
+// splits text into names, numbers, and punctuation.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() { continue; }
+        let mut token = c.to_string();
+        if is_name_char(c) {
+            while chars.peek().map(|&c| is_name_char(c)) == Some(true) { token.push(chars.next().unwrap()); }
+        }
+        else if c == '<' || c == '>' || c == '!' {
+            if chars.peek() == Some(&'=') || (c == '<' && chars.peek() == Some(&'>')) { token.push(chars.next().unwrap()); }
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+// names are non-empty runs of letters, digits, and underscores, with at most one '.' between runs.
+fn is_name(text: &str) -> bool {
+    let parts = text.split('.').collect::<Vec<_>>();
+    parts.len() <= 2 && parts.iter().all(|x| x.len() > 0 && x.chars().all(|c| c.is_alphanumeric() || c == '_'))
+}
+
+fn keyword(token: &str, word: &str) -> bool {
+    token.to_uppercase() == word
+}
+
+fn is_keyword(token: &str) -> bool {
+    ["SELECT", "FROM", "WHERE", "AND", "GROUP", "BY", "AS", "MIN", "MAX", "SUM", "COUNT"].iter().any(|x| keyword(token, x))
+}
+
+// consumes `word`, or reports what was found instead.
+fn expect<'t, I: Iterator<Item=&'t str>>(tokens: &mut I, word: &str) -> Result<(), String> {
+    match tokens.next() {
+        Some(token) if keyword(token, word) => Ok(()),
+        token => Err(format!("expected {}, found {:?}", word, token)),
+    }
+}
+
+// a name that is not a keyword.
+fn name(token: Option<&str>) -> Result<String, String> {
+    match token {
+        Some(token) if is_name(token) && !is_keyword(token) => Ok(token.to_owned()),
+        token => Err(format!("expected a name, found {:?}", token)),
+    }
+}
+
+// resolves a column written `alias.column` or `column` to `alias.column`.
+fn qualify(column: &str, tables: &[Table], from: &[(usize, String)]) -> Result<String, String> {
+    let mut parts = column.splitn(2, '.');
+    let first = parts.next().unwrap();
+    match parts.next() {
+        Some(name) => {
+            match from.iter().find(|x| x.1 == first) {
+                Some(&(index, _)) if tables[index].columns.iter().any(|x| x == name) => Ok(column.to_owned()),
+                Some(_) => Err(format!("unknown column: {}", column)),
+                None => Err(format!("unknown table or alias: {}", first)),
+            }
+        },
+        None => {
+            let found = from.iter().filter(|x| tables[x.0].columns.iter().any(|y| y == first)).collect::<Vec<_>>();
+            match found.len() {
+                0 => Err(format!("unknown column: {}", column)),
+                1 => Ok(format!("{}.{}", found[0].1, first)),
+                _ => Err(format!("ambiguous column: {}", column)),
+            }
+        },
+    }
+}
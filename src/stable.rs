@@ -0,0 +1,115 @@
+//! Stable matching, instrumented for explanation.
+//!
+//! Each individual proposes to its most appealing option not yet rejected, each recipient accepts its most
+//! appealing proposal, and rejected proposals are fed back until no proposals change. Preferences are
+//! `(a_id, (a_pref, b_id, b_pref))`, and queries name matched pairs in the same form.
+
+use std::cell::RefCell;
+
+use timely::dataflow::*;
+use timely::dataflow::scopes::Child;
+use timely::dataflow::operators::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+
+use {Variable, MonotonicVariable, Round, converged, dedup_queries, fan_out, count_at_query};
+use algos;
+use diagnostics::{self, Diagnostic};
+use testing::Dataflow;
+
+/// Explains stable matching for a collection of queries.
+///
+/// Returns the required preference records, each tagged by the query requiring them, markers for the first
+/// correction round in which each query's requirements stopped changing, and diagnostics for queries that could
+/// not be explained as asked. Queries are `(a_id, (a_pref, b_id, b_pref), time, query)`.
+pub fn explain<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(
+    streaming: &mut G,
+    prefs: &Collection<G, (u32, (u32, u32, u32))>,
+    query: &Collection<G, (u32, (u32, u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32)>)
+-> (Collection<G, ((u32, (u32, u32, u32)), u32)>, Collection<G, (u32, Round)>, Collection<G, Diagnostic>) {
+
+    let prefs = prefs.clone();
+
+    // identical queries share one explanation, reported to each of them at the end.
+    let (query, aliases) = dedup_queries(query);
+
+    // Iterative scope for rounds of input correction
+    let (prefs_must, converged, diagnostics) = streaming.scoped::<Round,_,_>(move |correction| {
+
+        // Bring each input into the scope.
+        let prefs = prefs.enter(correction);
+        let query = query.enter(correction);
+
+        // Each data input uses a MonotonicVariable to track its elements required to explain outputs.
+        // These collections grow monotonically in each round of correction, limited by the full set.
+        // Elements are tagged with the query requiring them, so that a batch of queries can share a
+        // fixpoint and still have their explanations reported separately.
+        let mut prefs_must = MonotonicVariable::new(correction);
+
+        // the working input is the union of requirements across all queries, where still present.
+        let prefs_work = prefs_must.stream.map(|(x,_q)| (x,())).semijoin(&prefs).map(|(x,())| x).threshold(|_, w| if w > 0 { 1 } else { 0 });
+
+        // Scope for explanation derivation.
+        let child_scope = RefCell::new(correction.new_subscope());
+        let child_index = child_scope.borrow().index;
+
+        // determine and return necessary members of `prefs`.
+        let (prefs_need, not_found) = {
+
+            // wrap an explanation scope builder.
+            let mut explanation_scope = Child {
+                subgraph: &child_scope,
+                parent: correction.clone(),
+            };
+
+            // define variables for each input to the computation.
+            // the data source is from outside the correction loop,
+            // and the working source is the *_need stream.
+            let mut var_prefs = Variable::new(prefs.clone(), prefs_work, &mut explanation_scope);
+
+            // proposals and rejections iterate to a matching; see `algos::stable_matching`.
+            let mut final_prefs = algos::stable_matching(&mut var_prefs, &mut explanation_scope);
+
+            // introduce any query elements as initial dependences.
+            final_prefs.seed(&query);
+
+            // queries naming matches that do not exist cannot be explained, and are reported instead.
+            let not_found = diagnostics::not_found(&query, &final_prefs.stream);
+
+            // pop input requirements out of the explanation scope and return them.
+            (var_prefs.depends.stream.leave(), not_found)
+        };
+
+        // all explanation infrastructure in place; add to correct scope.
+        correction.add_operator_with_index(child_scope.into_inner(), child_index);
+
+        // intersect required preferences with preferences as of each query's time.
+        prefs_must.add(&count_at_query(&prefs_need, &prefs, &query).threshold(|_, w| if w > 0 { 1 } else { 0 }));
+
+        // report the first round in which each query's requirements stopped changing.
+        let converged = converged(&prefs_must.stream, |x| x.1);
+
+        // merge the things we need, pop them out of the loop, and probe
+        (prefs_must.stream.leave(), converged.leave(), not_found.leave())
+    });
+
+    (fan_out(&prefs_must, &aliases),
+     fan_out(&converged.map(|(q,r)| (r,q)), &aliases).map(|(r,q)| (q,r)),
+     fan_out(&diagnostics.map(|d| (d, d.query())), &aliases).map(|(d,q)| d.for_query(q)))
+}
+
+/// The instrumented computation as a `testing::Dataflow`, with a single preference input.
+pub struct Instrumented;
+
+impl Dataflow for Instrumented {
+    type Data = (u32, (u32, u32, u32));
+    type Query = (u32, (u32, u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, (u32, u32, u32)), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let (prefs_must, _, _) = explain(scope, &inputs[0], queries);
+        vec![prefs_must]
+    }
+}
@@ -0,0 +1,110 @@
+//! Summaries of required records, by user-defined patterns.
+//!
+//! A must-set of thousands of edges says little when read record by record. A summary instead counts, for each
+//! query, the required records matching each of a list of named patterns, such as "edges incident to node 7" or
+//! "labels in 0 .. 10", with records matching no pattern counted as `other`. The counts are ordinary collections,
+//! maintained as the must-set changes, and `drill_down` recovers the records behind any one count.
+//!
+//! Patterns may overlap, in which case a record is counted once under each pattern it matches.
+//!
+//! Where the queried output is a sum or a count, which records mattered is only half the story. `contributions`
+//! reports how much each required record contributed, as its value times its count in the input, so that the
+//! contributions of a complete explanation add up to the queried aggregate.
+//!
+//! Asking why two outputs differ, say why nodes 4 and 9 have different labels, is a question about two
+//! explanations at once. `difference` compares the must-sets of pairs of queries, and reports the records only
+//! one of them requires, which are the inputs on which the two outputs part ways.
+
+use std::rc::Rc;
+use std::hash::Hash;
+
+use timely::dataflow::*;
+
+use differential_dataflow::{Data, Collection};
+use differential_dataflow::operators::*;
+use differential_dataflow::lattice::Lattice;
+
+/// Named predicates over records of type `D`.
+pub struct Patterns<D> {
+    patterns: Vec<(String, Rc<Fn(&D)->bool>)>,
+}
+
+impl<D> Patterns<D> {
+    /// An empty list of patterns, under which every record is `other`.
+    pub fn new() -> Self {
+        Patterns { patterns: Vec::new() }
+    }
+    /// Adds a pattern called `name`, matching the records for which `matches` holds.
+    pub fn pattern<F: Fn(&D)->bool+'static>(mut self, name: &str, matches: F) -> Self {
+        self.patterns.push((name.to_owned(), Rc::new(matches)));
+        self
+    }
+    /// The name of pattern `index`, as numbered in summaries; the last number is `other`.
+    pub fn name(&self, index: u32) -> &str {
+        self.patterns.get(index as usize).map(|x| &x.0[..]).unwrap_or("other")
+    }
+    /// The numbers of the patterns `record` matches, or just that of `other` if it matches none.
+    pub fn matching(&self, record: &D) -> Vec<u32> {
+        let matched = (0 .. self.patterns.len()).filter(|&i| (self.patterns[i].1)(record)).map(|i| i as u32).collect::<Vec<_>>();
+        if matched.is_empty() { vec![self.patterns.len() as u32] } else { matched }
+    }
+}
+
+impl<D> Clone for Patterns<D> {
+    fn clone(&self) -> Self {
+        Patterns { patterns: self.patterns.clone() }
+    }
+}
+
+/// Counts the `(record, query)` requirements of `must` matching each of `patterns`, as `((pattern, query), count)`.
+///
+/// Patterns are numbered in the order they were added, with `other` last. Only patterns a query requires some
+/// record of are reported for it.
+pub fn summarize<G, D>(must: &Collection<G, (D, u32)>, patterns: &Patterns<D>) -> Collection<G, ((u32, u32), u32)>
+    where G: Scope, D: Data, G::Timestamp: Lattice+Ord {
+    let patterns = patterns.clone();
+    must.flat_map(move |(x,q)| patterns.matching(&x).into_iter().map(move |p| ((p,q),())))
+        .group(|_k, s, t| t.push((s.map(|(_,w)| w).sum::<i32>() as u32, 1)))
+}
+
+/// The `(record, query)` requirements of `must` counted under pattern number `pattern`.
+pub fn drill_down<G, D>(must: &Collection<G, (D, u32)>, patterns: &Patterns<D>, pattern: u32) -> Collection<G, (D, u32)>
+    where G: Scope, D: Data {
+    let patterns = patterns.clone();
+    must.filter(move |&(ref x,_)| patterns.matching(x).contains(&pattern))
+}
+
+/// The contribution of each `(record, query)` requirement of `must` to its query's aggregate, as
+/// `((record, query), contribution)`.
+///
+/// A record contributes `value(record)` for each of its copies in `input`, so that a sum is broken down with
+/// `value` extracting the summed column, and a count with `value` always one. Copies are counted as `input`
+/// stands at each time, rather than as of the time each query asks about. Records absent from `input` contribute
+/// nothing, and are not reported.
+pub fn contributions<G, D, F>(must: &Collection<G, (D, u32)>, input: &Collection<G, D>, value: F) -> Collection<G, ((D, u32), i64)>
+    where G: Scope, D: Data+Default+Hash, F: Fn(&D)->i64+'static, G::Timestamp: Lattice+Ord {
+    must.semijoin(input)
+        .group(move |x, s, t| for (q, w) in s { t.push(((*q, value(x) * w as i64), 1)); })
+        .map(|(x,(q,c))| ((x,q),c))
+}
+
+/// The records of `must` required by exactly one query of each `(query1, query2)` pair of `comparisons`, as
+/// `((record, (query1, query2)), query)` naming the query requiring the record.
+///
+/// Records both queries require are common ground and explain neither output's difference from the other, while
+/// records one requires and the other does not are where their derivations diverge. Each query should ask about
+/// the same time, as the must-sets of queries about different times differ for that reason alone. A query
+/// compared with itself has no difference, and is not reported.
+pub fn difference<G, D>(must: &Collection<G, (D, u32)>, comparisons: &Collection<G, (u32, u32)>) -> Collection<G, ((D, (u32, u32)), u32)>
+    where G: Scope, D: Data+Default+Hash, G::Timestamp: Lattice+Ord {
+    let comparisons = comparisons.filter(|&(a,b)| a != b);
+    let by_query = must.map(|(x,q)| (q,x));
+    let first = by_query.join_u(&comparisons.map(|(a,b)| (a,(a,b))));
+    let second = by_query.join_u(&comparisons.map(|(a,b)| (b,(a,b))));
+    first.concat(&second)
+         .map(|(q,x,pair)| ((x,pair),q))
+         .group(|_k, s, t| {
+             let sides = s.filter(|x| x.1 > 0).map(|(q,_)| *q).collect::<Vec<_>>();
+             if sides.len() == 1 { t.push((sides[0], 1)); }
+         })
+}
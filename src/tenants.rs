@@ -0,0 +1,90 @@
+//! Query namespaces, for explanation services shared by several clients.
+//!
+//! Query identifiers are plain `u32`s throughout, and nothing stops two clients of a shared service from choosing
+//! the same ones. `Namespaces` partitions the identifiers instead, reserving the high bits for the client and the
+//! low bits for the client's own identifiers, so that queries from different clients never collide and each
+//! must-set record can be routed back to the client that asked. `isolate` splits must-sets by client, and `admit`
+//! and `within_quota` enforce per-client limits on concurrent queries and on the size of their results.
+
+use std::hash::Hash;
+
+use timely::dataflow::*;
+
+use differential_dataflow::{Data, Collection};
+use differential_dataflow::operators::*;
+use differential_dataflow::lattice::Lattice;
+
+/// A partition of query identifiers into client namespaces.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Namespaces {
+    bits: u32,
+}
+
+impl Namespaces {
+    /// Namespaces whose clients each have `bits` bits of query identifiers, leaving `32 - bits` for clients.
+    pub fn new(bits: u32) -> Namespaces {
+        assert!(bits > 0 && bits < 32, "namespaces need bits for both clients and queries, not {}", bits);
+        Namespaces { bits: bits }
+    }
+    /// The query identifier of `client`'s query `local`.
+    pub fn id(&self, client: u32, local: u32) -> u32 {
+        assert!(client < (1 << (32 - self.bits)), "client {} exceeds the namespaces", client);
+        assert!(local < (1 << self.bits), "query {} exceeds its client's namespace", local);
+        (client << self.bits) | local
+    }
+    /// Whether `local` fits in a client's namespace, so that `id` accepts it.
+    pub fn fits(&self, local: u32) -> bool {
+        local < (1 << self.bits)
+    }
+    /// The client a query identifier belongs to.
+    pub fn client(&self, id: u32) -> u32 {
+        id >> self.bits
+    }
+    /// The client's own identifier for a query.
+    pub fn local(&self, id: u32) -> u32 {
+        id & ((1 << self.bits) - 1)
+    }
+}
+
+/// Splits `(record, query)` requirements by client, as `(client, (record, local query))`.
+pub fn isolate<G: Scope, D: Data>(namespaces: Namespaces, must: &Collection<G, (D, u32)>) -> Collection<G, (u32, (D, u32))> {
+    must.map(move |(x,q)| (namespaces.client(q), (x, namespaces.local(q))))
+}
+
+/// Admits at most `limit(client)` concurrent queries from each client, returning the admitted and rejected queries.
+///
+/// Each client's queries with the least identifiers are admitted; withdrawing one admits the next in its place.
+pub fn admit<G, K, V, T, F>(namespaces: Namespaces, queries: &Collection<G, (K, V, T, u32)>, limit: F) -> (Collection<G, (K, V, T, u32)>, Collection<G, (K, V, T, u32)>)
+    where G: Scope, K: Data+Default+Hash, V: Data+Default+Hash, T: Data+Default+Hash, F: Fn(u32)->u32+'static, G::Timestamp: Lattice+Ord {
+
+    let admitted = queries.map(move |x| (namespaces.client(x.3), x))
+                          .group(move |&client, s, t| {
+                              let present = s.map(|(x,_)| (*x).clone()).collect::<Vec<_>>();
+                              let mut ids = present.iter().map(|x| x.3).collect::<Vec<_>>();
+                              ids.sort();
+                              ids.dedup();
+                              ids.truncate(limit(client) as usize);
+                              for x in present.into_iter().filter(|x| ids.contains(&x.3)) { t.push((x, 1)); }
+                          })
+                          .map(|(_,x)| x);
+
+    let rejected = queries.concat(&admitted.negate());
+    (admitted, rejected)
+}
+
+/// Withholds the requirements of each client whose results exceed `quota(client)` records.
+///
+/// Returns the requirements of clients within their quotas, and `(client, records)` for each client over its quota.
+/// A client's results are withheld entirely rather than truncated, as part of an explanation does not explain.
+pub fn within_quota<G, D, F>(namespaces: Namespaces, must: &Collection<G, (D, u32)>, quota: F) -> (Collection<G, (D, u32)>, Collection<G, (u32, u32)>)
+    where G: Scope, D: Data+Default+Hash, F: Fn(u32)->u32+'static, G::Timestamp: Lattice+Ord {
+
+    let sizes = must.map(move |(_,q)| (namespaces.client(q), ()))
+                    .group(|_client, s, t| t.push((s.map(|(_,w)| w).sum::<i32>() as u32, 1)));
+    let exceeded = sizes.filter(move |&(client, records)| records > quota(client));
+
+    let keyed = must.map(move |(x,q)| (namespaces.client(q), (x,q)));
+    let within = keyed.concat(&keyed.semijoin(&exceeded.map(|(client,_)| client)).negate())
+                      .map(|(_,x)| x);
+    (within, exceeded)
+}
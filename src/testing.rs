@@ -0,0 +1,193 @@
+//! Scripted single-worker runs of instrumented dataflows, for use in tests.
+//!
+//! A `Dataflow` describes how to build an instrumented computation from its input collections and a collection of
+//! queries, and which must-sets it produces. `run` builds it in the calling thread, applies a script of `Epoch`s,
+//! each a batch of input updates and queries, steps the computation until each epoch is complete, and returns the
+//! must-sets as they stand after each epoch. Assertions can then be made on plain vectors, rather than on the text
+//! the interactive examples print. `run_overlapped` instead introduces epochs without waiting for earlier ones to
+//! complete, for checking that epochs in flight together do not affect one another. `assert_explains` compares
+//! must-sets within the dataflow instead, reporting a verdict for each query.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::{Data, Collection};
+use differential_dataflow::operators::*;
+use differential_dataflow::lattice::Lattice;
+
+/// An instrumented computation, with some number of inputs of a common type and a collection of queries.
+pub trait Dataflow {
+    /// The type of input records.
+    type Data: Data+Send;
+    /// The type of queries.
+    type Query: Data+Send;
+    /// The type of records in the must-sets.
+    type Must: Data+Send+Hash;
+    /// Builds the computation in `scope`, and returns its must-sets.
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>>;
+}
+
+/// Input updates and queries introduced together, as one epoch of a script.
+#[derive(Clone, Debug)]
+pub struct Epoch<D, Q> {
+    /// Updates for each input, as `(input, record, weight)`.
+    pub updates: Vec<(usize, D, i32)>,
+    /// Queries to add (positive weight) or withdraw (negative weight).
+    pub queries: Vec<(Q, i32)>,
+}
+
+impl<D, Q> Epoch<D, Q> {
+    /// An epoch with no updates and no queries.
+    pub fn new() -> Self {
+        Epoch { updates: Vec::new(), queries: Vec::new() }
+    }
+    /// Adds an update of `weight` to `record` in input `input`.
+    pub fn update(mut self, input: usize, record: D, weight: i32) -> Self {
+        self.updates.push((input, record, weight));
+        self
+    }
+    /// Adds an update of `weight` to query `query`.
+    pub fn query(mut self, query: Q, weight: i32) -> Self {
+        self.queries.push((query, weight));
+        self
+    }
+}
+
+/// Builds `dataflow` with `inputs` inputs on a single worker, applies `script` one epoch at a time, and returns the
+/// must-sets after each epoch.
+///
+/// The result is indexed first by epoch and then by must-set, in the order `build` returns them. Each must-set is
+/// the sorted list of records with positive accumulated weight once the epoch is complete.
+pub fn run<F>(dataflow: F, inputs: usize, script: Vec<Epoch<F::Data, F::Query>>) -> Vec<Vec<Vec<F::Must>>>
+    where F: Dataflow+Send+Sync+'static, F::Must: Ord {
+    execute(dataflow, inputs, script, None)
+}
+
+/// As `run`, but introduces each epoch after only `steps` steps of the computation, rather than once the previous
+/// epoch is complete, so that several epochs are in flight at once.
+///
+/// The must-sets reported for each epoch are those at that epoch, which should be the same as `run` reports for
+/// the same script, however many later epochs were introduced before it completed.
+pub fn run_overlapped<F>(dataflow: F, inputs: usize, script: Vec<Epoch<F::Data, F::Query>>, steps: usize) -> Vec<Vec<Vec<F::Must>>>
+    where F: Dataflow+Send+Sync+'static, F::Must: Ord {
+    execute(dataflow, inputs, script, Some(steps))
+}
+
+// runs the script, stepping each epoch to completion or for a fixed number of steps, and reports must-sets as of
+// each epoch from updates recorded with their times.
+fn execute<F>(dataflow: F, inputs: usize, script: Vec<Epoch<F::Data, F::Query>>, steps: Option<usize>) -> Vec<Vec<Vec<F::Must>>>
+    where F: Dataflow+Send+Sync+'static, F::Must: Ord {
+
+    let epochs = script.len();
+    let updates = Arc::new(Mutex::new(Vec::new()));
+    let shared = updates.clone();
+
+    ::timely::execute(::timely::Configuration::Thread, move |root| {
+
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let results2 = results.clone();
+        let (mut input_handles, mut query_handle, probes) = root.scoped::<u32, _, _>(|streaming| {
+
+            let mut handles = Vec::new();
+            let mut collections = Vec::new();
+            for _ in 0 .. inputs {
+                let (handle, stream) = streaming.new_input();
+                handles.push(handle);
+                collections.push(Collection::new(stream));
+            }
+            let (query_handle, query) = streaming.new_input();
+
+            let mut probes = Vec::new();
+            for (index, must) in dataflow.build(streaming, &collections[..], &Collection::new(query)).into_iter().enumerate() {
+                results2.borrow_mut().push(Vec::new());
+                let results = results2.clone();
+                probes.push(must.inner
+                                .inspect_batch(move |t, xs| {
+                                    for &(ref x, w) in xs.iter() {
+                                        results.borrow_mut()[index].push((t.inner, x.clone(), w));
+                                    }
+                                })
+                                .probe().0);
+            }
+
+            (handles, query_handle, probes)
+        });
+
+        for (index, epoch) in script.iter().enumerate() {
+            for &(input, ref record, weight) in epoch.updates.iter() {
+                input_handles[input].send((record.clone(), weight));
+            }
+            for &(ref query, weight) in epoch.queries.iter() {
+                query_handle.send((query.clone(), weight));
+            }
+            for handle in input_handles.iter_mut() { handle.advance_to(index as u32 + 1); }
+            query_handle.advance_to(index as u32 + 1);
+            match steps {
+                None => root.step_while(|| probes.iter().any(|p| p.lt(&query_handle.time()))),
+                Some(steps) => for _ in 0 .. steps { root.step(); },
+            }
+        }
+        root.step_while(|| probes.iter().any(|p| p.lt(&query_handle.time())));
+
+        *shared.lock().unwrap() = ::std::mem::replace(&mut *results.borrow_mut(), Vec::new());
+
+    }).unwrap();
+
+    let updates = updates.lock().unwrap();
+    (0 .. epochs).map(|epoch| {
+        updates.iter().map(|must| {
+            let mut counts = HashMap::new();
+            for &(time, ref x, w) in must.iter() {
+                if time as usize <= epoch {
+                    *counts.entry(x.clone()).or_insert(0) += w;
+                }
+            }
+            let mut records = counts.into_iter().filter(|x| x.1 > 0).map(|x| x.0).collect::<Vec<_>>();
+            records.sort();
+            records
+        }).collect()
+    }).collect()
+}
+
+/// Compares a computed must-set with an expected one, and reports a verdict for each query either mentions.
+///
+/// Both collections hold `(record, query)` pairs, and are compared as sets. Each query is reported once, as
+/// `(query, (passed, missing, extra))`, where `missing` lists the expected records not computed and `extra` the
+/// computed records not expected, each sorted; a query passes when both are empty. Verdicts are revised as either
+/// collection changes, so a test can assert on the verdicts at each epoch rather than on the must-sets themselves.
+pub fn assert_explains<G, D>(expected: &Collection<G, (D, u32)>, computed: &Collection<G, (D, u32)>) -> Collection<G, (u32, (bool, Vec<D>, Vec<D>))>
+    where G: Scope, D: Data+Default+Hash, G::Timestamp: Lattice+Ord {
+
+    let expected = expected.threshold(|_, w| if w > 0 { 1 } else { 0 });
+    let computed = computed.threshold(|_, w| if w > 0 { 1 } else { 0 });
+
+    // positive weights are expected records not computed, negative weights computed records not expected.
+    let difference = expected.concat(&computed.negate());
+    let missing = difference.threshold(|_, w| if w > 0 { 1 } else { 0 });
+    let extra = difference.negate().threshold(|_, w| if w > 0 { 1 } else { 0 });
+
+    // every query present in either collection is reported, whether or not it has differences.
+    expected.concat(&computed).map(|(_,q)| (q, None))
+            .concat(&missing.map(|(x,q)| (q, Some((true, x)))))
+            .concat(&extra.map(|(x,q)| (q, Some((false, x)))))
+            .group_u(|_, s, t| {
+                let mut missing = Vec::new();
+                let mut extra = Vec::new();
+                for (entry, _) in s {
+                    match *entry {
+                        Some((true, ref x)) => missing.push(x.clone()),
+                        Some((false, ref x)) => extra.push(x.clone()),
+                        None => { },
+                    }
+                }
+                t.push(((missing.is_empty() && extra.is_empty(), missing, extra), 1));
+            })
+}
@@ -0,0 +1,337 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::algos;
+use explanation::generic;
+use explanation::diagnostics;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// `algos::bfs` explained, with edges as input 0 and roots as input 1.
+///
+/// Reports the required edges, then the required roots, and then the queries naming absent outputs as if they were
+/// required records of no node.
+struct Bfs;
+
+impl Dataflow for Bfs {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let (must, _, not_found) = generic::drive(scope, inputs, queries, |mut vars, queries, explanation_scope| {
+            let mut roots = vars.pop().unwrap();
+            let mut edges = vars.pop().unwrap();
+            let mut output = algos::bfs(&mut edges, &mut roots, explanation_scope);
+            output.seed(queries);
+            diagnostics::not_found(queries, &output.stream)
+        });
+        split(&must, 2, &not_found.map(|d| ((u32::max_value(), u32::max_value()), d.query())))
+    }
+}
+
+/// `algos::reachability` explained, with edges as input 0 and sources as input 1.
+///
+/// Reports the required edges, then the required sources, and then the queries naming absent outputs as if they
+/// were required records of no node.
+struct Reachability;
+
+impl Dataflow for Reachability {
+    type Data = (u32, u32);
+    type Query = (u64, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let (must, _, not_found) = generic::drive(scope, inputs, queries, |mut vars, queries, explanation_scope| {
+            let mut sources = vars.pop().unwrap();
+            let mut edges = vars.pop().unwrap();
+            let mut output = algos::reachability(&mut edges, &mut sources, explanation_scope);
+            output.seed(queries);
+            diagnostics::not_found(queries, &output.stream)
+        });
+        split(&must, 2, &not_found.map(|d| ((u32::max_value(), u32::max_value()), d.query())))
+    }
+}
+
+/// `algos::sssp` explained, with weighted edges `(src, (dst, weight))` as input 0 and sources `(node, (0, cost))`
+/// as input 1.
+///
+/// Reports the required edges, then the required sources, and then the queries naming absent outputs as if they were
+/// required records of no node.
+struct Sssp;
+
+impl Dataflow for Sssp {
+    type Data = (u32, (u32, u32));
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, (u32, u32)), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let (must, _, not_found) = generic::drive(scope, inputs, queries, |mut vars, queries, explanation_scope| {
+            let mut sources = vars.pop().unwrap().map_inverse(|(x,(_,c))| (x,c), |(x,c)| (x,(0,c)));
+            let mut edges = vars.pop().unwrap();
+            let mut output = algos::sssp(&mut edges, &mut sources, explanation_scope);
+            output.seed(queries);
+            diagnostics::not_found(queries, &output.stream)
+        });
+        split(&must, 2, &not_found.map(|d| ((u32::max_value(), (u32::max_value(), u32::max_value())), d.query())))
+    }
+}
+
+/// `algos::scc` explained, with edges as input 0 and sources as input 1.
+///
+/// Reports the required edges, then the required sources, and then the queries naming absent outputs as if they were
+/// required records of no node.
+struct Scc;
+
+impl Dataflow for Scc {
+    type Data = (u32, u32);
+    type Query = (u64, (u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let (must, _, not_found) = generic::drive(scope, inputs, queries, |mut vars, queries, explanation_scope| {
+            let mut sources = vars.pop().unwrap();
+            let mut edges = vars.pop().unwrap();
+            let mut output = algos::scc(&mut edges, &mut sources, explanation_scope);
+            output.seed(queries);
+            diagnostics::not_found(queries, &output.stream)
+        });
+        split(&must, 2, &not_found.map(|d| ((u32::max_value(), u32::max_value()), d.query())))
+    }
+}
+
+/// `algos::triangles` explained, with edges as input 0.
+///
+/// Reports the required edges, and then the queries naming absent outputs as if they were required edges of no node.
+struct Triangles;
+
+impl Dataflow for Triangles {
+    type Data = (u32, u32);
+    type Query = (u32, (u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let (must, _, not_found) = generic::drive(scope, inputs, queries, |mut vars, queries, _explanation_scope| {
+            let mut edges = vars.pop().unwrap();
+            let mut output = algos::triangles(&mut edges);
+            output.seed(queries);
+            diagnostics::not_found(queries, &output.stream)
+        });
+        split(&must, 1, &not_found.map(|d| ((u32::max_value(), u32::max_value()), d.query())))
+    }
+}
+
+/// `algos::maximal_independent_set` explained, with edges as input 0 and `(node, priority)` pairs as input 1.
+///
+/// Reports the required edges, then the required priorities, and then the queries naming absent outputs as if they
+/// were required records of no node.
+struct Mis;
+
+impl Dataflow for Mis {
+    type Data = (u32, u32);
+    type Query = (u32, (u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let (must, _, not_found) = generic::drive(scope, inputs, queries, |mut vars, queries, explanation_scope| {
+            let mut nodes = vars.pop().unwrap();
+            let mut edges = vars.pop().unwrap();
+            let mut output = algos::maximal_independent_set(&mut edges, &mut nodes, explanation_scope);
+            output.seed(queries);
+            diagnostics::not_found(queries, &output.stream)
+        });
+        split(&must, 2, &not_found.map(|d| ((u32::max_value(), u32::max_value()), d.query())))
+    }
+}
+
+/// `algos::top_contributors` explained by the largest contribution to each score, with edges `(src, (dst, share))`
+/// as input 0 and base scores `(node, (0, score))` as input 1.
+///
+/// Reports the required edges, then the required base scores, and then the queries naming absent outputs as if they
+/// were required records of no node.
+struct TopContributors;
+
+impl Dataflow for TopContributors {
+    type Data = (u32, (u32, u64));
+    type Query = (u32, u64, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, (u32, u64)), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let (must, _, not_found) = generic::drive(scope, inputs, queries, |mut vars, queries, explanation_scope| {
+            let mut base = vars.pop().unwrap().map_inverse(|(x,(_,b))| (x,b), |(x,b)| (x,(0,b)));
+            let mut edges = vars.pop().unwrap().map_inverse(|(x,(y,w))| (x,(y,w as u32)), |(x,(y,w))| (x,(y,w as u64)));
+            let mut output = algos::top_contributors(&mut edges, &mut base, 1, explanation_scope);
+            output.seed(queries);
+            diagnostics::not_found(queries, &output.stream)
+        });
+        split(&must, 2, &not_found.map(|d| ((u32::max_value(), (u32::max_value(), u64::max_value())), d.query())))
+    }
+}
+
+/// The required records of each of `inputs` inputs, in order, followed by `reports`.
+fn split<G: Scope, D: differential_dataflow::Data>(must: &Collection<G, (u32, D, u32)>, inputs: u32, reports: &Collection<G, (D, u32)>) -> Vec<Collection<G, (D, u32)>> {
+    let mut musts = (0 .. inputs).map(|input| must.filter(move |x| x.0 == input).map(|(_,x,q)| (x,q))).collect::<Vec<_>>();
+    musts.push(reports.clone());
+    musts
+}
+
+// Explores from root 0 along the edges 0 -> 1 -> 2 and 0 -> 3 -> 2, and asks about the root as query 1 and about
+// node 2, at distance two, as query 2. Checks that the root is explained by its own record alone, and that node 2
+// is explained by the root and the edges of one shortest path to it, the one through the lesser neighbor.
+#[test]
+fn bfs() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new().update(1, (0, 0), 1);
+    for &edge in [(0, 1), (1, 2), (0, 3), (3, 2)].iter() { load = load.update(0, edge, 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((0, 0, time, 1), 1).query((2, 2, time, 2), 1),
+    ];
+    let epochs = testing::run(Bfs, 2, script);
+
+    let expected = vec![vec![((0, 1), 2), ((1, 2), 2)], vec![((0, 0), 1), ((0, 0), 2)], vec![]];
+    if epochs[1] != expected {
+        panic!("bfs: expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("bfs:\tok");
+}
+
+// Explores from source 0 along the edges 0 -> 1 -> 2 and 0 -> 3 -> 2, and asks whether the source reaches node 2 as
+// query 1 and whether it reaches itself as query 2. Checks that the source reaching itself is explained by its own
+// record alone, and that it reaching node 2 is explained by the source and the edges of one path, the one through
+// the lesser neighbor.
+#[test]
+fn reachability() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new().update(1, (0, 0), 1);
+    for &edge in [(0, 1), (1, 2), (0, 3), (3, 2)].iter() { load = load.update(0, edge, 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((algos::reach_key(0, 2), 2, time, 1), 1).query((algos::reach_key(0, 0), 0, time, 2), 1),
+    ];
+    let epochs = testing::run(Reachability, 2, script);
+
+    let expected = vec![vec![((0, 1), 1), ((1, 2), 1)], vec![((0, 0), 1), ((0, 0), 2)], vec![]];
+    if epochs[1] != expected {
+        panic!("reachability: expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("reachability:\tok");
+}
+
+// Passes all of node 0's base score of 2^62 along the edge 0 -> 1, whose share of a million millionths scales it
+// beyond 64 bits, and asks about node 1's score as query 1. Checks that the scaled score saturates rather than
+// overflowing, and that node 1's score is explained by the edge and node 0's base score.
+#[test]
+fn top_contributors() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let score = 1u64 << 62;
+    let script = vec![
+        Epoch::new().update(0, (0, (1, 1000000)), 1).update(1, (0, (0, score)), 1),
+        Epoch::new().query((1, u64::max_value() / 1000000, time, 1), 1),
+    ];
+    let epochs = testing::run(TopContributors, 2, script);
+
+    let expected = vec![vec![((0, (1, 1000000)), 1)], vec![((0, (0, score)), 1)], vec![]];
+    if epochs[1] != expected {
+        panic!("top contributors: expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("top contributors:\tok");
+}
+
+// Starts from source 0 on the cycle 0 -> 1 -> 2 -> 0, with an edge 2 -> 3 leaving it, and asks whether node 2
+// shares the source's component, two steps out and one back, as query 1. Checks that node 2 is explained by the
+// source and exactly the cycle's three edges, and not by the edge leaving it.
+#[test]
+fn scc() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new().update(1, (0, 0), 1);
+    for &edge in [(0, 1), (1, 2), (2, 0), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((algos::reach_key(0, 2), (2, 1), time, 1), 1),
+    ];
+    let epochs = testing::run(Scc, 2, script);
+
+    let expected = vec![vec![((0, 1), 1), ((1, 2), 1), ((2, 0), 1)], vec![((0, 0), 1)], vec![]];
+    if epochs[1] != expected {
+        panic!("scc: expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("scc:\tok");
+}
+
+// Builds the triangles (1, 2, 3) and (2, 3, 4), which share the edge (2, 3), and asks about the first as query 1.
+// Checks that the triangle is explained by exactly its three edges, and not by the other triangle's.
+#[test]
+fn triangles() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(1, 2), (1, 3), (2, 3), (2, 4), (3, 4)].iter() { load = load.update(0, edge, 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((1, (2, 3), time, 1), 1),
+    ];
+    let epochs = testing::run(Triangles, 1, script);
+
+    let expected = vec![vec![((1, 2), 1), ((1, 3), 1), ((2, 3), 1)], vec![]];
+    if epochs[1] != expected {
+        panic!("triangles: expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("triangles:\tok");
+}
+
+// Starts from source 0 with cost zero, where node 1 is reached directly at cost 5 or through node 2 at cost 2, and
+// node 3 is reached from node 1; also starts from source 4 with cost one, whose edge to node 5 weighs as much as a
+// cost can. Asks about node 3 at cost 4 as query 1, about node 5 as query 2, and about source 4 as query 3. Checks
+// that node 3 is explained by the lighter path and its source, that the path to node 5, whose cost would overflow,
+// is not followed, and that a source is explained by its own record.
+#[test]
+fn sssp() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new().update(1, (0, (0, 0)), 1).update(1, (4, (0, 1)), 1);
+    for &edge in [(0, (1, 5)), (0, (2, 1)), (2, (1, 1)), (1, (3, 2)), (4, (5, u32::max_value()))].iter() {
+        load = load.update(0, edge, 1);
+    }
+    let script = vec![
+        load,
+        Epoch::new().query((3, 4, time, 1), 1).query((5, 0, time, 2), 1).query((4, 1, time, 3), 1),
+    ];
+    let epochs = testing::run(Sssp, 2, script);
+
+    let none = (u32::max_value(), (u32::max_value(), u32::max_value()));
+    let expected = vec![
+        vec![((0, (2, 1)), 1), ((1, (3, 2)), 1), ((2, (1, 1)), 1)],
+        vec![((0, (0, 0)), 1), ((4, (0, 1)), 3)],
+        vec![(none, 2)],
+    ];
+    if epochs[1] != expected {
+        panic!("sssp: expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("sssp:\tok");
+}
+
+// Decides the nodes 1 and 2, joined by an edge, with node 1 the first by priority, and asks why node 1 is in the set
+// as query 1 and why node 2 is excluded by node 1 as query 2. Checks that the member is explained by its own record
+// alone, and that the excluded node is explained by the edge to the member and the member's record, but not by its
+// own record, which lost.
+#[test]
+fn maximal_independent_set() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let script = vec![
+        Epoch::new().update(0, (1, 2), 1).update(1, (1, 1), 1).update(1, (2, 2), 1),
+        Epoch::new().query((1, (1, 1), time, 1), 1).query((2, (1, 1), time, 2), 1),
+    ];
+    let epochs = testing::run(Mis, 2, script);
+
+    let expected = vec![vec![((1, 2), 2)], vec![((1, 1), 1), ((1, 1), 2)], vec![]];
+    if epochs[1] != expected {
+        panic!("maximal independent set: expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("maximal independent set:\tok");
+}
@@ -0,0 +1,77 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::generic;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Paths `z -> x -> y` as `(x, (y, z))`, and pairs of edges `x -> y`, `x -> z` as the same, by two joins of the
+/// edges, either both through one arrangement of the edges or each with its own indices.
+///
+/// Reports the required edges, and diagnostics for queries naming absent outputs, as if they were required edges
+/// of no node.
+struct TwoJoins {
+    arranged: bool,
+}
+
+impl Dataflow for TwoJoins {
+    type Data = (u32, u32);
+    type Query = (u32, (u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let arranged = self.arranged;
+        let (must, _, diagnostics) = generic::explain(scope, &inputs[0], queries, move |edges| {
+            let mut reversed = edges.map_inverse(|(x,y)| (y,x), |(y,x)| (x,y));
+            let mut copy = edges.map_inverse(|x| x, |x| x);
+            if arranged {
+                let mut edges = edges.arrange_by_key();
+                let mut paths = edges.join(&mut reversed);
+                let mut pairs = edges.join(&mut copy);
+                paths.concat(&mut pairs)
+            }
+            else {
+                let mut paths = edges.join(&mut reversed);
+                let mut pairs = edges.join(&mut copy);
+                paths.concat(&mut pairs)
+            }
+        });
+        vec![must, diagnostics.map(|d| ((u32::max_value(), u32::max_value()), d.query()))]
+    }
+}
+
+// Asks about a path and a pair of edges, through two joins sharing an arrangement and through two plain joins, and
+// checks that both require the same edges, before and after one of the edges asked about is removed.
+#[test]
+fn arranged() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let script = || {
+        let mut load = Epoch::new();
+        for &edge in [(0, 1), (1, 2), (2, 0), (1, 3)].iter() { load = load.update(0, edge, 1); }
+        vec![
+            load,
+            Epoch::new().query((1, (2, 0), time, 1), 1).query((1, (2, 3), time, 2), 1),
+            Epoch::new().update(0, (1, 3), -1),
+        ]
+    };
+    let shared = testing::run(TwoJoins { arranged: true }, 1, script());
+    let plain = testing::run(TwoJoins { arranged: false }, 1, script());
+
+    let expected = vec![((0, 1), 1), ((1, 2), 1), ((1, 2), 2), ((1, 3), 2)];
+    if plain[1][0] != expected {
+        panic!("plain joins: expected {:?}, but required {:?}", expected, plain[1][0]);
+    }
+    for epoch in 1 .. 3 {
+        if shared[epoch] != plain[epoch] {
+            panic!("epoch {}: shared joins reported {:?}, but plain joins {:?}", epoch, shared[epoch], plain[epoch]);
+        }
+    }
+    println!("shared arrangement:\tok");
+}
@@ -0,0 +1,57 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::cc;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Connected components with graph, label, and expected graph requirement inputs, in that order, reporting the
+/// verdicts of `assert_explains` on the graph requirements of each query.
+///
+/// Expected requirements are all for the query about node 3, identified by 3.
+struct Asserted;
+
+impl Dataflow for Asserted {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = (u32, (bool, Vec<(u32, u32)>, Vec<(u32, u32)>));
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let must = cc::Instrumented.build(scope, &inputs[0..2], queries);
+        vec![testing::assert_explains(&inputs[2].map(|x| (x, 3)), &must[0])]
+    }
+}
+
+// Checks `assert_explains` against connected components on a path, first with the correct expected explanation
+// for a query and then with one whose edges are wrong in both directions, which should fail and report each.
+#[test]
+fn assert() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() {
+        load = load.update(0, edge, 1).update(2, edge, 1);
+    }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    let script = vec![
+        load.query((3, 0, time, 3), 1),
+        Epoch::new().update(2, (2, 3), -1).update(2, (0, 3), 1),
+    ];
+    let epochs = testing::run(Asserted, 3, script);
+
+    let pass = vec![vec![(3, (true, vec![], vec![]))]];
+    let fail = vec![vec![(3, (false, vec![(0, 3)], vec![(2, 3)]))]];
+    if epochs[0] == pass && epochs[1] == fail {
+        println!("assert_explains:\tok");
+    }
+    else {
+        panic!("assert_explains: expected {:?} and then {:?}, but reported {:?} and then {:?}", pass, fail, epochs[0], epochs[1]);
+    }
+}
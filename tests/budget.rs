@@ -0,0 +1,86 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::cc;
+use explanation::budget::Budget;
+use explanation::diagnostics::Diagnostic;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Connected components, with query 7 limited to two records of each input and other queries unlimited.
+///
+/// The third must-set marks each truncated query `q` as `((0, 0), q)`.
+struct Budgeted;
+
+impl Dataflow for Budgeted {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, ((u32, u32), u32)>> {
+
+        let (prior_graph_handle, prior_graph) = scope.loop_variable(u32::max_value(), 1);
+        let (prior_label_handle, prior_label) = scope.loop_variable(u32::max_value(), 1);
+        let prior_graph = Collection::new(prior_graph);
+        let prior_label = Collection::new(prior_label);
+
+        let (graph_must, label_must, _, diagnostics, _) = cc::explain_budgeted(scope, &inputs[0], &inputs[1], queries, &prior_graph, &prior_label, |q| {
+            if q == 7 { Budget { records: 2, rounds: Round::max_value() } } else { Budget::unlimited() }
+        });
+        graph_must.inner.connect_loop(prior_graph_handle);
+        label_must.inner.connect_loop(prior_label_handle);
+
+        let truncated = diagnostics.flat_map(|d| match d { Diagnostic::Truncated(q) => Some(((0, 0), q)), _ => None });
+        vec![graph_must, label_must, truncated]
+    }
+}
+
+// Explains connected components of a path with one query under a budget and one without, and checks that the
+// budgeted query receives at most its budget of records, all drawn from its full explanation, and is marked as
+// truncated, while the unlimited query is explained in full and not marked.
+#[test]
+fn budget() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3), (3, 4)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 5 { load = load.update(1, (node, node), 1); }
+    let script = vec![
+        load.query((4, 0, time, 7), 1).query((1, 0, time, 8), 1),
+    ];
+    let epochs = testing::run(Budgeted, 2, script);
+
+    let mut failures = 0;
+
+    let full = [(0, 1), (1, 2), (2, 3), (3, 4)];
+    let graph = epochs[0][0].iter().filter(|x| x.1 == 7).map(|x| x.0).collect::<Vec<_>>();
+    let labels = epochs[0][1].iter().filter(|x| x.1 == 7).map(|x| x.0).collect::<Vec<_>>();
+    if graph.len() <= 2 && labels.len() <= 2 && graph.iter().all(|x| full.contains(x)) && labels.iter().all(|x| *x == (0, 0)) && epochs[0][2] == vec![((0, 0), 7)] {
+        println!("budgeted query:\tok");
+    }
+    else {
+        println!("budgeted query:\texpected at most two of {:?} and truncation, but required {:?} and {:?}, truncated {:?}", full, graph, labels, epochs[0][2]);
+        failures += 1;
+    }
+
+    let graph = epochs[0][0].iter().filter(|x| x.1 == 8).cloned().collect::<Vec<_>>();
+    let labels = epochs[0][1].iter().filter(|x| x.1 == 8).cloned().collect::<Vec<_>>();
+    if graph == vec![((0, 1), 8)] && labels == vec![((0, 0), 8)] {
+        println!("unlimited query:\tok");
+    }
+    else {
+        println!("unlimited query:\texpected [((0, 1), 8)] and [((0, 0), 8)], but required {:?} and {:?}", graph, labels);
+        failures += 1;
+    }
+
+    if failures > 0 {
+        panic!("{} of 2 cases failed", failures);
+    }
+}
@@ -0,0 +1,49 @@
+extern crate explanation;
+extern crate timely;
+
+use std::collections::HashSet;
+
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use explanation::Round;
+use explanation::cc;
+use explanation::cache::Cache;
+use explanation::testing::{self, Epoch};
+
+// Explains the far end of a path once, caches its must-set, and checks that re-asking after adding an unrelated
+// edge is answered from the cache, while re-asking after deleting a required edge is not.
+#[test]
+fn cache() {
+
+    let query = (3, 0);
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut graph = vec![(0, 1), (1, 2), (2, 3), (4, 5)].into_iter().collect::<HashSet<_>>();
+    let label = (0 .. 6).map(|x| (x, x)).collect::<HashSet<_>>();
+
+    let mut load = Epoch::new();
+    for &edge in graph.iter() { load = load.update(0, edge, 1); }
+    for &node in label.iter() { load = load.update(1, node, 1); }
+    let script = vec![load, Epoch::new().query((query.0, query.1, time, 0), 1)];
+    let musts = testing::run(cc::Instrumented, 2, script).pop().unwrap();
+
+    let mut cache = Cache::new();
+    cache.insert(query, 1, musts.iter().map(|x| x.iter().map(|y| y.0).collect()).collect());
+
+    // an unrelated edge leaves every cached record in place.
+    graph.insert((3, 4));
+    let cached = cache.get(&query, 2, |input, x| if input == 0 { graph.contains(x) } else { label.contains(x) }).map(|x| x.to_vec());
+    let expected = vec![vec![(0, 1), (1, 2), (2, 3)], vec![(0, 0)]];
+    if cached != Some(expected.clone()) {
+        panic!("unrelated edge: expected {:?} from the cache, but found {:?}", expected, cached);
+    }
+    println!("unrelated edge:\tok");
+
+    // deleting a required edge invalidates the cached must-set.
+    graph.remove(&(1, 2));
+    let cached = cache.get(&query, 3, |input, x| if input == 0 { graph.contains(x) } else { label.contains(x) }).map(|x| x.to_vec());
+    if cached.is_some() || !cache.is_empty() {
+        panic!("required edge deleted: expected nothing from the cache, but found {:?}", cached);
+    }
+    println!("required edge deleted:\tok");
+}
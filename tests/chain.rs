@@ -0,0 +1,78 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::{Round, stitch};
+use explanation::cc;
+use explanation::generic;
+use explanation::tenants::Namespaces;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Connected components labels, indexed downstream by label, with downstream queries explained through both.
+///
+/// Reports the downstream requirements on the labels, the graph and label inputs they in turn require, and then the
+/// downstream queries too large to forward as if they were required labels of no node.
+struct Chained;
+
+impl Dataflow for Chained {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, ((u32, u32), u32)>> {
+        let labels = cc::components(&inputs[0], &inputs[1]);
+        let (downstream, _, _) = generic::explain(scope, &labels, queries, |x| x.map_inverse(|(n,l)| (l,n), |(l,n)| (n,l)));
+        let (forwarded, unforwarded) = stitch(&downstream, queries, Namespaces::new(16), 1);
+        let mut upstream = cc::Instrumented.build(scope, inputs, &forwarded);
+        let label = upstream.pop().unwrap();
+        let graph = upstream.pop().unwrap();
+        vec![downstream, graph, label, unforwarded.map(|q| ((u32::max_value(), u32::max_value()), q))]
+    }
+}
+
+// Asks the downstream index which nodes have label 0, naming node 3, and checks that the query is explained by
+// the label of node 3, and through it by the path from node 0 and node 0's label, reported upstream under the
+// downstream query's identifier in the namespace of the link.
+#[test]
+fn chain() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    let script = vec![load, Epoch::new().query((0, 3, time, 7), 1)];
+    let epochs = testing::run(Chained, 2, script);
+
+    let id = Namespaces::new(16).id(1, 7);
+    let expected = vec![vec![((3, 0), 7)], vec![((0, 1), id), ((1, 2), id), ((2, 3), id)], vec![((0, 0), id)], vec![]];
+    if epochs[1] != expected {
+        panic!("chained: expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("chained:\tok");
+}
+
+// Asks the downstream index about node 3 under an identifier too large for the link's namespace of 16 bits. Checks
+// that the query is still explained downstream, and reported rather than forwarded upstream, where it has no
+// identifier to be explained under.
+#[test]
+fn unforwarded() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    let script = vec![load, Epoch::new().query((0, 3, time, 1 << 16), 1)];
+    let epochs = testing::run(Chained, 2, script);
+
+    let none = (u32::max_value(), u32::max_value());
+    let expected = vec![vec![((3, 0), 1 << 16)], vec![], vec![], vec![(none, 1 << 16)]];
+    if epochs[1] != expected {
+        panic!("unforwarded: expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("unforwarded:\tok");
+}
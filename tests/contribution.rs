@@ -0,0 +1,55 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+
+use explanation::Round;
+use explanation::sql::{self, Query, Table};
+use explanation::summary;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// A SQL query summing the second column of its one table, reporting the contribution of each required row.
+struct Contribution(Query, Vec<Table>);
+
+impl Dataflow for Contribution {
+    type Data = Vec<u32>;
+    type Query = (Vec<u32>, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((Vec<u32>, u32), i64);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Vec<u32>>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let (must, _, _) = sql::explain(scope, &self.0, &self.1, inputs, queries);
+        let must = must.map(|(_table, row, q)| (row, q));
+        vec![summary::contributions(&must, &inputs[0], |row| row[1] as i64)]
+    }
+}
+
+// Explains a grouped sum whose group holds a row with two copies, and checks that each required row is reported
+// with its value times its copies, so that the contributions add up to the queried sum.
+#[test]
+fn contribution() {
+
+    let tables = vec![Table::new("edges", &["src", "dst"])];
+    let query = Query::parse("SELECT a.src, SUM(a.dst) FROM edges a GROUP BY a.src", &tables).unwrap();
+    assert_eq!(query.columns(), vec!["a.src".to_owned(), "sum(a.dst)".to_owned()]);
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let load = Epoch::new().update(0, vec![0, 1], 1)
+                           .update(0, vec![0, 2], 2)
+                           .update(0, vec![1, 5], 1);
+    let script = vec![
+        load,
+        Epoch::new().query((vec![0, 5], time, 7), 1),
+    ];
+    let epochs = testing::run(Contribution(query, tables), 1, script);
+
+    let expected = vec![vec![((vec![0, 1], 7), 1), ((vec![0, 2], 7), 4)]];
+    if epochs[1] != expected {
+        panic!("(0, 5): expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("(0, 5):\tok");
+}
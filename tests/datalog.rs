@@ -0,0 +1,70 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::datalog::{self, Program};
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// A Datalog program as a `testing::Dataflow`, with its inputs in order of declaration.
+struct Datalog(Program);
+
+impl Dataflow for Datalog {
+    type Data = (u32, u32);
+    type Query = (u32, (u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = (u32, (u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        vec![datalog::explain(scope, &self.0, inputs, queries).0]
+    }
+}
+
+// Checks that malformed programs are rejected with a reason, and that transitive closure explains a path fact by
+// the edges of one path. Rules are tried in order among equal derivations, so a fact with a direct edge is
+// explained by that edge rather than by a longer path.
+#[test]
+fn datalog() {
+
+    let malformed = vec![
+        "input edge. path(x, y) :- edge(x, y)",
+        "input edge. path(x, x) :- edge(x, y).",
+        "input edge. path(x, z) :- edge(x, y), edge(z, w).",
+        "input edge. path(x, y) :- nothing(x, y).",
+        "input edge. edge(x, y) :- edge(y, x).",
+        "input edge. path(x, y, z) :- edge(x, y).",
+        "input edge.",
+    ];
+    for text in malformed.iter() {
+        match Program::parse(text) {
+            Ok(program) => panic!("accepted {:?} as {:?}", text, program),
+            Err(reason) => println!("rejected:\t{}", reason),
+        }
+    }
+
+    let program = Program::parse("
+        input edge.
+        % paths are edges, and paths extended by edges.
+        path(x, y) :- edge(x, y).
+        path(x, z) :- path(x, y), edge(y, z).
+    ").unwrap();
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3), (0, 2)].iter() { load = load.update(0, edge, 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((0, (0, 3), time, 7), 1),
+    ];
+    let epochs = testing::run(Datalog(program), 1, script);
+
+    let expected = vec![vec![(0, (0, 2), 7), (0, (2, 3), 7)]];
+    if epochs[1] != expected {
+        panic!("path(0, 3): expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("path(0, 3):\tok");
+}
@@ -0,0 +1,62 @@
+extern crate explanation;
+extern crate timely;
+
+use std::sync::Mutex;
+use std::sync::mpsc::channel;
+
+use explanation::interactive::{self, CcCommand, Event};
+
+// Runs the same connected components session several times, with one through four workers, and checks that
+// each run reports exactly the same events in exactly the same order, other than timings. Only meaningful with
+// the `deterministic` feature, without which event order is left to the whims of scheduling.
+#[test]
+#[cfg_attr(not(feature = "deterministic"), ignore)]
+fn deterministic() {
+
+    let runs: usize = 3;
+
+    // a cycle with a tail, queried at a few nodes, with an edge removed to reroute one explanation.
+    let script = vec![
+        "graph + 0 1", "graph + 1 2", "graph + 2 3", "graph + 3 0", "graph + 3 4", "graph + 4 5",
+        "label + 0 0", "label + 1 1", "label + 2 2", "label + 3 3", "label + 4 4", "label + 5 5",
+        "query + 2 0 5 0 4 0", "graph - 3 0", "query - 4 0", "graph + 5 2",
+    ];
+    let commands = script.iter().map(|x| CcCommand::parse(x).unwrap().unwrap()).collect::<Vec<_>>();
+
+    let expected = session(1, &commands);
+    for run in 0 .. runs {
+        for workers in 1 .. 5 {
+            let observed = session(workers, &commands);
+            if observed != expected {
+                panic!("run {} with {} workers reported\n{:?}\nbut the first run reported\n{:?}", run, workers, observed, expected);
+            }
+        }
+    }
+    println!("{} events, identical across {} runs of 1-4 workers", expected.len(), runs);
+}
+
+/// Runs a session of `commands` with `workers` workers, returning the events reported other than timings.
+fn session(workers: usize, commands: &[CcCommand]) -> Vec<Event<((u32, u32), u32)>> {
+
+    // worker zero receives the commands; the others step along with it.
+    let (event_send, event_recv) = channel();
+    let mut channels = Vec::new();
+    for index in 0 .. workers {
+        let (command_send, command_recv) = channel();
+        for command in commands.iter() {
+            command_send.send(if index == 0 { command.clone() } else { CcCommand::Step }).unwrap();
+        }
+        channels.push(Mutex::new(Some((command_recv, event_send.clone()))));
+    }
+    drop(event_send);
+
+    timely::execute(timely::Configuration::Process(workers), move |root| {
+        let (commands, events) = channels[root.index()].lock().unwrap().take().unwrap();
+        interactive::cc(root, 4, vec![], vec![], commands, events);
+    }).unwrap();
+
+    event_recv.iter().filter(|x| match *x {
+        Event::Initialized(_) | Event::Round(_, _) => false,
+        _ => true,
+    }).collect()
+}
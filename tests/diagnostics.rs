@@ -0,0 +1,150 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+
+use explanation::Round;
+use explanation::generic;
+use explanation::diagnostics::{self, Diagnostic};
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// A copy of its input, explained by `generic::explain`.
+///
+/// Reports the required records, and then each query reported as not found as if it were a required record of
+/// no key.
+struct Copied;
+
+impl Dataflow for Copied {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, ((u32, u32), u32)>> {
+        let none = (u32::max_value(), u32::max_value());
+        let (must, _, diagnostics) = generic::explain(scope, &inputs[0], queries, |x| x.map_inverse(|x| x, |x| x));
+        let not_found = diagnostics.flat_map(move |d| match d { Diagnostic::QueryNotFound(q) => Some((none, q)), _ => None });
+        vec![must, not_found]
+    }
+}
+
+/// Queries about times of the input stream, checked by `before_data` against its input.
+///
+/// Reports each query reported as before any data as if it were a required record of no key.
+struct BeforeData;
+
+impl Dataflow for BeforeData {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<RootTimestamp, u32>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, _scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, ((u32, u32), u32)>> {
+        let none = (u32::max_value(), u32::max_value());
+        vec![diagnostics::before_data(queries, &inputs[0]).map(move |d| (none, d.query()))]
+    }
+}
+
+/// Requirements `(record, query)` entered into a correction scope at its first round, checked by `exhausted`
+/// against a round limit of `limit`.
+///
+/// Reports each query reported as exhausted as if it were a required record of no key.
+struct Exhausting {
+    limit: Round,
+}
+
+impl Dataflow for Exhausting {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, ((u32, u32), u32)>> {
+        let none = (u32::max_value(), u32::max_value());
+        let limit = self.limit;
+        let must = inputs[0].clone();
+        let live = queries.map(|(_,_,_,q)| q).threshold(|_, w| if w > 0 { 1 } else { 0 });
+        let exhausted = scope.scoped::<Round,_,_>(move |correction| {
+            diagnostics::exhausted(&must.enter(correction), &live.enter(correction), limit, |x| x.1).leave()
+        });
+        vec![exhausted.map(move |d| (none, d.query()))]
+    }
+}
+
+// Asks about records (2, 4) and (1, 3) as of the first epoch, as queries 1 and 2, then adds (2, 4) and asks about
+// it over the whole history as query 3. Checks that query 1 is reported as not found, and remains so once (2, 4)
+// is added, as the record was absent at the time it asks about.
+#[test]
+fn not_found() {
+
+    let then = Product::new(RootTimestamp::new(0), Round::max_value());
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let none = (u32::max_value(), u32::max_value());
+    let script = vec![
+        Epoch::new().update(0, (1, 3), 1),
+        Epoch::new().query((2, 4, then, 1), 1).query((1, 3, then, 2), 1),
+        Epoch::new().update(0, (2, 4), 1).query((2, 4, time, 3), 1),
+    ];
+    let epochs = testing::run(Copied, 1, script);
+
+    let expected = vec![vec![((1, 3), 2)], vec![(none, 1)]];
+    if epochs[1] != expected {
+        panic!("absent: expected {:?}, but found {:?}", expected, epochs[1]);
+    }
+    println!("absent:\tok");
+
+    let expected = vec![vec![((1, 3), 2), ((2, 4), 3)], vec![(none, 1)]];
+    if epochs[2] != expected {
+        panic!("added later: expected {:?}, but found {:?}", expected, epochs[2]);
+    }
+    println!("added later:\tok");
+}
+
+// Requires a record for each of queries 1 and 2 in the first round of correction, and withdraws query 1 in the
+// following epoch. Checks that with a round limit of zero both queries are reported as exhausted, and query 1's
+// report retracted once it is withdrawn, while a limit of one, which the records never reach, reports nothing.
+#[test]
+fn exhausted() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let none = (u32::max_value(), u32::max_value());
+    let script = vec![
+        Epoch::new().update(0, (5, 1), 1).update(0, (6, 2), 1).query((0, 0, time, 1), 1).query((0, 0, time, 2), 1),
+        Epoch::new().query((0, 0, time, 1), -1),
+    ];
+
+    let epochs = testing::run(Exhausting { limit: 0 }, 1, script.clone());
+    let expected = vec![vec![vec![(none, 1), (none, 2)]], vec![vec![(none, 2)]]];
+    if epochs != expected {
+        panic!("exhausted: expected {:?}, but found {:?}", expected, epochs);
+    }
+    println!("exhausted:\tok");
+
+    let epochs = testing::run(Exhausting { limit: 1 }, 1, script);
+    let expected = vec![vec![vec![]], vec![vec![]]];
+    if epochs != expected {
+        panic!("within limit: expected {:?}, but found {:?}", expected, epochs);
+    }
+    println!("within limit:\tok");
+}
+
+// Asks about the first epoch, before any data, as query 1 and about the whole history as query 2, then adds a record
+// in the next epoch, and withdraws query 1 in the one after. Checks that only query 1 is reported, as data may yet
+// arrive for query 2, and that withdrawing query 1 retracts its report though data has since arrived.
+#[test]
+fn before_data() {
+
+    let none = (u32::max_value(), u32::max_value());
+    let script = vec![
+        Epoch::new().query((0, 0, RootTimestamp::new(0), 1), 1).query((0, 0, RootTimestamp::new(u32::max_value()), 2), 1),
+        Epoch::new().update(0, (1, 3), 1),
+        Epoch::new().query((0, 0, RootTimestamp::new(0), 1), -1),
+    ];
+    let epochs = testing::run(BeforeData, 1, script);
+
+    let expected = vec![vec![vec![(none, 1)]], vec![vec![(none, 1)]], vec![vec![]]];
+    if epochs != expected {
+        panic!("before data: expected {:?}, but found {:?}", expected, epochs);
+    }
+    println!("before data:\tok");
+}
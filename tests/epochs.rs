@@ -0,0 +1,96 @@
+extern crate rand;
+extern crate explanation;
+extern crate timely;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use explanation::Round;
+use explanation::cc;
+use explanation::testing::{self, Epoch};
+use explanation::bench::random_graph;
+
+// Explains connected components over scripts of epochs that interleave edge updates with queries being asked
+// and withdrawn, and checks that introducing epochs before earlier ones complete does not change what any epoch
+// requires. Each script is run once with every epoch stepped to completion before the next, and then with the
+// next epoch introduced after zero, one, and a few steps, leaving several epochs in flight at once. The
+// requirements at each epoch must depend only on the updates and queries up to that epoch.
+#[test]
+fn epochs() {
+
+    let trials: usize = 10;
+    let nodes: u32 = 8;
+    let edges: usize = 10;
+    let epochs: usize = 6;
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+
+    for seed in 0 .. trials {
+
+        let seed_slice: &[_] = &[seed, 7, 8, 9];
+        let mut rng: StdRng = SeedableRng::from_seed(seed_slice);
+
+        // load a random graph, labeling each node by itself.
+        let mut current = random_graph(nodes, edges, seed);
+        let mut load = Epoch::new();
+        for &edge in current.iter() { load = load.update(0, edge, 1); }
+        for node in 0 .. nodes { load = load.update(1, (node, node), 1); }
+        let mut script = vec![load];
+
+        // each epoch updates an edge, and asks or withdraws a query about a random node's current label.
+        let mut asked = Vec::new();
+        for _ in 0 .. epochs {
+            let mut epoch = Epoch::new();
+            if rng.gen() && current.len() > 0 {
+                let edge = current.swap_remove(rng.gen_range(0, current.len()));
+                epoch = epoch.update(0, edge, -1);
+            }
+            else {
+                let edge = (rng.gen_range(0, nodes), rng.gen_range(0, nodes));
+                current.push(edge);
+                epoch = epoch.update(0, edge, 1);
+            }
+            if rng.gen() && asked.len() > 0 {
+                let (node, value) = asked.swap_remove(rng.gen_range(0, asked.len()));
+                epoch = epoch.query((node, value, time, node), -1);
+            }
+            else {
+                let node = rng.gen_range(0, nodes);
+                if !asked.iter().any(|x: &(u32, u32)| x.0 == node) {
+                    let value = least_reachable(node, &current);
+                    asked.push((node, value));
+                    epoch = epoch.query((node, value, time, node), 1);
+                }
+            }
+            script.push(epoch);
+        }
+
+        let expected = testing::run(cc::Instrumented, 2, script.clone());
+        for &steps in [0, 1, 5].iter() {
+            let observed = testing::run_overlapped(cc::Instrumented, 2, script.clone(), steps);
+            for epoch in 0 .. expected.len() {
+                if observed[epoch] != expected[epoch] {
+                    panic!("seed {}: with {} steps between epochs, epoch {} required\n{:?}\nbut stepping to completion required\n{:?}\nscript: {:?}",
+                           seed, steps, epoch, observed[epoch], expected[epoch], script);
+                }
+            }
+        }
+        println!("seed {}:\t{} epochs, identical when overlapped", seed, script.len());
+    }
+}
+
+/// The least node reachable from `node` along the symmetrized `edges`, which is the label it ends up with.
+fn least_reachable(node: u32, edges: &[(u32, u32)]) -> u32 {
+    let mut reached = vec![node];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(x, y) in edges.iter() {
+            if reached.contains(&x) && !reached.contains(&y) { reached.push(y); changed = true; }
+            if reached.contains(&y) && !reached.contains(&x) { reached.push(x); changed = true; }
+        }
+    }
+    *reached.iter().min().unwrap()
+}
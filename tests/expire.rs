@@ -0,0 +1,62 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+
+use explanation::{Round, expire};
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Queries passed through `expire` with a time to live of two epochs, and their negation, which holds any query
+/// the expiry made negative.
+struct Expiring;
+
+impl Dataflow for Expiring {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = Self::Query;
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, _scope: &mut G, _inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Query>> {
+        let expired = expire(queries, |_| 2);
+        vec![expired.clone(), expired.negate()]
+    }
+}
+
+// Asks one query and withdraws it before its time to live lapses, and asks another, lets it expire, asks it
+// again, and withdraws it once it has expired a second time. Each query is present exactly while asked and not
+// expired, and neither is ever negative.
+#[test]
+fn expire_queries() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let early = (1, 0, time, 1);
+    let late = (2, 0, time, 2);
+    let script = vec![
+        Epoch::new().query(early, 1).query(late, 1),
+        Epoch::new().query(early, -1),
+        Epoch::new(),
+        Epoch::new().query(late, 1),
+        Epoch::new(),
+        Epoch::new(),
+        Epoch::new().query(late, -1).query(late, -1),
+        Epoch::new(),
+    ];
+    let epochs = testing::run(Expiring, 0, script);
+
+    let expected = vec![
+        vec![early, late], vec![late], vec![], vec![late], vec![late], vec![], vec![], vec![],
+    ];
+    for (epoch, expected) in expected.into_iter().enumerate() {
+        if epochs[epoch][0] != expected {
+            panic!("epoch {}: expected {:?}, but found {:?}", epoch, expected, epochs[epoch][0]);
+        }
+        if !epochs[epoch][1].is_empty() {
+            panic!("epoch {}: negative queries {:?}", epoch, epochs[epoch][1]);
+        }
+    }
+    println!("expire:\tok");
+}
@@ -0,0 +1,96 @@
+extern crate rand;
+extern crate explanation;
+extern crate timely;
+
+use std::sync::mpsc::channel;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use explanation::interactive::{self, CcCommand, StableCommand};
+
+// fragments from which structured lines are assembled: well-formed words, near misses, and junk.
+const WORDS: &'static [&'static str] = &[
+    "graph", "label", "prefs", "query", "query@", "query@3", "query@0", "query@4294967295", "query@-1", "query@x",
+    "+", "-", "--", "+-", "0", "1", "2", "3", "7", "4294967295", "4294967296", "-1", "1.5", "0x10", "",
+    "graph+", "@", "é", "\u{0}", "\t", "  ",
+];
+
+// Feeds random lines to the interactive command parsers, and checks that no line panics them. Half the lines are
+// assembled from words the parsers know, near misses, and junk, so that most reach deep into the parsers; the
+// others are random characters. Lines parsed as commands are then applied to short connected components and stable
+// matching sessions, which must run to completion, so that no command a parser produces panics the worker loop.
+// Lines follow from the seed, so that a failing run can be replayed.
+#[test]
+fn fuzz() {
+
+    let lines: usize = 10000;
+    let seed: usize = 0;
+
+    let seed_slice: &[_] = &[seed, 1, 9, 0];
+    let mut rng: StdRng = SeedableRng::from_seed(seed_slice);
+
+    let mut cc_commands = Vec::new();
+    let mut stable_commands = Vec::new();
+    for _ in 0 .. lines {
+        let line = if rng.gen() { structured(&mut rng) } else { unstructured(&mut rng) };
+
+        let parsed = CcCommand::parse(&line);
+        if parsed == Ok(None) && line.split_whitespace().next().is_some() {
+            panic!("cc: parsed {:?} as {:?}", line, parsed);
+        }
+        if let Ok(Some(command)) = parsed { cc_commands.push(command); }
+
+        let parsed = StableCommand::parse(&line);
+        if parsed.is_some() != line.split_whitespace().next().is_some() {
+            panic!("stable: parsed {:?} as {:?}", line, parsed);
+        }
+        if let Some(command) = parsed { stable_commands.push(command); }
+    }
+    println!("parsers:\t{} lines ok", lines);
+
+    // sessions step once per command, so only the first few hundred commands are applied.
+    cc_commands.truncate(200);
+    stable_commands.truncate(200);
+
+    let (command_send, command_recv) = channel();
+    let (event_send, event_recv) = channel();
+    for command in cc_commands.iter().cloned() { command_send.send(command).unwrap(); }
+    drop(command_send);
+    let sessions = ::std::sync::Mutex::new(Some((command_recv, event_send)));
+    timely::execute(timely::Configuration::Thread, move |root| {
+        let (commands, events) = sessions.lock().unwrap().take().unwrap();
+        interactive::cc(root, 2, vec![], vec![], commands, events);
+    }).unwrap();
+    println!("cc session:\t{} commands, {} events ok", cc_commands.len(), event_recv.iter().count());
+
+    let (command_send, command_recv) = channel();
+    let (event_send, event_recv) = channel();
+    for command in stable_commands.iter().cloned() { command_send.send(command).unwrap(); }
+    drop(command_send);
+    let sessions = ::std::sync::Mutex::new(Some((command_recv, event_send)));
+    timely::execute(timely::Configuration::Thread, move |root| {
+        let (commands, events) = sessions.lock().unwrap().take().unwrap();
+        interactive::stable(root, vec![], commands, events);
+    }).unwrap();
+    println!("stable session:\t{} commands, {} events ok", stable_commands.len(), event_recv.iter().count());
+}
+
+/// A line of up to eight words, separated by runs of whitespace, with small numbers more likely than large ones.
+fn structured(rng: &mut StdRng) -> String {
+    let mut line = String::new();
+    for _ in 0 .. rng.gen_range(0, 9) {
+        if rng.gen_range(0, 4) == 0 {
+            line.push_str(&rng.gen_range(0u32, 8).to_string());
+        }
+        else {
+            line.push_str(rng.choose(WORDS).unwrap());
+        }
+        line.push_str(if rng.gen() { " " } else { " \t " });
+    }
+    line
+}
+
+/// A line of up to forty random characters, mostly ASCII.
+fn unstructured(rng: &mut StdRng) -> String {
+    (0 .. rng.gen_range(0, 41)).map(|_| if rng.gen_range(0, 8) == 0 { rng.gen::<char>() } else { rng.gen_range(0u8, 128) as char }).collect()
+}
@@ -0,0 +1,90 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::{Round, serve_hot, fan_out};
+use explanation::cc;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Connected components explained, with input 2 naming hot `(node, label)` outputs, each under the identifier
+/// `1 << 16` plus its node, and if `twice` also under `2 << 16` plus its node.
+struct Hot {
+    twice: bool,
+}
+
+impl Dataflow for Hot {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, ((u32, u32), u32)>> {
+        let twice = self.twice;
+        let hot = inputs[2].flat_map(move |(node, label)| {
+            let mut ids = vec![(node, label, (1 << 16) + node)];
+            if twice { ids.push((node, label, (2 << 16) + node)); }
+            ids
+        });
+        let (queries, aliases) = serve_hot(queries, &hot);
+        cc::Instrumented.build(scope, &inputs[0 .. 2], &queries).iter().map(|x| fan_out(x, &aliases)).collect()
+    }
+}
+
+// Marks the label of the far end of a path as hot, and checks that a query about it, and one about another node,
+// are each reported their own explanations, with the hot output's maintained requirements reported only for the
+// query it serves.
+#[test]
+fn hot() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    load = load.update(2, (3, 0), 1);
+    let script = vec![
+        load,
+        Epoch::new(),
+        Epoch::new().query((3, 0, time, 7), 1).query((1, 0, time, 8), 1),
+    ];
+    let epochs = testing::run(Hot { twice: false }, 3, script);
+
+    let expected = vec![vec![], vec![]];
+    if epochs[1] != expected {
+        panic!("hot, unasked: expected {:?}, but reported {:?}", expected, epochs[1]);
+    }
+    println!("hot, unasked:\tok");
+
+    let expected = vec![vec![((0, 1), 7), ((0, 1), 8), ((1, 2), 7), ((2, 3), 7)], vec![((0, 0), 7), ((0, 0), 8)]];
+    if epochs[2] != expected {
+        panic!("hot and cold: expected {:?}, but reported {:?}", expected, epochs[2]);
+    }
+    println!("hot and cold:\tok");
+}
+
+// Marks the label of the far end of a path as hot under two identifiers, and checks that the query it serves is
+// withdrawn from the loop once rather than once for each identifier, so that both queries are explained as when
+// it is hot under one.
+#[test]
+fn hot_twice() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    load = load.update(2, (3, 0), 1);
+    let script = vec![
+        load,
+        Epoch::new().query((3, 0, time, 7), 1).query((1, 0, time, 8), 1),
+    ];
+    let epochs = testing::run(Hot { twice: true }, 3, script);
+
+    let expected = vec![vec![((0, 1), 7), ((0, 1), 8), ((1, 2), 7), ((2, 3), 7)], vec![((0, 0), 7), ((0, 0), 8)]];
+    if epochs[1] != expected {
+        panic!("hot twice: expected {:?}, but reported {:?}", expected, epochs[1]);
+    }
+    println!("hot twice:\tok");
+}
@@ -0,0 +1,162 @@
+extern crate explanation;
+extern crate timely;
+
+use std::sync::mpsc::channel;
+use std::collections::HashMap;
+
+use explanation::interactive::{self, CcCommand, StableCommand, Event};
+
+// Scripts sessions of the interactive computations through their command channels, as the interactive examples
+// do from stdin, and checks the required records reported once each session completes. Commands are parsed from
+// the same text a user would type, so that the parsers are exercised along with the sessions. Each session's
+// event channel closes once its worker completes, which ends the accumulation of its events.
+#[test]
+fn interactive() {
+
+    // a path 0 - 1 - 2, labeled by node, with node 2's label queried; its edges, added and then one withdrawn
+    // and restored, and node 0's label explain it.
+    let script = vec![
+        "graph + 0 1", "graph + 1 2", "label + 0 0", "label + 1 1", "label + 2 2",
+        "graph - 1 2", "graph + 1 2", "query + 2 0",
+    ];
+    let commands = script.iter().map(|x| CcCommand::parse(x).unwrap().unwrap()).collect::<Vec<_>>();
+    let (command_send, command_recv) = channel();
+    let (event_send, event_recv) = channel();
+    for command in commands { command_send.send(command).unwrap(); }
+    drop(command_send);
+
+    let sessions = ::std::sync::Mutex::new(Some((command_recv, event_send)));
+    timely::execute(timely::Configuration::Thread, move |root| {
+        let (commands, events) = sessions.lock().unwrap().take().unwrap();
+        interactive::cc(root, 2, vec![], vec![], commands, events);
+    }).unwrap();
+
+    let must = accumulate(event_recv.iter());
+    let expected = vec![(0, ((0, 1), 2)), (0, ((1, 2), 2)), (1, ((0, 0), 2))];
+    if must != expected {
+        panic!("cc: expected {:?}, but required {:?}", expected, must);
+    }
+    println!("cc:\tok");
+
+    // two individuals and two recipients who agree on their preferences; the match of 0 and 10 is explained by
+    // its mutually first preference.
+    let script = vec![
+        "prefs + 0 0 10 0", "prefs + 0 1 11 1", "prefs + 1 0 10 1", "prefs + 1 1 11 0",
+        "query + 0 0 10 0",
+    ];
+    let commands = script.iter().map(|x| StableCommand::parse(x).unwrap()).collect::<Vec<_>>();
+    let (command_send, command_recv) = channel();
+    let (event_send, event_recv) = channel();
+    for command in commands { command_send.send(command).unwrap(); }
+    drop(command_send);
+
+    let sessions = ::std::sync::Mutex::new(Some((command_recv, event_send)));
+    timely::execute(timely::Configuration::Thread, move |root| {
+        let (commands, events) = sessions.lock().unwrap().take().unwrap();
+        interactive::stable(root, vec![], commands, events);
+    }).unwrap();
+
+    let must = accumulate(event_recv.iter());
+    let expected = vec![(0, ((0, (0, 10, 0)), 0))];
+    if must != expected {
+        panic!("stable: expected {:?}, but required {:?}", expected, must);
+    }
+    println!("stable:\tok");
+}
+
+// Asks about node 2's label, withdraws the query, and asks again after deleting an unrelated edge, checking that
+// the second ask is answered from the session's cache with the same records. Then adds a label, after which the
+// cached answer may no longer hold, and checks that it is retracted and the records explained afresh.
+#[test]
+#[cfg_attr(feature = "deterministic", ignore)]
+fn cached() {
+
+    // a path 0 - 1 - 2 and an unrelated edge 3 - 4, labeled by node.
+    let mut script = vec![
+        "graph + 0 1", "graph + 1 2", "graph + 3 4",
+        "label + 0 0", "label + 1 1", "label + 2 2", "label + 3 3", "label + 4 4",
+        "query + 2 0", "query - 2 0", "graph - 3 4", "query + 2 0",
+    ];
+    let expected = vec![(0, ((0, 1), 2)), (0, ((1, 2), 2)), (1, ((0, 0), 2))];
+
+    let events = cc_session(&script);
+    let must = accumulate(events.iter().cloned());
+    let cached = events.iter().map(|x| if let Event::Cached(2, w) = *x { w } else { 0 }).sum::<i32>();
+    if must != expected || cached != 1 {
+        panic!("unrelated edge deleted: expected {:?} from the cache, but required {:?}, cached {}", expected, must, cached);
+    }
+    println!("unrelated edge deleted:\tok");
+
+    script.push("label + 5 5");
+    let events = cc_session(&script);
+    let must = accumulate(events.iter().cloned());
+    let cached = events.iter().map(|x| if let Event::Cached(2, w) = *x { w } else { 0 }).sum::<i32>();
+    if must != expected || cached != 0 {
+        panic!("label added: expected {:?} explained afresh, but required {:?}, cached {}", expected, must, cached);
+    }
+    println!("label added:\tok");
+}
+
+/// Runs a connected components session of `script` on one worker, with one lane, returning the events reported.
+fn cc_session(script: &[&str]) -> Vec<Event<((u32, u32), u32)>> {
+    let (command_send, command_recv) = channel();
+    let (event_send, event_recv) = channel();
+    for line in script.iter() { command_send.send(CcCommand::parse(line).unwrap().unwrap()).unwrap(); }
+    drop(command_send);
+
+    let sessions = ::std::sync::Mutex::new(Some((command_recv, event_send)));
+    timely::execute(timely::Configuration::Thread, move |root| {
+        let (commands, events) = sessions.lock().unwrap().take().unwrap();
+        interactive::cc(root, 1, vec![], vec![], commands, events);
+    }).unwrap();
+
+    event_recv.iter().collect()
+}
+
+/// Accumulates the required records reported in `events`, returning those with positive weight, sorted.
+fn accumulate<M: Ord+Clone+::std::hash::Hash, I: Iterator<Item=Event<M>>>(events: I) -> Vec<(usize, M)> {
+    let mut counts = HashMap::new();
+    for event in events {
+        if let Event::Must(input, x, w) = event {
+            *counts.entry((input, x)).or_insert(0) += w;
+        }
+    }
+    let mut must = counts.into_iter().filter(|x| x.1 > 0).map(|x| x.0).collect::<Vec<_>>();
+    must.sort();
+    must
+}
+
+// Parses each documented form of the connected components commands, including the query suffixes in either
+// order, and checks that query commands with unknown, repeated, or empty suffixes are rejected.
+#[test]
+fn parse() {
+
+    let max = u32::max_value();
+    let forms = vec![
+        ("graph + 0 1", CcCommand::Graph((0, 1), 1)),
+        ("label - 2 2", CcCommand::Label((2, 2), -1)),
+        ("query + 2 0 3 0", CcCommand::Query(vec![(2, 0), (3, 0)], max, 1, 0)),
+        ("query@5 + 3 0", CcCommand::Query(vec![(3, 0)], 5, 1, 0)),
+        ("query^1 + 5 0", CcCommand::Query(vec![(5, 0)], max, 1, 1)),
+        ("query@3^1 - 5 0", CcCommand::Query(vec![(5, 0)], 3, -1, 1)),
+        ("query^2@4 + 5 0", CcCommand::Query(vec![(5, 0)], 4, 1, 2)),
+        ("step", CcCommand::Step),
+    ];
+    for (line, expected) in forms {
+        match CcCommand::parse(line) {
+            Ok(Some(ref command)) if *command == expected => println!("{}:\tok", line),
+            parsed => panic!("{:?}: expected {:?}, but parsed {:?}", line, expected, parsed),
+        }
+    }
+
+    if CcCommand::parse("   ") != Ok(None) {
+        panic!("blank line: expected nothing, but parsed {:?}", CcCommand::parse("   "));
+    }
+
+    for line in ["query! + 5 0", "query@ + 5 0", "query@1@2 + 5 0", "query^1x + 5 0", "queryé + 5 0"].iter() {
+        match CcCommand::parse(line) {
+            Err(reason) => println!("rejected:\t{}", reason),
+            parsed => panic!("{:?}: expected a rejection, but parsed {:?}", line, parsed),
+        }
+    }
+}
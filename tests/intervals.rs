@@ -0,0 +1,66 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::{Round, Interval};
+use explanation::generic;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Pairs of records `(key, (value, interval))` of one key whose intervals overlap, by `join_interval` of the
+/// records with themselves, asked about by key and instant with `query_at_instant`.
+///
+/// Reports the required records, and then the queries that converged as if they were required records of no key.
+struct Overlaps;
+
+impl Dataflow for Overlaps {
+    type Data = (u32, (u32, Interval));
+    type Query = (u32, u64, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, (u32, Interval)), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let none = (u32::max_value(), (u32::max_value(), (u64::max_value(), u64::max_value())));
+        let (must, converged, _) = generic::drive(scope, inputs, queries, |mut vars, queries, _explanation_scope| {
+            let mut records = vars.pop().unwrap();
+            let mut copy = records.map_inverse(|x| x, |x| x);
+            let mut pairs = records.join_interval(&mut copy);
+            pairs.query_at_instant(queries);
+            pairs.stream.map(|(k,(_,_,overlap))| (k,overlap))
+        });
+        vec![must.map(|(_,x,q)| (x,q)), converged.map(move |(q,_)| (none, q))]
+    }
+}
+
+// Keeps record 1 of key 1 valid over [0, 10) and record 2 over [5, 20), and asks about key 1 at instant 2 as query
+// 1, at instant 15 as query 2, and at instant 30 as query 3. Checks that each query requires only the records valid
+// at its instant, though all four pairs of the records share its key, and that query 3, which names no pair,
+// requires nothing and is not reported as converged.
+#[test]
+fn intervals() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let none = (u32::max_value(), (u32::max_value(), (u64::max_value(), u64::max_value())));
+    let early = (1, (1, (0, 10)));
+    let late = (1, (2, (5, 20)));
+    let script = vec![
+        Epoch::new().update(0, early, 1).update(0, late, 1),
+        Epoch::new().query((1, 2, time, 1), 1).query((1, 15, time, 2), 1).query((1, 30, time, 3), 1),
+    ];
+    let epochs = testing::run(Overlaps, 1, script);
+
+    let expected = vec![(early, 1), (late, 2)];
+    if epochs[1][0] != expected {
+        panic!("instants: expected {:?}, but required {:?}", expected, epochs[1][0]);
+    }
+    println!("instants:\tok");
+
+    let converged = vec![(none, 1), (none, 2)];
+    if epochs[1][1] != converged {
+        panic!("converged: expected {:?}, but found {:?}", converged, epochs[1][1]);
+    }
+    println!("converged:\tok");
+}
@@ -0,0 +1,63 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::algos;
+use explanation::generic;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// `algos::bipartite_matching` explained, for at most two left nodes.
+///
+/// Reports the required edges, and the queries naming edges that are not matched, as if they were required edges
+/// of no node.
+struct Matching;
+
+impl Dataflow for Matching {
+    type Data = (u32, (u32, u32));
+    type Query = (u32, (u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, (u32, u32)), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, Self::Data>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let (must, _, diagnostics) = generic::explain_iterative(scope, &inputs[0], queries, |x, s| algos::bipartite_matching(x, 2, s));
+        vec![must, diagnostics.map(|d| ((u32::max_value(), (0, 0)), d.query()))]
+    }
+}
+
+// Matches the path of three edges left 0 - right 0 - left 1 - right 1, where both ends of the middle edge prefer
+// it. Proposals and rejections keep the middle edge alone, which is maximal; a maximum matching takes the two
+// outer edges instead. Checks that the outer edges are matched and the middle edge is not, and that left 1's
+// edge is explained by left 0's edge too, whose bid displaced left 1 from its cheaper choice.
+#[test]
+fn matching() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let load = Epoch::new()
+        .update(0, (0, (0, 1)), 1)
+        .update(0, (1, (0, 0)), 1)
+        .update(0, (1, (1, 5)), 1);
+    let script = vec![
+        load,
+        Epoch::new().query((0, (0, 1), time, 1), 1).query((1, (1, 5), time, 2), 1).query((1, (0, 0), time, 3), 1),
+    ];
+    let epochs = testing::run(Matching, 1, script);
+
+    let unmatched = vec![((u32::max_value(), (0, 0)), 3)];
+    if epochs[1][1] != unmatched {
+        panic!("matched: expected only query 3 to name an unmatched edge, but {:?} did", epochs[1][1]);
+    }
+    println!("maximum matching:\tok");
+
+    let must = &epochs[1][0];
+    for &(edge, query) in [((0, (0, 1)), 1), ((1, (1, 5)), 2), ((0, (0, 1)), 2)].iter() {
+        if !must.contains(&(edge, query)) {
+            panic!("query {}: expected {:?} to be required, but required {:?}", query, edge, must);
+        }
+    }
+    println!("augmenting path:\tok");
+}
@@ -0,0 +1,105 @@
+extern crate rand;
+extern crate explanation;
+extern crate timely;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use explanation::{Round, cc, stable};
+use explanation::testing::{self, Epoch};
+use explanation::bench::random_graph;
+
+// Explains connected components and stable matching on random inputs, sending each epoch's input records and
+// queries in several random orders, and checks that every order requires exactly the same records at every
+// epoch. Ties between candidate minima are broken by the records themselves rather than by arrival order, and
+// thresholds look only at accumulated counts, so no order should be able to select a different witness.
+#[test]
+fn order() {
+
+    let trials: usize = 10;
+    let permutations: usize = 4;
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+
+    for seed in 0 .. trials {
+
+        let seed_slice: &[_] = &[seed, 10, 11, 12];
+        let mut rng: StdRng = SeedableRng::from_seed(seed_slice);
+
+        // connected components: a random graph with duplicated edges and tied labels, then every node queried.
+        let nodes = 8;
+        let mut graph = random_graph(nodes, 12, seed);
+        let duplicate = graph[0];
+        graph.push(duplicate);
+        let mut load = Epoch::new();
+        for &edge in graph.iter() { load = load.update(0, edge, 1); }
+        for node in 0 .. nodes { load = load.update(1, (node, node % 3), 1); }
+        let labels = components(nodes, &graph);
+        let mut asked = Epoch::new();
+        for node in 0 .. nodes { asked = asked.query((node, labels[node as usize], time, node), 1); }
+        let script = vec![load, asked];
+
+        let expected = testing::run(cc::Instrumented, 2, script.clone());
+        for _ in 0 .. permutations {
+            let shuffled = shuffle(&script, &mut rng);
+            let observed = testing::run(cc::Instrumented, 2, shuffled.clone());
+            if observed != expected {
+                panic!("seed {}: cc required\n{:?}\nwhen sent as {:?}, but\n{:?}\nwhen sent as {:?}", seed, observed, shuffled, expected, script);
+            }
+        }
+
+        // stable matching: random preferences with ties, queried at the matches found by the first run.
+        let mut prefs = Vec::new();
+        for a in 0 .. 4u32 {
+            for b in 10 .. 14u32 {
+                prefs.push((a, (rng.gen_range(0, 3), b, rng.gen_range(0, 3))));
+            }
+        }
+        let mut load = Epoch::new();
+        for &pref in prefs.iter() { load = load.update(0, pref, 1); }
+        let mut asked = Epoch::new();
+        for &pref in prefs.iter() { asked = asked.query((pref.0, pref.1, time, pref.0), 1); }
+        let script = vec![load, asked];
+
+        let expected = testing::run(stable::Instrumented, 1, script.clone());
+        for _ in 0 .. permutations {
+            let shuffled = shuffle(&script, &mut rng);
+            let observed = testing::run(stable::Instrumented, 1, shuffled.clone());
+            if observed != expected {
+                panic!("seed {}: stable required\n{:?}\nwhen sent as {:?}, but\n{:?}\nwhen sent as {:?}", seed, observed, shuffled, expected, script);
+            }
+        }
+
+        println!("seed {}:\tidentical across {} orders", seed, permutations + 1);
+    }
+}
+
+/// The script with each epoch's updates and queries in a random order.
+fn shuffle<D: Clone, Q: Clone>(script: &[Epoch<D, Q>], rng: &mut StdRng) -> Vec<Epoch<D, Q>> {
+    script.iter().map(|epoch| {
+        let mut epoch = epoch.clone();
+        rng.shuffle(&mut epoch.updates[..]);
+        rng.shuffle(&mut epoch.queries[..]);
+        epoch
+    }).collect()
+}
+
+/// The least label reachable from each node, which connected components assigns to it.
+fn components(nodes: u32, edges: &[(u32, u32)]) -> Vec<u32> {
+    let mut label = (0 .. nodes).map(|x| x % 3).collect::<Vec<_>>();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(x, y) in edges.iter() {
+            let min = ::std::cmp::min(label[x as usize], label[y as usize]);
+            if label[x as usize] != min || label[y as usize] != min {
+                label[x as usize] = min;
+                label[y as usize] = min;
+                changed = true;
+            }
+        }
+    }
+    label
+}
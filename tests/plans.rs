@@ -0,0 +1,64 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::{Round, JoinPlan};
+use explanation::generic;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Paths of two edges `x -> y -> z` explained, joined with indices built according to a `JoinPlan`.
+///
+/// Reports the required edges, and then the paths computed as `((x, z), y)`.
+struct TwoHops(JoinPlan);
+
+impl Dataflow for TwoHops {
+    type Data = (u32, u32);
+    type Query = (u32, (u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let plan = self.0;
+        let (must, _, paths) = generic::drive(scope, inputs, queries, move |mut vars, queries, _explanation_scope| {
+            let mut edges = vars.pop().unwrap();
+            let mut reversed = edges.map_inverse(|(x,y)| (y,x), |(y,x)| (x,y));
+            let mut output = reversed.join_u_planned(&mut edges, plan);
+            output.seed(queries);
+            output.stream.clone()
+        });
+        vec![must.map(|(_,x,q)| (x,q)), paths.map(|(y,(x,z))| ((x,z),y))]
+    }
+}
+
+// Asks about the path 0 -> 1 -> 3 as query 7, then removes the edge 1 -> 2 and adds 2 -> 3, and asks about the
+// path 1 -> 2 -> 3 as query 8. Checks that a shared index computes the same paths, and requires the same edges
+// for each query, as separate indices do, and that a path is explained by its two edges.
+#[test]
+fn shared() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (1, 3)].iter() { load = load.update(0, edge, 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((1, (0, 3), time, 7), 1),
+        Epoch::new().update(0, (1, 2), -1).update(0, (2, 3), 1).query((2, (1, 3), time, 8), 1),
+    ];
+
+    let separate = testing::run(TwoHops(JoinPlan::Separate), 1, script.clone());
+    let shared = testing::run(TwoHops(JoinPlan::Shared), 1, script);
+    if shared != separate {
+        panic!("shared: expected {:?}, as separate indices, but found {:?}", separate, shared);
+    }
+    println!("shared:\tok");
+
+    let expected = vec![vec![((0, 1), 7), ((1, 3), 7)], vec![((0, 2), 1), ((0, 3), 1)]];
+    if shared[1] != expected {
+        panic!("(1, (0, 3)): expected {:?}, but found {:?}", expected, shared[1]);
+    }
+    println!("(1, (0, 3)):\tok");
+}
@@ -0,0 +1,124 @@
+extern crate rand;
+extern crate explanation;
+extern crate timely;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use explanation::Round;
+use explanation::cc;
+use explanation::replay;
+use explanation::testing::{self, Epoch};
+use explanation::bench::random_graph;
+
+// Generates random small graphs and sequences of edge insertions and deletions, explains connected components
+// for a few random nodes once the updates are applied, and checks that each query's required records are
+// sufficient to reproduce its output. Trials are seeded by their index, so a failing trial is reported by
+// seed and can be replayed.
+#[test]
+fn random() {
+
+    let trials: usize = 20;
+    let nodes: u32 = 8;
+    let edges: usize = 10;
+    let updates: usize = 4;
+
+    for seed in 0 .. trials {
+
+        let initial = random_graph(nodes, edges, seed);
+
+        // each update removes a present edge or adds a random one, with equal probability.
+        let seed_slice: &[_] = &[seed, 4, 5, 6];
+        let mut rng: StdRng = SeedableRng::from_seed(seed_slice);
+        let mut current = initial.clone();
+        let mut script = Vec::new();
+        for _ in 0 .. updates {
+            if rng.gen() && current.len() > 0 {
+                let edge = current.swap_remove(rng.gen_range(0, current.len()));
+                script.push((edge, -1));
+            }
+            else {
+                let edge = (rng.gen_range(0, nodes), rng.gen_range(0, nodes));
+                current.push(edge);
+                script.push((edge, 1));
+            }
+        }
+
+        // query a few random nodes about the label they end up with.
+        let labels = components(nodes, &current);
+        let mut queries = (0 .. 3).map(|_| rng.gen_range(0, nodes)).map(|n| (n, labels[n as usize])).collect::<Vec<_>>();
+        queries.sort();
+        queries.dedup();
+
+        // each query is numbered by its node, and replayed on its required records alone.
+        let required = explain(nodes, &initial, &script, &queries);
+        let tagged = queries.iter().map(|&query| (query, query.0)).collect::<Vec<_>>();
+        for replayed in replay::verify(&cc::Components, 2, &required, &tagged).unwrap() {
+            if !replayed.reproduced {
+                let exported = replay::export(&required, 2, replayed.query).unwrap();
+                panic!("seed {}: query {:?} required graph {:?} and label {:?}, which do not reproduce it\ninitial graph: {:?}\nupdates: {:?}",
+                       seed, replayed.output, exported[0], exported[1], initial, script);
+            }
+        }
+        println!("seed {}:\t{} queries explained sufficiently", seed, queries.len());
+    }
+}
+
+// Exports must-set records naming an input past the last, and checks that this is reported rather than panicking.
+#[test]
+fn export_unknown_input() {
+    let required = vec![(0, (0, 1), 3), (2, (0, 0), 3)];
+    if replay::export(&required, 2, 3).is_ok() {
+        panic!("export: expected records of input 2 of 2 to be rejected");
+    }
+    if replay::export(&required, 3, 3) != Ok(vec![vec![(0, 1)], vec![], vec![(0, 0)]]) {
+        panic!("export: expected the records of each of 3 inputs, but found {:?}", replay::export(&required, 3, 3));
+    }
+    println!("export:\tok");
+}
+
+/// The least node in each node's component, which connected components assigns as its label.
+fn components(nodes: u32, edges: &[(u32, u32)]) -> Vec<u32> {
+    let mut label = (0 .. nodes).collect::<Vec<_>>();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(x, y) in edges.iter() {
+            let min = ::std::cmp::min(label[x as usize], label[y as usize]);
+            if label[x as usize] != min || label[y as usize] != min {
+                label[x as usize] = min;
+                label[y as usize] = min;
+                changed = true;
+            }
+        }
+    }
+    label
+}
+
+/// Loads `initial`, applies `script` one update per round, then asks `queries`; returns the required records as
+/// `(input, (key, val), query)`, where `input` is 0 for graph records and 1 for label records.
+fn explain(nodes: u32, initial: &[(u32, u32)], script: &[((u32, u32), i32)], queries: &[(u32, u32)]) -> Vec<(u32, (u32, u32), u32)> {
+
+    let mut load = Epoch::new();
+    for &edge in initial.iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. nodes { load = load.update(1, (node, node), 1); }
+    let mut epochs = vec![load];
+    for &(edge, weight) in script.iter() {
+        epochs.push(Epoch::new().update(0, edge, weight));
+    }
+    let mut asked = Epoch::new();
+    for &(node, value) in queries.iter() {
+        asked = asked.query((node, value, Product::new(RootTimestamp::new(u32::max_value()), Round::max_value()), node), 1);
+    }
+    epochs.push(asked);
+
+    let musts = testing::run(cc::Instrumented, 2, epochs).pop().unwrap();
+    let mut required = Vec::new();
+    for (input, must) in musts.into_iter().enumerate() {
+        required.extend(must.into_iter().map(|(x, q)| (input as u32, x, q)));
+    }
+    required.sort();
+    required
+}
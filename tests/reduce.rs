@@ -0,0 +1,119 @@
+#[macro_use]
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+
+use explanation::{Variable, Round, ExplanationPolicy};
+use explanation::generic;
+use explanation::testing::{self, Dataflow, Epoch};
+
+type Time = Product<Product<RootTimestamp, u32>, Round>;
+
+/// The grouping operators, each applied to `(key, value)` records.
+#[derive(Copy, Clone, Debug)]
+enum Operator {
+    /// `group_with_policy!` summing each group, explained by all of its members.
+    AllMembers,
+    /// `group_with_policy!` taking each group's least value, explained by the fewest least members reproducing it.
+    Selective,
+    /// `reduce_with_witnesses!` taking each group's greatest value, witnessed by the record holding it.
+    Witnesses,
+    /// `min_by_key!` taking ten times the value nearest six, the lesser value breaking ties.
+    MinByKey,
+    /// `reduce_core!` counting each group, demanding the members greater than the count.
+    Core,
+    /// `antijoin_u!` keeping the records of keys without a value of four.
+    Antijoin,
+}
+
+/// One grouping operator explained.
+///
+/// Reports the required records, and the queries naming absent outputs, as if they were required records of no key.
+struct Reduce {
+    operator: Operator,
+}
+
+impl Dataflow for Reduce {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Time, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let operator = self.operator;
+        let (must, _, diagnostics) = generic::explain_iterative(scope, &inputs[0], queries, move |x, s| match operator {
+            Operator::AllMembers => {
+                group_with_policy!(x, |values: &[(u32, i32)]| values.iter().map(|&(v,w)| v * w as u32).sum::<u32>(), ExplanationPolicy::AllMembers, *s)
+            },
+            Operator::Selective => {
+                group_with_policy!(x, |values: &[(u32, i32)]| values[0].0, ExplanationPolicy::MinimalSelective, *s)
+            },
+            Operator::Witnesses => {
+                reduce_with_witnesses!(x, |_key: &u32, values: &[(u32, i32)]| values.last().map(|&(v,_)| (v, vec![v])), *s)
+            },
+            Operator::MinByKey => {
+                min_by_key!(x, |v: u32| if v > 6 { v - 6 } else { 6 - v }, |v: u32| 10 * v, *s)
+            },
+            Operator::Core => {
+                reduce_core!(x,
+                    |_key: &u32, values: &[(u32, i32)]| vec![(values.len() as u32, 1)],
+                    |_key: &u32, members: &[(u32, Time)], requirements: &[(u32, Time, u32)]| {
+                        let mut demands = Vec::new();
+                        for &(count, ref time, query) in requirements.iter() {
+                            for &(value, ref changed) in members.iter().filter(|m| m.0 > count && m.1 <= *time) {
+                                demands.push((value, changed.clone(), count, query));
+                            }
+                        }
+                        demands
+                    },
+                    *s)
+            },
+            Operator::Antijoin => {
+                let mut fours = x.filter(|&(_,v)| v == 4);
+                antijoin_u!(x, fours, *s)
+            },
+        });
+        vec![must, diagnostics.map(|d| ((u32::max_value(), u32::max_value()), d.query()))]
+    }
+}
+
+// Groups the values 3, 5, and 7 under key 1 and the value 4 under key 2, and asks with each operator about one
+// output of each key: query 1 about key 1, and query 2 about key 2. Checks the records each operator demands,
+// and that only the antijoin, which keeps nothing of key 2, reports query 2 as naming an absent output.
+#[test]
+fn reduce() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let none = (u32::max_value(), u32::max_value());
+    let cases = vec![
+        (Operator::AllMembers, (1, 15), (2, 4), vec![((1, 3), 1), ((1, 5), 1), ((1, 7), 1), ((2, 4), 2)], vec![]),
+        (Operator::Selective, (1, 3), (2, 4), vec![((1, 3), 1), ((2, 4), 2)], vec![]),
+        (Operator::Witnesses, (1, 7), (2, 4), vec![((1, 7), 1), ((2, 4), 2)], vec![]),
+        (Operator::MinByKey, (1, 50), (2, 40), vec![((1, 5), 1), ((2, 4), 2)], vec![]),
+        (Operator::Core, (1, 3), (2, 1), vec![((1, 5), 1), ((1, 7), 1), ((2, 4), 2)], vec![]),
+        (Operator::Antijoin, (1, 5), (2, 4), vec![((1, 5), 1), ((2, 4), 2)], vec![(none, 2)]),
+    ];
+
+    for (operator, first, second, expected, absent) in cases {
+        let mut load = Epoch::new();
+        for &record in [(1, 3), (1, 5), (1, 7), (2, 4)].iter() { load = load.update(0, record, 1); }
+        let script = vec![
+            load,
+            Epoch::new().query((first.0, first.1, time, 1), 1).query((second.0, second.1, time, 2), 1),
+        ];
+        let epochs = testing::run(Reduce { operator: operator }, 1, script);
+
+        if epochs[1][0] != expected {
+            panic!("{:?}: expected {:?}, but required {:?}", operator, expected, epochs[1][0]);
+        }
+        if epochs[1][1] != absent {
+            panic!("{:?}: expected {:?} to name absent outputs, but {:?} did", operator, absent, epochs[1][1]);
+        }
+        println!("{:?}:\tok", operator);
+    }
+}
@@ -0,0 +1,109 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::{generic, strip_metadata, echo_metadata, completed, with_rounds};
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// A copy of its input, asked about by queries submitted with metadata.
+///
+/// Reports the required records with their queries' metadata, as `(record, (query, metadata))`, then the
+/// `completed` markers as `((query, epoch), none)`, and then the required records `with_rounds`, as
+/// `(record, (query, 1))` for each carrying a round past the first.
+struct Annotated;
+
+impl Dataflow for Annotated {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32, u32);
+    type Must = ((u32, u32), (u32, u32));
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let none = (u32::max_value(), u32::max_value());
+        let (queries, metadata) = strip_metadata(queries);
+        let (must, converged, _) = generic::explain(scope, &inputs[0], &queries, |x| x.map_inverse(|x| x, |x| x));
+        vec![
+            echo_metadata(&must, &metadata),
+            completed(&queries, &must).map(move |marker| (marker, none)),
+            with_rounds(&must, &converged, |x| x.1).map(|((x,q),r)| (x,(q,if r > 0 { 1 } else { 0 }))),
+        ]
+    }
+}
+
+// Asks about record (1, 3) as query 1 with metadata 77 at epoch 1, about record (2, 4) as query 2 with metadata 88
+// at epoch 2, and withdraws query 1 at epoch 3. Checks that each query's requirements carry its metadata and a
+// round, that each query is marked complete at the epoch it was asked, and that withdrawing query 1 retracts its
+// marker and its results.
+#[test]
+fn results() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let none = (u32::max_value(), u32::max_value());
+    let script = vec![
+        Epoch::new().update(0, (1, 3), 1).update(0, (2, 4), 1),
+        Epoch::new().query((1, 3, time, 1, 77), 1),
+        Epoch::new().query((2, 4, time, 2, 88), 1),
+        Epoch::new().query((1, 3, time, 1, 77), -1),
+    ];
+    let epochs = testing::run(Annotated, 1, script);
+
+    let expected = vec![
+        vec![
+            vec![((1, 3), (1, 77))],
+            vec![((1, 1), none)],
+            vec![((1, 3), (1, 1))],
+        ],
+        vec![
+            vec![((1, 3), (1, 77)), ((2, 4), (2, 88))],
+            vec![((1, 1), none), ((2, 2), none)],
+            vec![((1, 3), (1, 1)), ((2, 4), (2, 1))],
+        ],
+        vec![
+            vec![((2, 4), (2, 88))],
+            vec![((2, 2), none)],
+            vec![((2, 4), (2, 1))],
+        ],
+    ];
+    for (epoch, expected) in expected.into_iter().enumerate() {
+        if epochs[epoch + 1] != expected {
+            panic!("epoch {}: expected {:?}, but found {:?}", epoch + 1, expected, epochs[epoch + 1]);
+        }
+    }
+    println!("results:\tok");
+}
+
+// Asks about record (1, 3) as query 1 at epoch 1, asks it again at epoch 2 while the first ask stands, and then
+// withdraws it once at epoch 3 and once more at epoch 4. Checks that each ask is marked complete at its own epoch,
+// and that each withdrawal retracts one marker, rather than leaving the first ask's marker behind.
+#[test]
+fn reask_completed() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let none = (u32::max_value(), u32::max_value());
+    let script = vec![
+        Epoch::new().update(0, (1, 3), 1),
+        Epoch::new().query((1, 3, time, 1, 77), 1),
+        Epoch::new().query((1, 3, time, 1, 77), 1),
+        Epoch::new().query((1, 3, time, 1, 77), -1),
+        Epoch::new().query((1, 3, time, 1, 77), -1),
+    ];
+    let epochs = testing::run(Annotated, 1, script);
+
+    let expected = vec![
+        vec![((1, 1), none)],
+        vec![((1, 1), none), ((1, 2), none)],
+        vec![((1, 1), none)],
+        vec![],
+    ];
+    for (epoch, expected) in expected.into_iter().enumerate() {
+        if epochs[epoch + 1][1] != expected {
+            panic!("epoch {}: expected markers {:?}, but found {:?}", epoch + 1, expected, epochs[epoch + 1][1]);
+        }
+    }
+    println!("re-ask:\tok");
+}
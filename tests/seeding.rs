@@ -0,0 +1,82 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::generic;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// A copy of its input, seeded two ways: queries numbered below 100 name records and are admitted only for keys
+/// below 10, by `seed_admitted`, and the others name a key alone, by `query_view`. Each query may require at most
+/// two input records, by `cap_by`.
+///
+/// Reports the required records, then as if they were required records of no key the rejected queries, then the
+/// records `reproduced` for each query numbered below 100, and then as if they were required records of no key the
+/// queries that reached their cap.
+struct Seeded;
+
+impl Dataflow for Seeded {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let none = (u32::max_value(), u32::max_value());
+        let (must, _, reports) = generic::drive(scope, inputs, queries, move |mut vars, queries, _explanation_scope| {
+
+            let mut input = vars.pop().unwrap();
+            let capped = input.depends.cap_by(2, |x| x.3).leave();
+            let mut output = input.map_inverse(|x| x, |x| x);
+
+            let rejected = output.seed_admitted(&queries.filter(|x| x.3 < 100), |x| x.0 < 10);
+            output.query_view(&queries.filter(|x| x.3 >= 100).map(|(k,_,t,q)| (k,t,q)), |x| x.0);
+            let reproduced = output.reproduced(&queries.filter(|x| x.3 < 100));
+
+            rejected.map(move |d| (0, (none, d.query())))
+                    .concat(&reproduced.map(|((k,_),v,q)| (1, ((k,v),q))))
+                    .concat(&capped.map(move |q| (2, (none, q))))
+        });
+
+        let mut musts = vec![must.map(|(_,x,q)| (x,q))];
+        for tag in 0 .. 3 {
+            musts.push(reports.filter(move |x| x.0 == tag).map(|(_,x)| x));
+        }
+        musts
+    }
+}
+
+// Asks about record (1, 3) as query 1, about record (20, 1) as query 2, and about key 1 as query 101, which
+// names three records. Checks that query 2 is rejected and requires nothing though its record is present, that
+// query 101 reaches its cap with the two least records of key 1, and that query 1's record reproduces every record
+// of its key that some query requires.
+#[test]
+fn seeding() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let none = (u32::max_value(), u32::max_value());
+    let mut load = Epoch::new();
+    for &record in [(1, 3), (1, 4), (1, 5), (20, 1)].iter() { load = load.update(0, record, 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((1, 3, time, 1), 1).query((20, 1, time, 2), 1).query((1, 0, time, 101), 1),
+    ];
+    let epochs = testing::run(Seeded, 1, script);
+
+    let expected = vec![
+        ("required", vec![((1, 3), 1), ((1, 3), 101), ((1, 4), 101)]),
+        ("rejected", vec![(none, 2)]),
+        ("reproduced", vec![((1, 3), 1), ((1, 4), 1)]),
+        ("capped", vec![(none, 101)]),
+    ];
+    for (index, (name, expected)) in expected.into_iter().enumerate() {
+        if epochs[1][index] != expected {
+            panic!("{}: expected {:?}, but found {:?}", name, expected, epochs[1][index]);
+        }
+        println!("{}:\tok", name);
+    }
+}
@@ -0,0 +1,59 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::{Round, share};
+use explanation::cc;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Connected components explained, with the required graph records split into a shared core and extras.
+///
+/// Reports both as `(record, queries)`, each extra with its one query.
+struct Shared;
+
+impl Dataflow for Shared {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), Vec<u32>);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let musts = cc::Instrumented.build(scope, inputs, queries);
+        let (core, extras) = share(&musts[0]);
+        vec![core, extras.map(|(x,q)| (x,vec![q]))]
+    }
+}
+
+// Asks about two nodes of one path, whose explanations share the start of the path, and checks that the shared
+// edges are reported once, with the queries sharing them, apart from the edge only one query needs.
+// Withdrawing a query leaves the other's edges all its own.
+#[test]
+fn share() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((3, 0, time, 3), 1).query((2, 0, time, 2), 1),
+        Epoch::new().query((2, 0, time, 2), -1),
+    ];
+    let epochs = testing::run(Shared, 2, script);
+
+    let expected = vec![vec![((0, 1), vec![2, 3]), ((1, 2), vec![2, 3])], vec![((2, 3), vec![3])]];
+    if epochs[1] != expected {
+        panic!("both queries: expected {:?}, but reported {:?}", expected, epochs[1]);
+    }
+    println!("both queries:\tok");
+
+    let expected = vec![vec![], vec![((0, 1), vec![3]), ((1, 2), vec![3]), ((2, 3), vec![3])]];
+    if epochs[2] != expected {
+        panic!("one withdrawn: expected {:?}, but reported {:?}", expected, epochs[2]);
+    }
+    println!("one withdrawn:\tok");
+}
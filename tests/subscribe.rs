@@ -0,0 +1,71 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::{subscribe, fan_out};
+use explanation::cc;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Connected components explained for subscriptions `(node, label, subscription)`, asked afresh as the graph and
+/// labels change.
+///
+/// Reports the required edges and labels, and then the `(query, subscription)` pairs as if they were required
+/// records of no subscription.
+struct Subscribed;
+
+impl Dataflow for Subscribed {
+    type Data = (u32, u32);
+    type Query = (u32, u32, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], subscriptions: &Collection<G, (u32, u32, u32)>) -> Vec<Collection<G, ((u32, u32), u32)>> {
+        let (queries, aliases) = subscribe(subscriptions, &inputs[0].concat(&inputs[1]), 1 << 16);
+        let mut musts = cc::Instrumented.build(scope, inputs, &queries).iter().map(|x| fan_out(x, &aliases)).collect::<Vec<_>>();
+        musts.push(aliases.map(|pair| (pair, u32::max_value())));
+        musts
+    }
+}
+
+// Subscribes to the label of the far end of a path, then cuts the path and adds a shortcut. The subscription's
+// explanation moves to the shortcut, retracting the path rather than keeping it as a query about the whole
+// history would. A second subscription, arriving at an epoch without changes, is asked under the identifier the
+// first subscription's withdrawn query held, while the first is not asked again.
+#[test]
+fn subscribe() {
+
+    let base = 1 << 16;
+    let none = u32::max_value();
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((3, 0, 5), 1),
+        Epoch::new().update(0, (1, 2), -1).update(0, (0, 3), 1),
+        Epoch::new().query((1, 0, 6), 1),
+    ];
+    let epochs = testing::run(Subscribed, 2, script);
+
+    let expected = vec![vec![((0, 1), 5), ((1, 2), 5), ((2, 3), 5)], vec![((0, 0), 5)], vec![((base, 5), none)]];
+    if epochs[1] != expected {
+        panic!("subscribed: expected {:?}, but required {:?}", expected, epochs[1]);
+    }
+    println!("subscribed:\tok");
+
+    let expected = vec![vec![((0, 3), 5)], vec![((0, 0), 5)], vec![((base + 1, 5), none)]];
+    if epochs[2] != expected {
+        panic!("path cut: expected {:?}, but required {:?}", expected, epochs[2]);
+    }
+    println!("path cut:\tok");
+
+    let expected = vec![vec![((0, 1), 6), ((0, 3), 5)], vec![((0, 0), 5), ((0, 0), 6)], vec![((base, 6), none), ((base + 1, 5), none)]];
+    if epochs[3] != expected {
+        panic!("second subscription: expected {:?}, but required {:?}", expected, epochs[3]);
+    }
+    println!("second subscription:\tok");
+}
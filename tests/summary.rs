@@ -0,0 +1,63 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::cc;
+use explanation::summary::{self, Patterns};
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Edges incident to nodes 2 and 3, numbered 0 and 1, with `other` numbered 2.
+fn patterns() -> Patterns<(u32, u32)> {
+    Patterns::new()
+        .pattern("incident to 2", |&(x, y)| x == 2 || y == 2)
+        .pattern("incident to 3", |&(x, y)| x == 3 || y == 3)
+}
+
+/// Connected components explained, with the required graph records summarized, and those of `other` listed.
+struct Summarized;
+
+impl Dataflow for Summarized {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, ((u32, u32), u32)>> {
+        let musts = cc::Instrumented.build(scope, inputs, queries);
+        let patterns = patterns();
+        vec![summary::summarize(&musts[0], &patterns), summary::drill_down(&musts[0], &patterns, 2)]
+    }
+}
+
+// Explains the far end of a path, and checks that its edges are counted under each pattern they match, with
+// the edge matching neither counted as `other` and recovered by drilling down.
+#[test]
+fn summary() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((3, 0, time, 3), 1),
+    ];
+    let epochs = testing::run(Summarized, 2, script);
+
+    let expected = vec![((0, 3), 2), ((1, 3), 1), ((2, 3), 1)];
+    if epochs[1][0] != expected {
+        panic!("summary: expected {:?}, but counted {:?}", expected, epochs[1][0]);
+    }
+    println!("summary:\tok");
+
+    let expected = vec![((0, 1), 3)];
+    if epochs[1][1] != expected {
+        panic!("{}: expected {:?}, but listed {:?}", patterns().name(2), expected, epochs[1][1]);
+    }
+    println!("{}:\tok", patterns().name(2));
+}
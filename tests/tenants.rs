@@ -0,0 +1,65 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::cc;
+use explanation::tenants::{self, Namespaces};
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Connected components explained for two clients with 16 bits of query identifiers each, each admitted one query at a time, with client 2 allowed
+/// results of at most two graph records.
+///
+/// Reports the required graph records within quota, and the clients over quota as `((client, records), 0)`.
+struct Tenants;
+
+impl Dataflow for Tenants {
+    type Data = (u32, u32);
+    type Query = (u32, u32, Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, ((u32, u32), u32)>> {
+        let namespaces = Namespaces::new(16);
+        let (admitted, _rejected) = tenants::admit(namespaces, queries, |_| 1);
+        let musts = cc::Instrumented.build(scope, inputs, &admitted);
+        let (within, exceeded) = tenants::within_quota(namespaces, &musts[0], |client| if client == 2 { 2 } else { 16 });
+        vec![within, exceeded.map(|(client, records)| ((client, records), 0))]
+    }
+}
+
+// Client 1 asks two questions at once and client 2 one, each about the far end of a path. Only client 1's first
+// query is admitted, and client 2's three edges exceed its quota of two, so only client 1's first query is
+// explained; withdrawing it admits client 1's second query in its place.
+#[test]
+fn tenants() {
+
+    let namespaces = Namespaces::new(16);
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    let (first, second, other) = (namespaces.id(1, 0), namespaces.id(1, 1), namespaces.id(2, 0));
+    let script = vec![
+        load,
+        Epoch::new().query((3, 0, time, first), 1).query((2, 0, time, second), 1).query((3, 0, time, other), 1),
+        Epoch::new().query((3, 0, time, first), -1),
+    ];
+    let epochs = testing::run(Tenants, 2, script);
+
+    let expected = vec![vec![((0, 1), first), ((1, 2), first), ((2, 3), first)], vec![((2, 3), 0)]];
+    if epochs[1] != expected {
+        panic!("quotas: expected {:?}, but reported {:?}", expected, epochs[1]);
+    }
+    println!("quotas:\tok");
+
+    let expected = vec![vec![((0, 1), second), ((1, 2), second)], vec![((2, 3), 0)]];
+    if epochs[2] != expected {
+        panic!("withdrawal: expected {:?}, but reported {:?}", expected, epochs[2]);
+    }
+    println!("withdrawal:\tok");
+}
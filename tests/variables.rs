@@ -0,0 +1,99 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+
+use explanation::{Variable, Round};
+use explanation::generic;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// `Variable` methods with no counterpart among plain collections, each applied to `(key, value)` records and
+/// presenting its results as `(key, (value, other))`.
+#[derive(Copy, Clone, Debug)]
+enum Method {
+    /// `concat_many` of the records with odd values and those of key 1, with `other` zero.
+    ConcatMany,
+    /// `map_fallible` halving even values, with `other` zero; odd values fail, and are presented with `other` one.
+    MapFallible,
+    /// `join_on` of records `(a, b)` and `(b, c)` on `b`, as `(b, (a, c))`.
+    JoinOn,
+    /// `distinct_by` key, keeping the least record of each key, with `other` zero.
+    DistinctBy,
+}
+
+/// One method explained.
+///
+/// Reports the required records, and the queries naming absent outputs, as if they were required records of no key.
+struct Methods {
+    method: Method,
+}
+
+impl Dataflow for Methods {
+    type Data = (u32, u32);
+    type Query = (u32, (u32, u32), Product<Product<RootTimestamp, u32>, Round>, u32);
+    type Must = ((u32, u32), u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, scope: &mut G, inputs: &[Collection<G, (u32, u32)>], queries: &Collection<G, Self::Query>) -> Vec<Collection<G, Self::Must>> {
+        let method = self.method;
+        let (must, _, diagnostics) = generic::explain(scope, &inputs[0], queries, move |x| match method {
+            Method::ConcatMany => {
+                let mut odd = x.filter(|&(_,v)| v % 2 == 1);
+                let mut ones = x.filter(|&(k,_)| k == 1);
+                Variable::concat_many(vec![&mut odd, &mut ones]).map_inverse(|(k,v)| (k,(v,0)), |(k,(v,_))| (k,v))
+            },
+            Method::MapFallible => {
+                let (mut halved, mut failed) = x.map_fallible(|&(k,v)| if v % 2 == 0 { Ok((k, v / 2)) } else { Err(()) }, |(k,v)| (k, 2 * v));
+                let mut halved = halved.map_inverse(|(k,v)| (k,(v,0)), |(k,(v,_))| (k,v));
+                let mut failed = failed.map_inverse(|(k,(v,()))| (k,(v,1)), |(k,(v,_))| (k,(v,())));
+                halved.concat(&mut failed)
+            },
+            Method::JoinOn => {
+                let mut copy = x.map_inverse(|x| x, |x| x);
+                x.join_on(&mut copy, |e| e.1, |e| e.0)
+                 .map_inverse(|(b,((a,_),(_,c)))| (b,(a,c)), |(b,(a,c))| (b,((a,b),(b,c))))
+            },
+            Method::DistinctBy => {
+                x.distinct_by(|&(k,_)| k).map_inverse(|(k,v)| (k,(v,0)), |(k,(v,_))| (k,v))
+            },
+        });
+        vec![must, diagnostics.map(|d| ((u32::max_value(), u32::max_value()), d.query()))]
+    }
+}
+
+// Applies each method to the records (1, 3), (1, 4), and (3, 5), and asks about two outputs, as queries 1 and 2.
+// Checks that each output is explained by the records it was derived from, a failed mapping by the record that
+// failed and a distinct record by itself alone, and that outputs a method does not produce are reported as absent.
+#[test]
+fn variables() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let none = (u32::max_value(), u32::max_value());
+    let cases = vec![
+        (Method::ConcatMany, (1, (3, 0)), (3, (4, 0)), vec![((1, 3), 1)], vec![(none, 2)]),
+        (Method::MapFallible, (1, (2, 0)), (1, (3, 1)), vec![((1, 3), 2), ((1, 4), 1)], vec![]),
+        (Method::JoinOn, (3, (1, 5)), (2, (0, 0)), vec![((1, 3), 1), ((3, 5), 1)], vec![(none, 2)]),
+        (Method::DistinctBy, (1, (3, 0)), (3, (5, 0)), vec![((1, 3), 1), ((3, 5), 2)], vec![]),
+    ];
+
+    for (method, first, second, expected, absent) in cases {
+        let mut load = Epoch::new();
+        for &record in [(1, 3), (1, 4), (3, 5)].iter() { load = load.update(0, record, 1); }
+        let script = vec![
+            load,
+            Epoch::new().query((first.0, first.1, time, 1), 1).query((second.0, second.1, time, 2), 1),
+        ];
+        let epochs = testing::run(Methods { method: method }, 1, script);
+
+        if epochs[1][0] != expected {
+            panic!("{:?}: expected {:?}, but required {:?}", method, expected, epochs[1][0]);
+        }
+        if epochs[1][1] != absent {
+            panic!("{:?}: expected {:?} to name absent outputs, but {:?} did", method, absent, epochs[1][1]);
+        }
+        println!("{:?}:\tok", method);
+    }
+}
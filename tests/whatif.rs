@@ -0,0 +1,67 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::Collection;
+use differential_dataflow::operators::*;
+
+use explanation::cc;
+use explanation::replay::Computation;
+use explanation::speculate;
+use explanation::testing::{self, Dataflow, Epoch};
+
+/// Connected components, with inputs 2 and 3 hypothetical changes to the graph and labels.
+///
+/// Reports the labels, and the labels that hypothetical changes would add and remove.
+struct WhatIf;
+
+impl Dataflow for WhatIf {
+    type Data = (u32, u32);
+    type Query = ();
+    type Must = (u32, u32);
+    fn build<G: Scope<Timestamp=Product<RootTimestamp, u32>>>(&self, _scope: &mut G, inputs: &[Collection<G, (u32, u32)>], _queries: &Collection<G, ()>) -> Vec<Collection<G, (u32, u32)>> {
+        let impact = speculate::what_if(&cc::Components, &inputs[0 .. 2], &inputs[2 .. 4]);
+        vec![cc::Components.build(&inputs[0 .. 2]), impact.clone(), impact.negate()]
+    }
+}
+
+// On a path labeled by node, hypothetically deleting its middle edge would relabel the far half of the path,
+// and checks that this is reported while the labels themselves are unchanged. Retracting the hypothetical
+// deletion withdraws its impact, and actually deleting the edge then has the impact that was predicted.
+#[test]
+fn whatif() {
+
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    let script = vec![
+        load,
+        Epoch::new().update(2, (1, 2), -1),
+        Epoch::new().update(2, (1, 2), 1),
+        Epoch::new().update(0, (1, 2), -1),
+    ];
+    let epochs = testing::run(WhatIf, 4, script);
+
+    let labels = vec![(0, 0), (1, 0), (2, 0), (3, 0)];
+    let expected = vec![labels.clone(), vec![(2, 2), (3, 2)], vec![(2, 0), (3, 0)]];
+    if epochs[1] != expected {
+        panic!("hypothetical deletion: expected {:?}, but reported {:?}", expected, epochs[1]);
+    }
+    println!("hypothetical deletion:\tok");
+
+    let expected = vec![labels, vec![], vec![]];
+    if epochs[2] != expected {
+        panic!("hypothetical deletion withdrawn: expected {:?}, but reported {:?}", expected, epochs[2]);
+    }
+    println!("hypothetical deletion withdrawn:\tok");
+
+    let expected = vec![vec![(0, 0), (1, 0), (2, 2), (3, 2)], vec![], vec![]];
+    if epochs[3] != expected {
+        panic!("actual deletion: expected {:?}, but reported {:?}", expected, epochs[3]);
+    }
+    println!("actual deletion:\tok");
+}
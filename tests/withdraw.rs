@@ -0,0 +1,70 @@
+extern crate explanation;
+extern crate timely;
+
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+
+use explanation::Round;
+use explanation::cc;
+use explanation::testing::{self, Epoch};
+
+// Asks about the far end of a path, then withdraws the query, and checks that the requirements carried from one
+// epoch to the next are those of queries still asked: once withdrawn, the query's graph and label records are
+// no longer reported, rather than fed back into every later epoch.
+#[test]
+fn withdraw() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((3, 0, time, 3), 1),
+        Epoch::new().query((3, 0, time, 3), -1),
+        Epoch::new(),
+    ];
+    let epochs = testing::run(cc::Instrumented, 2, script);
+
+    let asked = vec![vec![((0, 1), 3), ((1, 2), 3), ((2, 3), 3)], vec![((0, 0), 3)]];
+    if epochs[1] != asked {
+        panic!("asked: expected {:?}, but required {:?}", asked, epochs[1]);
+    }
+    for epoch in 2 .. 4 {
+        if epochs[epoch] != vec![vec![], vec![]] {
+            panic!("withdrawn, epoch {}: expected nothing, but required {:?}", epoch, epochs[epoch]);
+        }
+    }
+    println!("withdraw:\tok");
+}
+
+// Asks about the far end of a path, withdraws the query, and asks it again under the same identifier, as cc
+// drivers do with node identifiers. The re-asked query is explained as the first was, rather than having its
+// requirements retired as they arrive.
+#[test]
+fn reask() {
+
+    let time = Product::new(RootTimestamp::new(u32::max_value()), Round::max_value());
+    let mut load = Epoch::new();
+    for &edge in [(0, 1), (1, 2), (2, 3)].iter() { load = load.update(0, edge, 1); }
+    for node in 0 .. 4 { load = load.update(1, (node, node), 1); }
+    let script = vec![
+        load,
+        Epoch::new().query((3, 0, time, 3), 1),
+        Epoch::new().query((3, 0, time, 3), -1),
+        Epoch::new().query((3, 0, time, 3), 1),
+        Epoch::new(),
+    ];
+    let epochs = testing::run(cc::Instrumented, 2, script);
+
+    let asked = vec![vec![((0, 1), 3), ((1, 2), 3), ((2, 3), 3)], vec![((0, 0), 3)]];
+    for &epoch in [1, 3, 4].iter() {
+        if epochs[epoch] != asked {
+            panic!("epoch {}: expected {:?}, but required {:?}", epoch, asked, epochs[epoch]);
+        }
+    }
+    if epochs[2] != vec![vec![], vec![]] {
+        panic!("withdrawn: expected nothing, but required {:?}", epochs[2]);
+    }
+    println!("re-ask:\tok");
+}
@@ -0,0 +1,125 @@
+extern crate explanation;
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::*;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+use differential_dataflow::Collection;
+
+use explanation::Round;
+use explanation::cc;
+
+// Explains connected components on small graphs with one through four workers, and checks that each run
+// requires exactly the graph and label records that the single-worker run requires. Requirements are
+// produced on whichever worker derives them and exchanged to the workers holding the named records, so
+// any record lost or duplicated in the exchange shows up as a difference here.
+#[test]
+fn workers() {
+
+    let graphs = vec![
+        ("path", 6, vec![(0, 1), (1, 2), (2, 3), (3, 4), (4, 5)]),
+        ("star", 6, vec![(0, 1), (0, 2), (0, 3), (0, 4), (0, 5)]),
+        ("two components", 7, vec![(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 6)]),
+        ("reversed path", 6, vec![(5, 4), (4, 3), (3, 2), (2, 1), (1, 0)]),
+    ];
+
+    for (name, nodes, edges) in graphs {
+        let expected = explain(1, nodes, &edges);
+        for workers in 2 .. 5 {
+            let observed = explain(workers, nodes, &edges);
+            if observed != expected {
+                panic!("{}: {} workers required\n{:?}\nbut one worker required\n{:?}", name, workers, observed, expected);
+            }
+        }
+        println!("{}:\t{} requirements, identical for 1-4 workers", name, expected.len());
+    }
+}
+
+/// The least node in each node's component, which connected components assigns as its label.
+fn components(nodes: u32, edges: &[(u32, u32)]) -> Vec<u32> {
+    let mut label = (0 .. nodes).collect::<Vec<_>>();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(x, y) in edges.iter() {
+            let min = ::std::cmp::min(label[x as usize], label[y as usize]);
+            if label[x as usize] != min || label[y as usize] != min {
+                label[x as usize] = min;
+                label[y as usize] = min;
+                changed = true;
+            }
+        }
+    }
+    label
+}
+
+/// Queries every node's label with `workers` workers, and returns the required records as
+/// `(input, (key, val), query)`, where `input` is 0 for graph records and 1 for label records.
+fn explain(workers: usize, nodes: u32, edges: &[(u32, u32)]) -> Vec<(u32, (u32, u32), u32)> {
+
+    let labels = components(nodes, edges);
+    let edges = edges.to_vec();
+    let results = Arc::new(Mutex::new(HashMap::new()));
+    let shared = results.clone();
+
+    timely::execute(timely::Configuration::Process(workers), move |root| {
+
+        let results = shared.clone();
+        let (mut graph, mut label, mut query, probe) = root.scoped::<u32, _, _>(move |streaming| {
+
+            let (graph_handle, graph) = streaming.new_input(); let graph = Collection::new(graph);
+            let (label_handle, label) = streaming.new_input(); let label = Collection::new(label);
+            let (query_handle, query) = streaming.new_input(); let query = Collection::new(query);
+
+            let (prior_graph_handle, prior_graph) = streaming.loop_variable(u32::max_value(), 1);
+            let (prior_label_handle, prior_label) = streaming.loop_variable(u32::max_value(), 1);
+            let prior_graph = Collection::new(prior_graph);
+            let prior_label = Collection::new(prior_label);
+
+            let (graph_must, label_must, _converged, _diagnostics, _shortfall) = cc::explain(streaming, &graph, &label, &query, &prior_graph, &prior_label);
+            graph_must.inner.connect_loop(prior_graph_handle);
+            label_must.inner.connect_loop(prior_label_handle);
+
+            let results1 = results.clone();
+            let results2 = results.clone();
+            let graph_must = graph_must.inspect(move |&((x, q), w)| *results1.lock().unwrap().entry((0, x, q)).or_insert(0) += w);
+            let label_must = label_must.inspect(move |&((x, q), w)| *results2.lock().unwrap().entry((1, x, q)).or_insert(0) += w);
+
+            (graph_handle, label_handle, query_handle, graph_must.concat(&label_must).probe().0)
+        });
+
+        // worker zero supplies all input; the dataflow is responsible for distributing it.
+        if root.index() == 0 {
+            for &edge in edges.iter() {
+                graph.send((edge, 1));
+            }
+            for node in 0 .. nodes {
+                label.send(((node, node), 1));
+            }
+        }
+        graph.advance_to(1);
+        label.advance_to(1);
+        query.advance_to(1);
+        root.step_while(|| probe.lt(&query.time()));
+
+        if root.index() == 0 {
+            for node in 0 .. nodes {
+                query.send(((node, labels[node as usize], Product::new(RootTimestamp::new(u32::max_value()), Round::max_value()), node), 1));
+            }
+        }
+        graph.advance_to(2);
+        label.advance_to(2);
+        query.advance_to(2);
+        root.step_while(|| probe.lt(&query.time()));
+
+    }).unwrap();
+
+    let mut required = results.lock().unwrap().iter().filter(|x| *x.1 > 0).map(|x| *x.0).collect::<Vec<_>>();
+    required.sort();
+    required
+}